@@ -0,0 +1,931 @@
+use pinocchio::pubkey::Pubkey;
+
+use crate::{
+    instructions::{
+        find_associated_token_address, AddToWhitelist, BackfillLpMetadata, CreateDcaOrder, Deposit,
+        DepositSingle, FlashBorrow, GetAccounts, GetPoolInfo, IncreaseObservationCardinality,
+        InitializeConfig, LockLp, RebalanceSwap, RolloverEpochStats, Swap, SwapExactOut, UnlockLp,
+        Withdraw, WithdrawSingle, ADD_TO_WHITELIST_DATA_LEN, BACKFILL_LP_METADATA_DATA_LEN,
+        CREATE_DCA_ORDER_DATA_LEN, DEPOSIT_BOOTSTRAP_DATA_LEN, DEPOSIT_SINGLE_DATA_LEN,
+        FLASH_BORROW_DATA_LEN, GET_ACCOUNTS_DATA_LEN, GET_POOL_INFO_DATA_LEN,
+        INCREASE_OBSERVATION_CARDINALITY_DATA_LEN, INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN,
+        LOCK_LP_DATA_LEN, MAX_ACCOUNT_METAS, REBALANCE_SWAP_DATA_LEN,
+        ROLLOVER_EPOCH_STATS_DATA_LEN, SWAP_EXACT_OUT_DATA_LEN, SWAP_EXPECTED_POOL_DATA_LEN,
+        TOKEN_2022_PROGRAM_ID, UNLOCK_LP_DATA_LEN, WITHDRAW_SINGLE_DATA_LEN,
+        WITHDRAW_SLOT_DATA_LEN,
+    },
+    math::{mul_div_floor, Bps},
+    pdas::{pool_accounts_of, PoolAddresses},
+    state::{royalty_amount, CurveError, CurveType, PriceBand, BPS_DENOMINATOR},
+};
+
+// instruction data builders for clients and for programs calling this one over cpi.
+// each writes the discriminator followed by the payload its handler parses, into a
+// buffer sized by the same *_DATA_LEN constant the parser checks (the longest variant
+// for instructions with optional fields)
+pub struct InstructionData<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> InstructionData<N> {
+    fn new(discriminator: u8) -> Self {
+        let mut data = Self {
+            bytes: [0; N],
+            len: 0,
+        };
+        data.push(&[discriminator]);
+        data
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn push_bool(&mut self, value: bool) {
+        self.push(&[value as u8]);
+    }
+
+    fn push_u16(&mut self, value: u16) {
+        self.push(&value.to_le_bytes());
+    }
+
+    fn push_u64(&mut self, value: u64) {
+        self.push(&value.to_le_bytes());
+    }
+
+    fn push_i64(&mut self, value: i64) {
+        self.push(&value.to_le_bytes());
+    }
+
+    // the full instruction data, discriminator first
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    // what the handler's parser receives once the discriminator is split off
+    pub fn payload(&self) -> &[u8] {
+        &self.bytes[1..self.len]
+    }
+}
+
+// account entry laid out like solana_sdk's AccountMeta. keys are plain 32 byte
+// arrays, solana_sdk::pubkey::Pubkey::new_from_array takes them as they are
+#[derive(Clone, Copy)]
+pub struct AccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+// a whole instruction laid out like solana_sdk's Instruction, ready to be mapped
+// field for field by clients on the standard sdk. the account list lives in a
+// fixed buffer so the builders stay no_std
+pub struct Instruction<const N: usize> {
+    pub program_id: Pubkey,
+    accounts: [AccountMeta; MAX_ACCOUNT_METAS],
+    accounts_len: usize,
+    pub data: InstructionData<N>,
+}
+
+impl<const N: usize> Instruction<N> {
+    fn new(program_id: Pubkey, data: InstructionData<N>) -> Self {
+        Self {
+            program_id,
+            accounts: [AccountMeta {
+                pubkey: [0; 32],
+                is_signer: false,
+                is_writable: false,
+            }; MAX_ACCOUNT_METAS],
+            accounts_len: 0,
+            data,
+        }
+    }
+
+    // appends an account, the trailing accounts a pool needs on top of the fixed
+    // ones (observation buffer, royalty and launch accounts) are listed by
+    // GetAccounts and pushed here in its order
+    pub fn push_account(&mut self, pubkey: &Pubkey, is_signer: bool, is_writable: bool) {
+        self.accounts[self.accounts_len] = AccountMeta {
+            pubkey: *pubkey,
+            is_signer,
+            is_writable,
+        };
+        self.accounts_len += 1;
+    }
+
+    pub fn accounts(&self) -> &[AccountMeta] {
+        &self.accounts[..self.accounts_len]
+    }
+}
+
+// cluster the client talks to. it selects the program id the builders address and
+// derive pdas under, and what the cluster has deployed
+#[derive(Clone, Copy)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    // a local validator, with the program loaded under `program_id` and token-2022
+    // only when the validator was started with it
+    Localnet {
+        program_id: Pubkey,
+        token_2022: bool,
+    },
+}
+
+impl Cluster {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Self::Mainnet | Self::Devnet => crate::ID,
+            Self::Localnet { program_id, .. } => *program_id,
+        }
+    }
+
+    pub fn supports_token_2022(&self) -> bool {
+        match self {
+            Self::Mainnet | Self::Devnet => true,
+            Self::Localnet { token_2022, .. } => *token_2022,
+        }
+    }
+}
+
+// a pool's mints with the program owning each, and the addresses derived from them
+pub struct PoolKeys {
+    pub program_id: Pubkey,
+    pub mint_x: Pubkey,
+    pub mint_y: Pubkey,
+    pub token_program_x: Pubkey,
+    pub token_program_y: Pubkey,
+    pub addresses: PoolAddresses,
+}
+
+impl PoolKeys {
+    // None for a token-2022 mint on a cluster without the token-2022 program, no
+    // instruction built for the pool could land there
+    pub fn find(
+        cluster: &Cluster,
+        mint_x: &Pubkey,
+        mint_y: &Pubkey,
+        seed: u64,
+        token_program_x: &Pubkey,
+        token_program_y: &Pubkey,
+    ) -> Option<Self> {
+        let program_id = cluster.program_id();
+
+        let pool = Self {
+            program_id,
+            mint_x: *mint_x,
+            mint_y: *mint_y,
+            token_program_x: *token_program_x,
+            token_program_y: *token_program_y,
+            addresses: pool_accounts_of(
+                &program_id,
+                mint_x,
+                mint_y,
+                seed,
+                token_program_x,
+                token_program_y,
+            ),
+        };
+
+        if pool.uses_token_2022() && !cluster.supports_token_2022() {
+            return None;
+        }
+        Some(pool)
+    }
+
+    fn uses_token_2022(&self) -> bool {
+        self.token_program_x == TOKEN_2022_PROGRAM_ID
+            || self.token_program_y == TOKEN_2022_PROGRAM_ID
+    }
+}
+
+// fee tiers the router compares, in basis points. a tier pool is created with its
+// fee as the seed, so its addresses follow from the mints alone
+pub const FEE_TIERS: [u16; 2] = [5, 30];
+
+// the candidate pools of a pair, one per fee tier, in FEE_TIERS order. a client
+// fetches them to fill in the PoolState best_pool_for_pair compares
+pub fn fee_tier_pools(
+    cluster: &Cluster,
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    token_program_x: &Pubkey,
+    token_program_y: &Pubkey,
+) -> [Option<PoolKeys>; FEE_TIERS.len()] {
+    FEE_TIERS.map(|fee| {
+        PoolKeys::find(
+            cluster,
+            mint_x,
+            mint_y,
+            fee as u64,
+            token_program_x,
+            token_program_y,
+        )
+    })
+}
+
+// what a quote needs of a fetched pool: its config's curve_type, fee and
+// royalty_bps at the current time and slot, and its reserves, the vault balances
+// less the accrued protocol fees
+#[derive(Clone, Copy)]
+pub struct PoolState {
+    pub curve_type: CurveType,
+    pub fee: Bps,
+    pub royalty_bps: Bps,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+#[derive(Clone, Copy)]
+pub struct Quote {
+    // what the vault pays out, before any transfer fee of the output mint
+    pub amount_out: u64,
+    // how far the trade moves the spot price, in basis points of the price before
+    pub price_impact_bps: u64,
+}
+
+impl PoolState {
+    // the same math the swap runs: the royalty comes off the input, the rest is
+    // priced on the curve net of the fee
+    pub fn quote(&self, x_to_y: bool, amount_in: u64) -> Result<Quote, CurveError> {
+        let amount_in = amount_in
+            .checked_sub(royalty_amount(amount_in, self.royalty_bps)?)
+            .ok_or(CurveError::Overflow)?;
+
+        let (reserve_in, reserve_out) = if x_to_y {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        };
+        let amount_out =
+            self.curve_type
+                .swap_exact_in(reserve_in, reserve_out, amount_in, self.fee)?;
+
+        let reserve_in = reserve_in
+            .checked_add(amount_in)
+            .ok_or(CurveError::Overflow)?;
+        let reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .ok_or(CurveError::Overflow)?;
+        let (reserve_x_after, reserve_y_after) = if x_to_y {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+
+        let price_before = self.curve_type.spot_price(self.reserve_x, self.reserve_y)?;
+        let price_after = self
+            .curve_type
+            .spot_price(reserve_x_after, reserve_y_after)?;
+        let price_impact_bps = mul_div_floor(
+            price_before.abs_diff(price_after),
+            BPS_DENOMINATOR as u128,
+            price_before,
+        )
+        .and_then(|impact| u64::try_from(impact).ok())
+        .ok_or(CurveError::Overflow)?;
+
+        Ok(Quote {
+            amount_out,
+            price_impact_bps,
+        })
+    }
+}
+
+// the candidate paying the most for `amount_in` of `mints.0` in `mints.1`, with its
+// quote. candidates are the fetched fee_tier_pools, a pool of another pair or one
+// too shallow to quote the trade is skipped
+pub fn best_pool_for_pair<'a>(
+    mints: (&Pubkey, &Pubkey),
+    amount_in: u64,
+    candidates: &'a [(PoolKeys, PoolState)],
+) -> Option<(&'a PoolKeys, Quote)> {
+    let (mint_in, mint_out) = mints;
+
+    candidates
+        .iter()
+        .filter_map(|(pool, state)| {
+            let x_to_y = if (&pool.mint_x, &pool.mint_y) == (mint_in, mint_out) {
+                true
+            } else if (&pool.mint_y, &pool.mint_x) == (mint_in, mint_out) {
+                false
+            } else {
+                return None;
+            };
+
+            let quote = state.quote(x_to_y, amount_in).ok()?;
+            Some((pool, quote))
+        })
+        .max_by_key(|(_, quote)| quote.amount_out)
+}
+
+// a constant product pool is written without the curve fields unless a price band
+// follows them
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_config(
+    fee: u16,
+    config_bump: u8,
+    min_lp_mint: u64,
+    min_lp_burn: u64,
+    trading_start_ts: i64,
+    max_lp_supply: u64,
+    seed: u64,
+    curve_type: CurveType,
+    price_band: Option<PriceBand>,
+) -> InstructionData<{ 1 + INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN }> {
+    let mut data = InstructionData::new(*InitializeConfig::DISCRIMINATOR);
+    data.push_u16(fee);
+    data.push(&[config_bump]);
+    data.push_u64(min_lp_mint);
+    data.push_u64(min_lp_burn);
+    data.push_i64(trading_start_ts);
+    data.push_u64(max_lp_supply);
+    data.push_u64(seed);
+
+    if matches!(curve_type, CurveType::Stable { .. }) || price_band.is_some() {
+        data.push(&[curve_type.discriminator()]);
+        data.push_u64(curve_type.amp());
+    }
+
+    if let Some(price_band) = price_band {
+        data.push_u64(price_band.min_price);
+        data.push_u64(price_band.max_price);
+    }
+
+    data
+}
+
+// `bootstrap_price` is the (expected_price, tolerance_bps) guard of the first deposit
+pub fn deposit(
+    max_x: u64,
+    max_y: u64,
+    min_lp_amount: u64,
+    expiration: i64,
+    bootstrap_price: Option<(u64, u16)>,
+) -> InstructionData<{ 1 + DEPOSIT_BOOTSTRAP_DATA_LEN }> {
+    let mut data = InstructionData::new(*Deposit::DISCRIMINATOR);
+    data.push_u64(max_x);
+    data.push_u64(max_y);
+    data.push_u64(min_lp_amount);
+    data.push_i64(expiration);
+
+    if let Some((expected_price, tolerance_bps)) = bootstrap_price {
+        data.push_u64(expected_price);
+        data.push_u16(tolerance_bps);
+    }
+
+    data
+}
+
+// `slot_window` is the (recent_slot, max_slot_age) guard for durable nonce transactions,
+// the withdraw then also takes the SlotHashes sysvar as a trailing account
+pub fn withdraw(
+    amount: u64,
+    min_x: u64,
+    min_y: u64,
+    expiration: i64,
+    slot_window: Option<(u64, u64)>,
+) -> InstructionData<{ 1 + WITHDRAW_SLOT_DATA_LEN }> {
+    let mut data = InstructionData::new(*Withdraw::DISCRIMINATOR);
+    data.push_u64(amount);
+    data.push_u64(min_x);
+    data.push_u64(min_y);
+    data.push_i64(expiration);
+
+    if let Some((recent_slot, max_slot_age)) = slot_window {
+        data.push_u64(recent_slot);
+        data.push_u64(max_slot_age);
+    }
+
+    data
+}
+
+// `expected_pool` is the (fee, admin_nonce) the swap was quoted against
+pub fn swap(
+    amount_in: u64,
+    min_out: u64,
+    x_to_y: bool,
+    expiration: i64,
+    expected_pool: Option<(u16, u64)>,
+) -> InstructionData<{ 1 + SWAP_EXPECTED_POOL_DATA_LEN }> {
+    let mut data = InstructionData::new(*Swap::DISCRIMINATOR);
+    data.push_u64(amount_in);
+    data.push_u64(min_out);
+    data.push_bool(x_to_y);
+    data.push_i64(expiration);
+
+    if let Some((fee, admin_nonce)) = expected_pool {
+        data.push_u16(fee);
+        data.push_u64(admin_nonce);
+    }
+
+    data
+}
+
+pub fn get_accounts(
+    operation: u8,
+    user: &Pubkey,
+) -> InstructionData<{ 1 + GET_ACCOUNTS_DATA_LEN }> {
+    let mut data = InstructionData::new(*GetAccounts::DISCRIMINATOR);
+    data.push(&[operation]);
+    data.push(user);
+    data
+}
+
+pub fn swap_exact_out(
+    amount_out: u64,
+    max_in: u64,
+    x_to_y: bool,
+    expiration: i64,
+) -> InstructionData<{ 1 + SWAP_EXACT_OUT_DATA_LEN }> {
+    let mut data = InstructionData::new(*SwapExactOut::DISCRIMINATOR);
+    data.push_u64(amount_out);
+    data.push_u64(max_in);
+    data.push_bool(x_to_y);
+    data.push_i64(expiration);
+    data
+}
+
+// Swap or SwapExactOut on `pool` for `user`, with `data` from swap or
+// swap_exact_out. the user's atas are derived under the program owning each mint
+pub fn swap_instruction<const N: usize>(
+    pool: &PoolKeys,
+    user: &Pubkey,
+    data: InstructionData<N>,
+) -> Instruction<N> {
+    let mut instruction = Instruction::new(pool.program_id, data);
+
+    instruction.push_account(user, true, true);
+    instruction.push_account(&pool.mint_x, false, false);
+    instruction.push_account(&pool.mint_y, false, false);
+    instruction.push_account(&pool.addresses.lp_mint, false, false);
+    instruction.push_account(&pool.addresses.config, false, true);
+    instruction.push_account(&pool.addresses.vault_x, false, true);
+    instruction.push_account(&pool.addresses.vault_y, false, true);
+    instruction.push_account(
+        &find_associated_token_address(user, &pool.mint_x, &pool.token_program_x),
+        false,
+        true,
+    );
+    instruction.push_account(
+        &find_associated_token_address(user, &pool.mint_y, &pool.token_program_y),
+        false,
+        true,
+    );
+    instruction.push_account(&pinocchio_token::ID, false, false);
+
+    if pool.uses_token_2022() {
+        instruction.push_account(&TOKEN_2022_PROGRAM_ID, false, false);
+    }
+
+    instruction
+}
+
+pub fn create_dca_order(
+    seed: u64,
+    x_to_y: bool,
+    amount_per_tick: u64,
+    min_out_per_tick: u64,
+    tip_per_tick: u64,
+    interval: i64,
+    amount: u64,
+) -> InstructionData<{ 1 + CREATE_DCA_ORDER_DATA_LEN }> {
+    let mut data = InstructionData::new(*CreateDcaOrder::DISCRIMINATOR);
+    data.push_u64(seed);
+    data.push_bool(x_to_y);
+    data.push_u64(amount_per_tick);
+    data.push_u64(min_out_per_tick);
+    data.push_u64(tip_per_tick);
+    data.push_i64(interval);
+    data.push_u64(amount);
+    data
+}
+
+pub fn increase_observation_cardinality(
+    cardinality: u16,
+) -> InstructionData<{ 1 + INCREASE_OBSERVATION_CARDINALITY_DATA_LEN }> {
+    let mut data = InstructionData::new(*IncreaseObservationCardinality::DISCRIMINATOR);
+    data.push_u16(cardinality);
+    data
+}
+
+pub fn flash_borrow(amount: u64, x: bool) -> InstructionData<{ 1 + FLASH_BORROW_DATA_LEN }> {
+    let mut data = InstructionData::new(*FlashBorrow::DISCRIMINATOR);
+    data.push_u64(amount);
+    data.push_bool(x);
+    data
+}
+
+pub fn deposit_single(
+    amount: u64,
+    min_lp_amount: u64,
+    x: bool,
+    expiration: i64,
+) -> InstructionData<{ 1 + DEPOSIT_SINGLE_DATA_LEN }> {
+    let mut data = InstructionData::new(*DepositSingle::DISCRIMINATOR);
+    data.push_u64(amount);
+    data.push_u64(min_lp_amount);
+    data.push_bool(x);
+    data.push_i64(expiration);
+    data
+}
+
+pub fn withdraw_single(
+    amount: u64,
+    min_out: u64,
+    x: bool,
+    expiration: i64,
+) -> InstructionData<{ 1 + WITHDRAW_SINGLE_DATA_LEN }> {
+    let mut data = InstructionData::new(*WithdrawSingle::DISCRIMINATOR);
+    data.push_u64(amount);
+    data.push_u64(min_out);
+    data.push_bool(x);
+    data.push_i64(expiration);
+    data
+}
+
+pub fn add_to_whitelist() -> InstructionData<{ 1 + ADD_TO_WHITELIST_DATA_LEN }> {
+    InstructionData::new(*AddToWhitelist::DISCRIMINATOR)
+}
+
+pub fn get_pool_info() -> InstructionData<{ 1 + GET_POOL_INFO_DATA_LEN }> {
+    InstructionData::new(*GetPoolInfo::DISCRIMINATOR)
+}
+
+pub fn rollover_epoch_stats() -> InstructionData<{ 1 + ROLLOVER_EPOCH_STATS_DATA_LEN }> {
+    InstructionData::new(*RolloverEpochStats::DISCRIMINATOR)
+}
+
+pub fn lock_lp(
+    seed: u64,
+    amount: u64,
+    unlock_ts: i64,
+) -> InstructionData<{ 1 + LOCK_LP_DATA_LEN }> {
+    let mut data = InstructionData::new(*LockLp::DISCRIMINATOR);
+    data.push_u64(seed);
+    data.push_u64(amount);
+    data.push_i64(unlock_ts);
+    data
+}
+
+pub fn unlock_lp() -> InstructionData<{ 1 + UNLOCK_LP_DATA_LEN }> {
+    InstructionData::new(*UnlockLp::DISCRIMINATOR)
+}
+
+pub fn backfill_lp_metadata() -> InstructionData<{ 1 + BACKFILL_LP_METADATA_DATA_LEN }> {
+    InstructionData::new(*BackfillLpMetadata::DISCRIMINATOR)
+}
+
+pub fn rebalance_swap(
+    amount_in: u64,
+    min_out: u64,
+    protocol_fee: bool,
+) -> InstructionData<{ 1 + REBALANCE_SWAP_DATA_LEN }> {
+    let mut data = InstructionData::new(*RebalanceSwap::DISCRIMINATOR);
+    data.push_u64(amount_in);
+    data.push_u64(min_out);
+    data.push_bool(protocol_fee);
+    data
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::instructions::{
+        FlashBorrowInstruction, GetAccountsInstruction, IncreaseObservationCardinalityInstruction,
+        InitializeConfigInstruction, RebalanceSwapInstruction, DEPOSIT_DATA_LEN,
+        INITIALIZE_CONFIG_CURVE_DATA_LEN, INITIALIZE_CONFIG_DATA_LEN, SWAP_DATA_LEN,
+        WITHDRAW_DATA_LEN,
+    };
+
+    fn assert_len<const N: usize>(data: InstructionData<N>, discriminator: u8, len: usize) {
+        assert_eq!(data.as_bytes()[0], discriminator);
+        assert_eq!(data.payload().len(), len);
+    }
+
+    #[test]
+    fn builder_lengths_match_parser_constants() {
+        let band = PriceBand {
+            min_price: 1,
+            max_price: 2,
+        };
+        let stable = CurveType::Stable { amp: 100 };
+        let user = [7; 32];
+
+        assert_len(
+            initialize_config(30, 255, 1, 1, 0, 0, 0, CurveType::ConstantProduct, None),
+            *InitializeConfig::DISCRIMINATOR,
+            INITIALIZE_CONFIG_DATA_LEN,
+        );
+        assert_len(
+            initialize_config(30, 255, 1, 1, 0, 0, 0, stable, None),
+            *InitializeConfig::DISCRIMINATOR,
+            INITIALIZE_CONFIG_CURVE_DATA_LEN,
+        );
+        assert_len(
+            initialize_config(
+                30,
+                255,
+                1,
+                1,
+                0,
+                0,
+                0,
+                CurveType::ConstantProduct,
+                Some(band),
+            ),
+            *InitializeConfig::DISCRIMINATOR,
+            INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN,
+        );
+        assert_len(
+            deposit(1, 2, 3, 4, None),
+            *Deposit::DISCRIMINATOR,
+            DEPOSIT_DATA_LEN,
+        );
+        assert_len(
+            deposit(1, 2, 3, 4, Some((5, 6))),
+            *Deposit::DISCRIMINATOR,
+            DEPOSIT_BOOTSTRAP_DATA_LEN,
+        );
+        assert_len(
+            withdraw(1, 2, 3, 4, None),
+            *Withdraw::DISCRIMINATOR,
+            WITHDRAW_DATA_LEN,
+        );
+        assert_len(
+            withdraw(1, 2, 3, 4, Some((5, 6))),
+            *Withdraw::DISCRIMINATOR,
+            WITHDRAW_SLOT_DATA_LEN,
+        );
+        assert_len(
+            swap(1, 2, true, 3, None),
+            *Swap::DISCRIMINATOR,
+            SWAP_DATA_LEN,
+        );
+        assert_len(
+            swap(1, 2, true, 3, Some((30, 4))),
+            *Swap::DISCRIMINATOR,
+            SWAP_EXPECTED_POOL_DATA_LEN,
+        );
+        assert_len(
+            get_accounts(0, &user),
+            *GetAccounts::DISCRIMINATOR,
+            GET_ACCOUNTS_DATA_LEN,
+        );
+        assert_len(
+            swap_exact_out(1, 2, false, 3),
+            *SwapExactOut::DISCRIMINATOR,
+            SWAP_EXACT_OUT_DATA_LEN,
+        );
+        assert_len(
+            create_dca_order(1, true, 2, 3, 4, 5, 6),
+            *CreateDcaOrder::DISCRIMINATOR,
+            CREATE_DCA_ORDER_DATA_LEN,
+        );
+        assert_len(
+            increase_observation_cardinality(8),
+            *IncreaseObservationCardinality::DISCRIMINATOR,
+            INCREASE_OBSERVATION_CARDINALITY_DATA_LEN,
+        );
+        assert_len(
+            flash_borrow(1, true),
+            *FlashBorrow::DISCRIMINATOR,
+            FLASH_BORROW_DATA_LEN,
+        );
+        assert_len(
+            deposit_single(1, 2, true, 3),
+            *DepositSingle::DISCRIMINATOR,
+            DEPOSIT_SINGLE_DATA_LEN,
+        );
+        assert_len(
+            withdraw_single(1, 2, false, 3),
+            *WithdrawSingle::DISCRIMINATOR,
+            WITHDRAW_SINGLE_DATA_LEN,
+        );
+        assert_len(
+            add_to_whitelist(),
+            *AddToWhitelist::DISCRIMINATOR,
+            ADD_TO_WHITELIST_DATA_LEN,
+        );
+        assert_len(
+            get_pool_info(),
+            *GetPoolInfo::DISCRIMINATOR,
+            GET_POOL_INFO_DATA_LEN,
+        );
+        assert_len(
+            rollover_epoch_stats(),
+            *RolloverEpochStats::DISCRIMINATOR,
+            ROLLOVER_EPOCH_STATS_DATA_LEN,
+        );
+        assert_len(lock_lp(1, 2, 3), *LockLp::DISCRIMINATOR, LOCK_LP_DATA_LEN);
+        assert_len(unlock_lp(), *UnlockLp::DISCRIMINATOR, UNLOCK_LP_DATA_LEN);
+        assert_len(
+            backfill_lp_metadata(),
+            *BackfillLpMetadata::DISCRIMINATOR,
+            BACKFILL_LP_METADATA_DATA_LEN,
+        );
+        assert_len(
+            rebalance_swap(1, 2, true),
+            *RebalanceSwap::DISCRIMINATOR,
+            REBALANCE_SWAP_DATA_LEN,
+        );
+    }
+
+    // off-chain pinocchio has no pda derivation, it only exists as a runtime syscall
+    #[test]
+    #[cfg_attr(
+        not(target_os = "solana"),
+        ignore = "pda derivation needs the runtime syscall"
+    )]
+    fn swap_instruction_lists_the_swap_accounts() {
+        let (mint_x, mint_y, user) = ([1; 32], [2; 32], [7; 32]);
+        let pool = PoolKeys::find(
+            &Cluster::Devnet,
+            &mint_x,
+            &mint_y,
+            7,
+            &pinocchio_token::ID,
+            &TOKEN_2022_PROGRAM_ID,
+        )
+        .unwrap();
+
+        let instruction = swap_instruction(&pool, &user, swap(1, 2, true, 3, None));
+        let accounts = instruction.accounts();
+
+        assert_eq!(instruction.program_id, crate::ID);
+        assert_eq!(instruction.data.as_bytes()[0], *Swap::DISCRIMINATOR);
+        assert_eq!(accounts.len(), 11);
+        assert!(accounts[0].is_signer);
+        assert_eq!(accounts[4].pubkey, pool.addresses.config);
+        assert_eq!(
+            accounts[7].pubkey,
+            find_associated_token_address(&user, &mint_x, &pinocchio_token::ID)
+        );
+        assert_eq!(
+            accounts[8].pubkey,
+            find_associated_token_address(&user, &mint_y, &TOKEN_2022_PROGRAM_ID)
+        );
+        // a token-2022 mint puts its program last
+        assert_eq!(accounts[10].pubkey, TOKEN_2022_PROGRAM_ID);
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(target_os = "solana"),
+        ignore = "pda derivation needs the runtime syscall"
+    )]
+    fn localnet_pools_follow_the_cluster() {
+        let (mint_x, mint_y) = ([1; 32], [2; 32]);
+        let localnet = |token_2022| Cluster::Localnet {
+            program_id: [9; 32],
+            token_2022,
+        };
+
+        let local = PoolKeys::find(
+            &localnet(false),
+            &mint_x,
+            &mint_y,
+            7,
+            &pinocchio_token::ID,
+            &pinocchio_token::ID,
+        )
+        .unwrap();
+        let devnet = PoolKeys::find(
+            &Cluster::Devnet,
+            &mint_x,
+            &mint_y,
+            7,
+            &pinocchio_token::ID,
+            &pinocchio_token::ID,
+        )
+        .unwrap();
+
+        assert_eq!(local.program_id, [9; 32]);
+        assert_ne!(local.addresses.config, devnet.addresses.config);
+        assert_eq!(
+            swap_instruction(&local, &[7; 32], swap(1, 2, true, 3, None)).program_id,
+            [9; 32]
+        );
+
+        // token-2022 pools only where the program is deployed
+        for token_2022 in [false, true] {
+            let pool = PoolKeys::find(
+                &localnet(token_2022),
+                &mint_x,
+                &mint_y,
+                7,
+                &TOKEN_2022_PROGRAM_ID,
+                &pinocchio_token::ID,
+            );
+            assert_eq!(pool.is_some(), token_2022);
+        }
+    }
+
+    fn tier_pool(fee: u16, reserve_x: u64, reserve_y: u64) -> (PoolKeys, PoolState) {
+        let keys = PoolKeys {
+            program_id: crate::ID,
+            mint_x: [1; 32],
+            mint_y: [2; 32],
+            token_program_x: pinocchio_token::ID,
+            token_program_y: pinocchio_token::ID,
+            addresses: PoolAddresses {
+                config: [fee as u8; 32],
+                config_bump: 255,
+                lp_mint: [0; 32],
+                lp_bump: 255,
+                vault_x: [0; 32],
+                vault_y: [0; 32],
+            },
+        };
+        let state = PoolState {
+            curve_type: CurveType::ConstantProduct,
+            fee: Bps::new(fee),
+            royalty_bps: Bps::new(0),
+            reserve_x,
+            reserve_y,
+        };
+        (keys, state)
+    }
+
+    #[test]
+    fn routing_picks_the_tier_paying_the_most() {
+        let mints = (&[1; 32], &[2; 32]);
+
+        // equal depth, the cheaper tier wins
+        let pools = [
+            tier_pool(5, 1_000_000, 1_000_000),
+            tier_pool(30, 1_000_000, 1_000_000),
+        ];
+        let (pool, quote) = best_pool_for_pair(mints, 10_000, &pools).unwrap();
+        assert_eq!(pool.addresses.config, [5; 32]);
+        assert_eq!(quote.amount_out, 9_896);
+        assert!(quote.price_impact_bps > 0);
+
+        // a much deeper pool outweighs its higher fee on a large trade
+        let pools = [
+            tier_pool(5, 100_000, 100_000),
+            tier_pool(30, 10_000_000, 10_000_000),
+        ];
+        let (pool, quote) = best_pool_for_pair(mints, 10_000, &pools).unwrap();
+        assert_eq!(pool.addresses.config, [30; 32]);
+        assert!(quote.price_impact_bps < 100);
+
+        // the reverse direction is quoted y to x
+        let (_, reverse) = best_pool_for_pair((mints.1, mints.0), 10_000, &pools).unwrap();
+        assert_eq!(reverse.amount_out, quote.amount_out);
+
+        // another pair or an empty pool is no candidate
+        assert!(best_pool_for_pair((&[3; 32], &[2; 32]), 10_000, &pools).is_none());
+        assert!(best_pool_for_pair(mints, 10_000, &[tier_pool(5, 0, 0)]).is_none());
+    }
+
+    // parsers that don't read the clock take the builders' output back unchanged
+    #[test]
+    fn builders_round_trip_through_parsers() {
+        let band = PriceBand {
+            min_price: 10,
+            max_price: 20,
+        };
+        let data = initialize_config(
+            30,
+            254,
+            1,
+            2,
+            3,
+            4,
+            5,
+            CurveType::Stable { amp: 100 },
+            Some(band),
+        );
+        let parsed = InitializeConfigInstruction::try_from(data.payload()).unwrap();
+        assert_eq!(parsed.fee, 30);
+        assert_eq!(parsed.config_bump, 254);
+        assert_eq!(parsed.seed, 5);
+        assert_eq!(parsed.curve_type.amp(), 100);
+        assert_eq!(parsed.price_band.max_price, 20);
+
+        let user = [7; 32];
+        let data = get_accounts(2, &user);
+        let parsed = GetAccountsInstruction::try_from(data.payload()).unwrap();
+        assert_eq!(parsed.operation, 2);
+        assert_eq!(parsed.user, user);
+
+        let data = flash_borrow(9, true);
+        let parsed = FlashBorrowInstruction::try_from(data.payload()).unwrap();
+        assert_eq!(parsed.amount, 9);
+        assert!(parsed.x);
+
+        let data = increase_observation_cardinality(8);
+        let parsed = IncreaseObservationCardinalityInstruction::try_from(data.payload()).unwrap();
+        assert_eq!(parsed.cardinality, 8);
+
+        let data = rebalance_swap(5, 4, true);
+        let parsed = RebalanceSwapInstruction::try_from(data.payload()).unwrap();
+        assert_eq!(parsed.amount_in, 5);
+        assert_eq!(parsed.min_out, 4);
+        assert!(parsed.protocol_fee);
+    }
+}
@@ -1,9 +1,90 @@
-use pinocchio::{account_info::AccountInfo, pubkey::Pubkey, ProgramResult};
+// the runtime entrypoint deserializes raw input, the macros below expand to
+// unsafe code even when the rest of the crate is built with safe-deser
+#![allow(unsafe_code)]
+
+use pinocchio::{
+    account_info::AccountInfo, no_allocator, nostd_panic_handler, program_entrypoint,
+    program_error::ProgramError, pubkey::Pubkey, ProgramResult,
+};
+
+use crate::instructions::{
+    AddToWhitelist, Admin, BackfillLpMetadata, CancelDcaOrder, CloseConfig, CollectProtocolFees,
+    CreateDcaOrder, Deposit, DepositSingle, ExecuteDcaTick, FlashBorrow, FlashRepay, GetAccounts,
+    GetPoolInfo, IncreaseObservationCardinality, InitializeConfig, LockLp, RebalanceSwap,
+    RolloverEpochStats, Swap, SwapExactOut, UnlockLp, Withdraw, WithdrawSingle,
+};
+
+program_entrypoint!(process_instruction);
+no_allocator!();
+nostd_panic_handler!();
 
 pub fn process_instruction(
     _program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    Ok(())
+    // the first byte selects the instruction, the rest is its payload
+    match instruction_data.split_first() {
+        Some((InitializeConfig::DISCRIMINATOR, data)) => {
+            InitializeConfig::try_from((accounts, data))?.process()
+        }
+        Some((Deposit::DISCRIMINATOR, data)) => Deposit::try_from((accounts, data))?.process(),
+        Some((Withdraw::DISCRIMINATOR, data)) => Withdraw::try_from((accounts, data))?.process(),
+        Some((Swap::DISCRIMINATOR, data)) => Swap::try_from((accounts, data))?.process(),
+        Some((Admin::DISCRIMINATOR, data)) => Admin::try_from((accounts, data))?.process(),
+        Some((GetAccounts::DISCRIMINATOR, data)) => {
+            GetAccounts::try_from((accounts, data))?.process()
+        }
+        Some((SwapExactOut::DISCRIMINATOR, data)) => {
+            SwapExactOut::try_from((accounts, data))?.process()
+        }
+        Some((CollectProtocolFees::DISCRIMINATOR, data)) => {
+            CollectProtocolFees::try_from((accounts, data))?.process()
+        }
+        Some((CreateDcaOrder::DISCRIMINATOR, data)) => {
+            CreateDcaOrder::try_from((accounts, data))?.process()
+        }
+        Some((ExecuteDcaTick::DISCRIMINATOR, data)) => {
+            ExecuteDcaTick::try_from((accounts, data))?.process()
+        }
+        Some((CancelDcaOrder::DISCRIMINATOR, data)) => {
+            CancelDcaOrder::try_from((accounts, data))?.process()
+        }
+        Some((CloseConfig::DISCRIMINATOR, data)) => {
+            CloseConfig::try_from((accounts, data))?.process()
+        }
+        Some((IncreaseObservationCardinality::DISCRIMINATOR, data)) => {
+            IncreaseObservationCardinality::try_from((accounts, data))?.process()
+        }
+        Some((FlashBorrow::DISCRIMINATOR, data)) => {
+            FlashBorrow::try_from((accounts, data))?.process()
+        }
+        Some((FlashRepay::DISCRIMINATOR, data)) => {
+            FlashRepay::try_from((accounts, data))?.process()
+        }
+        Some((DepositSingle::DISCRIMINATOR, data)) => {
+            DepositSingle::try_from((accounts, data))?.process()
+        }
+        Some((WithdrawSingle::DISCRIMINATOR, data)) => {
+            WithdrawSingle::try_from((accounts, data))?.process()
+        }
+        Some((AddToWhitelist::DISCRIMINATOR, data)) => {
+            AddToWhitelist::try_from((accounts, data))?.process()
+        }
+        Some((GetPoolInfo::DISCRIMINATOR, data)) => {
+            GetPoolInfo::try_from((accounts, data))?.process()
+        }
+        Some((RolloverEpochStats::DISCRIMINATOR, data)) => {
+            RolloverEpochStats::try_from((accounts, data))?.process()
+        }
+        Some((LockLp::DISCRIMINATOR, data)) => LockLp::try_from((accounts, data))?.process(),
+        Some((UnlockLp::DISCRIMINATOR, data)) => UnlockLp::try_from((accounts, data))?.process(),
+        Some((BackfillLpMetadata::DISCRIMINATOR, data)) => {
+            BackfillLpMetadata::try_from((accounts, data))?.process()
+        }
+        Some((RebalanceSwap::DISCRIMINATOR, data)) => {
+            RebalanceSwap::try_from((accounts, data))?.process()
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
 }
@@ -1,21 +1,92 @@
 use pinocchio::program_error::ProgramError;
 
+use crate::state::CurveError;
+
 impl From<PinocchioError> for ProgramError {
     fn from(e: PinocchioError) -> Self {
         ProgramError::Custom(e as u32)
     }
 }
 
+// the curve math has its own error type so it stays free of program types,
+// each variant surfaces as the matching code in the curve range
+impl From<CurveError> for ProgramError {
+    fn from(e: CurveError) -> Self {
+        match e {
+            CurveError::Overflow => PinocchioError::MathOverflow,
+            CurveError::InvalidSupply => PinocchioError::InvalidMintSupply,
+            CurveError::ZeroAmount => PinocchioError::InvalidAmount,
+            CurveError::InsufficientLiquidity => PinocchioError::InsufficientLiquidity,
+            CurveError::InvalidFee => PinocchioError::InvalidFee,
+            CurveError::InvalidCurve => PinocchioError::InvalidCurve,
+            CurveError::NotConverged => PinocchioError::CurveNotConverged,
+            CurveError::InvalidPriceBand => PinocchioError::InvalidPriceBand,
+            CurveError::PriceOutOfBand => PinocchioError::PriceOutOfBand,
+            CurveError::InvalidAmpRamp => PinocchioError::InvalidAmpRamp,
+        }
+        .into()
+    }
+}
+
+// codes are grouped into fixed ranges so new variants never shift existing ones,
+// always append to the end of a range
 pub enum PinocchioError {
-    IdenticalTokenMints = 0x0,
-    InvalidMintAmount = 0x1,
-    InvalidOwner = 0x2,
-    MathOverflow = 0x3,
-    InvalidMintSupply = 0x4,
-    InvalidAmount = 0x5,
-    SlipageExceeded = 0x6,
-    LessThanMinimum = 0x7,
-    Expired = 0x8,
+    // account and instruction validation, 1000+
+    IdenticalTokenMints = 1000,
+    InvalidMintAmount = 1001,
+    InvalidOwner = 1002,
+    LessThanMinimum = 1003,
+    Expired = 1004,
+    NotRentExempt = 1005,
+    DuplicateAccount = 1006,
+    CpiNotAllowed = 1007,
+    LpSupplyCapExceeded = 1008,
+    VaultFrozen = 1009,
+    TradingNotStarted = 1010,
+    PoolDisabled = 1011,
+    PoolWithdrawOnly = 1012,
+    BootstrapPriceMismatch = 1013,
+    SwappedMints = 1014,
+    DcaNotDue = 1015,
+    PoolNotEmpty = 1016,
+    FlashLoanActive = 1017,
+    FlashRepayMissing = 1018,
+    FlashLoanNotRepaid = 1019,
+    DepositPermitMissing = 1020,
+    ObservationsMissing = 1021,
+    EpochNotEnded = 1022,
+    LpStillLocked = 1023,
+    StalePoolQuote = 1024,
+    LaunchGuardActive = 1025,
+    LaunchBuysMissing = 1026,
+    LaunchBuyCapExceeded = 1027,
+    LaunchSlotCapExceeded = 1028,
+    RoyaltyAccountMissing = 1029,
+    RoyaltyNotSupported = 1030,
+    UnknownRecentSlot = 1031,
+
+    // curve and math, 2000+
+    MathOverflow = 2000,
+    InvalidMintSupply = 2001,
+    InvalidAmount = 2002,
+    SlipageExceeded = 2003,
+    InsufficientLiquidity = 2004,
+    InvalidFee = 2005,
+    InvalidCurve = 2006,
+    CurveNotConverged = 2007,
+    InvalidPriceBand = 2008,
+    PriceOutOfBand = 2009,
+    InvalidAmpRamp = 2010,
+
+    // admin operations, 3000+
+    AuthorityRenounced = 3000,
+    InvalidAuthority = 3001,
+    InvalidTradingStart = 3002,
+    InvalidLpSupplyCap = 3003,
+    StaleAdminNonce = 3004,
+    PoolNotPermissioned = 3005,
+    FeeChangeRateLimited = 3006,
+    LaunchAlreadyStarted = 3007,
 }
 
 impl PinocchioError {
@@ -30,8 +101,170 @@ impl PinocchioError {
             PinocchioError::InvalidMintSupply => "Invalid Mint Supply",
             PinocchioError::InvalidAmount => "Invalid Amount",
             PinocchioError::SlipageExceeded => "Slippage Exceeded",
+            PinocchioError::InsufficientLiquidity => "Pool has no liquidity to swap against",
+            PinocchioError::InvalidFee => "Fee must be below 10000 basis points",
+            PinocchioError::InvalidCurve => "Unknown curve type or amplification out of range",
+            PinocchioError::CurveNotConverged => "Stable swap invariant did not converge",
+            PinocchioError::InvalidPriceBand => "Price band minimum is above its maximum",
+            PinocchioError::PriceOutOfBand => "Swap would move the price outside the pool band",
+            PinocchioError::InvalidAmpRamp => {
+                "Amp ramp is too short or changes amp by more than 10x"
+            }
             PinocchioError::LessThanMinimum => "Amount is less than minimum",
-            PinocchioError::Expired => "Withdrawal expired",
+            PinocchioError::Expired => "Transaction expired",
+            PinocchioError::AuthorityRenounced => "Pool authority has been renounced",
+            PinocchioError::InvalidAuthority => "Signer is not the pool authority",
+            PinocchioError::InvalidTradingStart => "Trading start can only be moved earlier",
+            PinocchioError::NotRentExempt => "Pool account is not rent exempt",
+            PinocchioError::DuplicateAccount => "Same account passed for multiple roles",
+            PinocchioError::CpiNotAllowed => "Pool only accepts top level instructions",
+            PinocchioError::LpSupplyCapExceeded => "Deposit would exceed the LP supply cap",
+            PinocchioError::VaultFrozen => "Pool vault is frozen",
+            PinocchioError::TradingNotStarted => "Trading has not started for this pool",
+            PinocchioError::PoolDisabled => "Pool is paused",
+            PinocchioError::PoolWithdrawOnly => "Pool only accepts withdrawals",
+            PinocchioError::BootstrapPriceMismatch => {
+                "First deposit ratio is too far from the expected price"
+            }
+            PinocchioError::SwappedMints => "Mint accounts are passed in reverse order",
+            PinocchioError::DcaNotDue => "DCA order is not due for its next tick",
+            PinocchioError::PoolNotEmpty => "Pool still holds liquidity",
+            PinocchioError::FlashLoanActive => "Pool has an outstanding flash loan",
+            PinocchioError::FlashRepayMissing => "Flash borrow has no matching repay",
+            PinocchioError::FlashLoanNotRepaid => "Flash loan was not repaid in full",
+            PinocchioError::DepositPermitMissing => {
+                "Pool is permissioned and the depositor has no deposit permit"
+            }
+            PinocchioError::ObservationsMissing => {
+                "Pool records observations and the buffer was not passed"
+            }
+            PinocchioError::EpochNotEnded => "Epoch stats bucket is still open",
+            PinocchioError::LpStillLocked => "LP lock has not reached its unlock time",
+            PinocchioError::StalePoolQuote => {
+                "Pool fee or version changed since the swap was quoted"
+            }
+            PinocchioError::LaunchGuardActive => {
+                "Pool is in its launch window and only takes buys of the launched token"
+            }
+            PinocchioError::LaunchBuysMissing => {
+                "Pool is in its launch window and the buyer's launch account was not passed"
+            }
+            PinocchioError::LaunchBuyCapExceeded => {
+                "Buy would take the wallet past the launch window cap"
+            }
+            PinocchioError::LaunchSlotCapExceeded => {
+                "Buy would take the slot past the launch window cap"
+            }
+            PinocchioError::RoyaltyAccountMissing => {
+                "Pool charges a royalty and the destination's token account was not passed"
+            }
+            PinocchioError::RoyaltyNotSupported => {
+                "Pool charges a royalty, which only Swap and SwapExactOut pay"
+            }
+            PinocchioError::UnknownRecentSlot => {
+                "Recent slot has no entry in the SlotHashes sysvar"
+            }
+            PinocchioError::InvalidLpSupplyCap => {
+                "LP supply cap cannot be below the current supply"
+            }
+            PinocchioError::StaleAdminNonce => "Admin nonce does not match the pool",
+            PinocchioError::PoolNotPermissioned => "Pool has no whitelist authority",
+            PinocchioError::FeeChangeRateLimited => {
+                "Fee change is too large or too soon after the last one"
+            }
+            PinocchioError::LaunchAlreadyStarted => {
+                "Launch guard can only be set before trading starts"
+            }
+        }
+    }
+}
+
+// monitoring alerts on these exact codes, changing one fails the build
+const _: () = {
+    assert!(PinocchioError::IdenticalTokenMints as u32 == 1000);
+    assert!(PinocchioError::InvalidMintAmount as u32 == 1001);
+    assert!(PinocchioError::InvalidOwner as u32 == 1002);
+    assert!(PinocchioError::LessThanMinimum as u32 == 1003);
+    assert!(PinocchioError::Expired as u32 == 1004);
+    assert!(PinocchioError::NotRentExempt as u32 == 1005);
+    assert!(PinocchioError::DuplicateAccount as u32 == 1006);
+    assert!(PinocchioError::CpiNotAllowed as u32 == 1007);
+    assert!(PinocchioError::LpSupplyCapExceeded as u32 == 1008);
+    assert!(PinocchioError::VaultFrozen as u32 == 1009);
+    assert!(PinocchioError::TradingNotStarted as u32 == 1010);
+    assert!(PinocchioError::PoolDisabled as u32 == 1011);
+    assert!(PinocchioError::PoolWithdrawOnly as u32 == 1012);
+    assert!(PinocchioError::BootstrapPriceMismatch as u32 == 1013);
+    assert!(PinocchioError::SwappedMints as u32 == 1014);
+    assert!(PinocchioError::DcaNotDue as u32 == 1015);
+    assert!(PinocchioError::PoolNotEmpty as u32 == 1016);
+    assert!(PinocchioError::FlashLoanActive as u32 == 1017);
+    assert!(PinocchioError::FlashRepayMissing as u32 == 1018);
+    assert!(PinocchioError::FlashLoanNotRepaid as u32 == 1019);
+    assert!(PinocchioError::DepositPermitMissing as u32 == 1020);
+    assert!(PinocchioError::ObservationsMissing as u32 == 1021);
+    assert!(PinocchioError::EpochNotEnded as u32 == 1022);
+    assert!(PinocchioError::LpStillLocked as u32 == 1023);
+    assert!(PinocchioError::StalePoolQuote as u32 == 1024);
+    assert!(PinocchioError::LaunchGuardActive as u32 == 1025);
+    assert!(PinocchioError::LaunchBuysMissing as u32 == 1026);
+    assert!(PinocchioError::LaunchBuyCapExceeded as u32 == 1027);
+    assert!(PinocchioError::LaunchSlotCapExceeded as u32 == 1028);
+    assert!(PinocchioError::RoyaltyAccountMissing as u32 == 1029);
+    assert!(PinocchioError::RoyaltyNotSupported as u32 == 1030);
+    assert!(PinocchioError::UnknownRecentSlot as u32 == 1031);
+
+    assert!(PinocchioError::MathOverflow as u32 == 2000);
+    assert!(PinocchioError::InvalidMintSupply as u32 == 2001);
+    assert!(PinocchioError::InvalidAmount as u32 == 2002);
+    assert!(PinocchioError::SlipageExceeded as u32 == 2003);
+    assert!(PinocchioError::InsufficientLiquidity as u32 == 2004);
+    assert!(PinocchioError::InvalidFee as u32 == 2005);
+    assert!(PinocchioError::InvalidCurve as u32 == 2006);
+    assert!(PinocchioError::CurveNotConverged as u32 == 2007);
+    assert!(PinocchioError::InvalidPriceBand as u32 == 2008);
+    assert!(PinocchioError::PriceOutOfBand as u32 == 2009);
+    assert!(PinocchioError::InvalidAmpRamp as u32 == 2010);
+
+    assert!(PinocchioError::AuthorityRenounced as u32 == 3000);
+    assert!(PinocchioError::InvalidAuthority as u32 == 3001);
+    assert!(PinocchioError::InvalidTradingStart as u32 == 3002);
+    assert!(PinocchioError::InvalidLpSupplyCap as u32 == 3003);
+    assert!(PinocchioError::StaleAdminNonce as u32 == 3004);
+    assert!(PinocchioError::PoolNotPermissioned as u32 == 3005);
+    assert!(PinocchioError::FeeChangeRateLimited as u32 == 3006);
+    assert!(PinocchioError::LaunchAlreadyStarted as u32 == 3007);
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the const asserts above pin the discriminants, this pins what the program
+    // actually returns through both conversion layers
+    #[test]
+    fn error_codes_are_stable() {
+        let codes: [(ProgramError, u32); 16] = [
+            (PinocchioError::IdenticalTokenMints.into(), 1000),
+            (PinocchioError::LpStillLocked.into(), 1023),
+            (PinocchioError::MathOverflow.into(), 2000),
+            (PinocchioError::PriceOutOfBand.into(), 2009),
+            (PinocchioError::AuthorityRenounced.into(), 3000),
+            (PinocchioError::FeeChangeRateLimited.into(), 3006),
+            (CurveError::Overflow.into(), 2000),
+            (CurveError::InvalidSupply.into(), 2001),
+            (CurveError::ZeroAmount.into(), 2002),
+            (CurveError::InsufficientLiquidity.into(), 2004),
+            (CurveError::InvalidFee.into(), 2005),
+            (CurveError::InvalidCurve.into(), 2006),
+            (CurveError::NotConverged.into(), 2007),
+            (CurveError::InvalidPriceBand.into(), 2008),
+            (CurveError::PriceOutOfBand.into(), 2009),
+            (CurveError::InvalidAmpRamp.into(), 2010),
+        ];
+
+        for (error, code) in codes {
+            assert_eq!(error, ProgramError::Custom(code));
         }
     }
 }
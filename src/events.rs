@@ -0,0 +1,186 @@
+use pinocchio::{cpi::set_return_data, log::sol_log_data, pubkey::Pubkey};
+
+// events are logged with sol_log_data as a single little endian buffer,
+// discriminator (1) | pool config (32) | event_seq (8) | event fields. event_seq is
+// taken from Config::next_event_seq, it has no gaps within a pool so an indexer can
+// tell a missed or reordered log from the sequence alone
+pub const EVENT_HEADER_LEN: usize = 1 + 32 + 8;
+
+// fixed capacity event buffer, the program has no allocator
+struct EventData<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> EventData<N> {
+    fn empty() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    fn new(discriminator: u8, config: &Pubkey, event_seq: u64) -> Self {
+        let mut data = Self::empty();
+        data.push(&[discriminator]);
+        data.push(config);
+        data.push_u64(event_seq);
+        data
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.bytes[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+
+    fn push_u64(&mut self, value: u64) {
+        self.push(&value.to_le_bytes());
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    fn emit(&self) {
+        sol_log_data(&[self.as_bytes()]);
+    }
+}
+
+// a trade against the pool, from Swap, SwapExactOut and ExecuteDcaTick. amounts
+// are what the vaults received and sent
+pub struct SwapEvent {
+    pub user: Pubkey,
+    pub x_to_y: bool,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+impl SwapEvent {
+    pub const DISCRIMINATOR: u8 = 0;
+    pub const LEN: usize = EVENT_HEADER_LEN + 32 + 1 + 8 + 8;
+
+    pub fn emit(&self, config: &Pubkey, event_seq: u64) {
+        let mut data = EventData::<{ Self::LEN }>::new(Self::DISCRIMINATOR, config, event_seq);
+        data.push(&self.user);
+        data.push(&[self.x_to_y as u8]);
+        data.push_u64(self.amount_in);
+        data.push_u64(self.amount_out);
+        data.emit();
+    }
+}
+
+// liquidity added by Deposit or DepositSingle, amounts are what the vaults received
+pub struct DepositEvent {
+    pub user: Pubkey,
+    pub amount_x: u64,
+    pub amount_y: u64,
+    pub lp_amount: u64,
+}
+
+impl DepositEvent {
+    pub const DISCRIMINATOR: u8 = 1;
+    pub const LEN: usize = EVENT_HEADER_LEN + 32 + 8 + 8 + 8;
+
+    pub fn emit(&self, config: &Pubkey, event_seq: u64) {
+        let mut data = EventData::<{ Self::LEN }>::new(Self::DISCRIMINATOR, config, event_seq);
+        data.push(&self.user);
+        data.push_u64(self.amount_x);
+        data.push_u64(self.amount_y);
+        data.push_u64(self.lp_amount);
+        data.emit();
+    }
+}
+
+// liquidity removed by Withdraw or WithdrawSingle. gross is the share of the reserves
+// the burned lp claimed, fee what was deducted from it (the swap fee on the side
+// WithdrawSingle swaps, token-2022 transfer fees on the way out) and net what reached
+// the user. on a WithdrawSingle the swapped side nets 0, it arrives in the other token
+pub struct WithdrawEvent {
+    pub user: Pubkey,
+    pub lp_amount: u64,
+    pub gross_x: u64,
+    pub gross_y: u64,
+    pub fee_x: u64,
+    pub fee_y: u64,
+    pub net_x: u64,
+    pub net_y: u64,
+}
+
+impl WithdrawEvent {
+    pub const DISCRIMINATOR: u8 = 2;
+    pub const LEN: usize = EVENT_HEADER_LEN + 32 + 8 * 7;
+    // the receipt returned to the caller, the event fields after the user
+    pub const RETURN_DATA_LEN: usize = 8 * 7;
+
+    pub fn emit(&self, config: &Pubkey, event_seq: u64) {
+        let mut data = EventData::<{ Self::LEN }>::new(Self::DISCRIMINATOR, config, event_seq);
+        data.push(&self.user);
+        self.push_amounts(&mut data);
+        data.emit();
+    }
+
+    // lp_amount | gross_x | gross_y | fee_x | fee_y | net_x | net_y as the
+    // instruction's return data, so wallets get the receipt without parsing logs
+    pub fn set_return_data(&self) {
+        let mut data = EventData::<{ Self::RETURN_DATA_LEN }>::empty();
+        self.push_amounts(&mut data);
+        set_return_data(data.as_bytes());
+    }
+
+    fn push_amounts<const N: usize>(&self, data: &mut EventData<N>) {
+        data.push_u64(self.lp_amount);
+        data.push_u64(self.gross_x);
+        data.push_u64(self.gross_y);
+        data.push_u64(self.fee_x);
+        data.push_u64(self.fee_y);
+        data.push_u64(self.net_x);
+        data.push_u64(self.net_y);
+    }
+}
+
+// an epoch bucket closed by RolloverEpochStats, covering `epoch` up to `end_epoch`
+pub struct EpochStatsEvent {
+    pub epoch: u64,
+    pub end_epoch: u64,
+    pub volume_x: u64,
+    pub volume_y: u64,
+    pub fees_x: u64,
+    pub fees_y: u64,
+}
+
+impl EpochStatsEvent {
+    pub const DISCRIMINATOR: u8 = 3;
+    pub const LEN: usize = EVENT_HEADER_LEN + 8 * 6;
+
+    pub fn emit(&self, config: &Pubkey, event_seq: u64) {
+        let mut data = EventData::<{ Self::LEN }>::new(Self::DISCRIMINATOR, config, event_seq);
+        data.push_u64(self.epoch);
+        data.push_u64(self.end_epoch);
+        data.push_u64(self.volume_x);
+        data.push_u64(self.volume_y);
+        data.push_u64(self.fees_x);
+        data.push_u64(self.fees_y);
+        data.emit();
+    }
+}
+
+// creator royalty paid out of a swap's input, in the input token, to a token
+// account of the pool's royalty destination
+pub struct RoyaltyEvent {
+    pub user: Pubkey,
+    pub x_to_y: bool,
+    pub amount: u64,
+}
+
+impl RoyaltyEvent {
+    pub const DISCRIMINATOR: u8 = 4;
+    pub const LEN: usize = EVENT_HEADER_LEN + 32 + 1 + 8;
+
+    pub fn emit(&self, config: &Pubkey, event_seq: u64) {
+        let mut data = EventData::<{ Self::LEN }>::new(Self::DISCRIMINATOR, config, event_seq);
+        data.push(&self.user);
+        data.push(&[self.x_to_y as u8]);
+        data.push_u64(self.amount);
+        data.emit();
+    }
+}
@@ -0,0 +1,113 @@
+use pinocchio::{
+    account_info::AccountInfo, instruction::Seed, program_error::ProgramError, ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, AccountCheck, ProgramAccount, ProgramAccountInit, SignerAccount,
+        WritableAccount,
+    },
+    pdas::{deposit_permit_address, DEPOSIT_PERMIT_SEED},
+    state::{Config, DepositPermit},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct AddToWhitelistAccounts<'a> {
+    // the pool's whitelist authority, pays the permit rent
+    pub whitelist_authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    // wallet allowed to deposit, doesn't have to sign
+    pub user: &'a AccountInfo,
+    pub permit: &'a AccountInfo,
+
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AddToWhitelistAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [whitelist_authority, config, user, permit, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(whitelist_authority)?;
+        WritableAccount::check(whitelist_authority)?;
+        WritableAccount::check(permit)?;
+
+        assert_unique_accounts(&[whitelist_authority, config, user, permit])?;
+
+        Ok(Self {
+            whitelist_authority,
+            config,
+            user,
+            permit,
+            system_program,
+        })
+    }
+}
+
+pub const ADD_TO_WHITELIST_DATA_LEN: usize = 0;
+
+pub struct AddToWhitelist<'a> {
+    pub accounts: AddToWhitelistAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for AddToWhitelist<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if data.len() != ADD_TO_WHITELIST_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = AddToWhitelistAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> AddToWhitelist<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &17;
+
+    // issues `user` a deposit permit for a permissioned pool
+    pub fn process(&self) -> ProgramResult {
+        {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.require_whitelist_authority(self.accounts.whitelist_authority)?;
+        }
+
+        // searched for once here, the permit stores the canonical bump so deposits
+        // can re-derive its address. the system program rejects the create if the
+        // user already holds a permit
+        let (permit, permit_bump) =
+            deposit_permit_address(self.accounts.config.key(), self.accounts.user.key());
+        if permit.ne(self.accounts.permit.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let bump = [permit_bump];
+        let permit_seeds = [
+            Seed::from(DEPOSIT_PERMIT_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(self.accounts.user.key().as_ref()),
+            Seed::from(&bump),
+        ];
+
+        ProgramAccount::init::<DepositPermit>(
+            self.accounts.whitelist_authority,
+            self.accounts.permit,
+            &permit_seeds,
+            DepositPermit::LEN,
+        )?;
+
+        DepositPermit::load_mut(self.accounts.permit)?.set_inner(
+            *self.accounts.config.key(),
+            *self.accounts.user.key(),
+            permit_bump,
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_unique_accounts, read_bool, read_i64, read_pubkey, read_u16, read_u64, AccountCheck,
+        MintData, SignerAccount, WritableAccount,
+    },
+    state::{
+        AmmState, Config, LaunchGuard, PriceBand, BPS_DENOMINATOR, MAX_FEE_BPS,
+        MAX_LAUNCH_WINDOW_SECS, MAX_ROYALTY_BPS,
+    },
+};
+
+// accounts are expected in the same order as the fields below, ops that need
+// more accounts take them from `remaining`
+pub struct AdminAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub remaining: &'a [AccountInfo],
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for AdminAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, remaining @ ..] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        assert_unique_accounts(&[authority, config])?;
+
+        SignerAccount::check(authority)?;
+        WritableAccount::check(config)?;
+
+        Ok(Self {
+            authority,
+            config,
+            remaining,
+        })
+    }
+}
+
+// admin sub operations, selected by the byte following the admin nonce
+pub enum AdminOp {
+    UpdateTradingStart { trading_start_ts: i64 },
+    SetNoCpi { no_cpi: bool },
+    SetMaxLpSupply { max_lp_supply: u64 },
+    // takes effect after ADMIN_TIMELOCK_SLOTS
+    SetPriceBand { price_band: PriceBand },
+    // lifecycle switches, see Config::require_active / require_withdrawable
+    Pause,
+    Unpause,
+    SetWithdrawOnly,
+    // share of each swap fee kept for the protocol, in basis points of the fee
+    SetProtocolFee { protocol_fee_bps: u16 },
+    // share of each swap fee paid to the referrer a swap names, in basis points of the fee
+    SetReferralFee { referral_fee_bps: u16 },
+    // takes effect after ADMIN_TIMELOCK_SLOTS so lps can exit ahead of a fee hike,
+    // and moves the fee by at most the pool's max fee change per timelock period
+    UpdateFee { fee: u16 },
+    // makes the pool permissioned, the default key opens it again
+    SetWhitelistAuthority { whitelist_authority: Pubkey },
+    // moves a stable pool's amp linearly to `future_amp` by `ramp_end_ts`, see
+    // Config::schedule_amp_ramp
+    RampAmp { future_amp: u64, ramp_end_ts: i64 },
+    // buy-only window of `window` seconds after the trading start, see
+    // Config::set_launch_guard. a window of 0 removes it
+    SetLaunchGuard { window: i64, guard: LaunchGuard },
+    // creator royalty of `bps` on every swap input, paid to token accounts of
+    // `destination`. 0 bps stops charging it
+    SetRoyalty { bps: u16, destination: Pubkey },
+    // most a single UpdateFee may move the fee from now on, 0 freezes it
+    SetMaxFeeChange { max_fee_change_bps: u16 },
+}
+
+impl AdminOp {
+    pub const UPDATE_TRADING_START: u8 = 0;
+    pub const SET_NO_CPI: u8 = 1;
+    pub const SET_MAX_LP_SUPPLY: u8 = 2;
+    pub const SET_PRICE_BAND: u8 = 3;
+    pub const PAUSE: u8 = 4;
+    pub const UNPAUSE: u8 = 5;
+    pub const SET_WITHDRAW_ONLY: u8 = 6;
+    pub const SET_PROTOCOL_FEE: u8 = 7;
+    pub const UPDATE_FEE: u8 = 8;
+    pub const SET_REFERRAL_FEE: u8 = 9;
+    pub const SET_WHITELIST_AUTHORITY: u8 = 10;
+    pub const RAMP_AMP: u8 = 11;
+    pub const SET_LAUNCH_GUARD: u8 = 12;
+    pub const SET_ROYALTY: u8 = 13;
+    pub const SET_MAX_FEE_CHANGE: u8 = 14;
+}
+
+impl<'a> TryFrom<&'a [u8]> for AdminOp {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        let Some((op, data)) = data.split_first() else {
+            return Err(ProgramError::InvalidInstructionData);
+        };
+
+        match *op {
+            Self::UPDATE_TRADING_START => {
+                if data.len() != size_of::<i64>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let trading_start_ts = read_i64(data, 0)?;
+
+                Ok(Self::UpdateTradingStart { trading_start_ts })
+            }
+            Self::SET_NO_CPI => {
+                let [no_cpi] = data else {
+                    return Err(ProgramError::InvalidInstructionData);
+                };
+
+                match *no_cpi {
+                    0 => Ok(Self::SetNoCpi { no_cpi: false }),
+                    1 => Ok(Self::SetNoCpi { no_cpi: true }),
+                    _ => Err(ProgramError::InvalidInstructionData),
+                }
+            }
+            Self::SET_MAX_LP_SUPPLY => {
+                if data.len() != size_of::<u64>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let max_lp_supply = read_u64(data, 0)?;
+
+                Ok(Self::SetMaxLpSupply { max_lp_supply })
+            }
+            Self::SET_PRICE_BAND => {
+                if data.len() != size_of::<u64>() * 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let price_band = PriceBand {
+                    min_price: read_u64(data, 0)?,
+                    max_price: read_u64(data, 8)?,
+                };
+                price_band.validate()?;
+
+                Ok(Self::SetPriceBand { price_band })
+            }
+            Self::PAUSE | Self::UNPAUSE | Self::SET_WITHDRAW_ONLY => {
+                if !data.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                match *op {
+                    Self::PAUSE => Ok(Self::Pause),
+                    Self::UNPAUSE => Ok(Self::Unpause),
+                    _ => Ok(Self::SetWithdrawOnly),
+                }
+            }
+            Self::SET_PROTOCOL_FEE => {
+                if data.len() != size_of::<u16>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let protocol_fee_bps = read_u16(data, 0)?;
+
+                if protocol_fee_bps as u64 > BPS_DENOMINATOR {
+                    return Err(PinocchioError::InvalidFee.into());
+                }
+
+                Ok(Self::SetProtocolFee { protocol_fee_bps })
+            }
+            Self::UPDATE_FEE => {
+                if data.len() != size_of::<u16>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let fee = read_u16(data, 0)?;
+
+                if fee > MAX_FEE_BPS {
+                    return Err(PinocchioError::InvalidFee.into());
+                }
+
+                Ok(Self::UpdateFee { fee })
+            }
+            Self::SET_REFERRAL_FEE => {
+                if data.len() != size_of::<u16>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let referral_fee_bps = read_u16(data, 0)?;
+
+                if referral_fee_bps as u64 > BPS_DENOMINATOR {
+                    return Err(PinocchioError::InvalidFee.into());
+                }
+
+                Ok(Self::SetReferralFee { referral_fee_bps })
+            }
+            Self::SET_WHITELIST_AUTHORITY => {
+                if data.len() != size_of::<Pubkey>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let whitelist_authority = read_pubkey(data, 0)?;
+
+                Ok(Self::SetWhitelistAuthority {
+                    whitelist_authority,
+                })
+            }
+            Self::RAMP_AMP => {
+                if data.len() != size_of::<u64>() + size_of::<i64>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let future_amp = read_u64(data, 0)?;
+                let ramp_end_ts = read_i64(data, 8)?;
+
+                Ok(Self::RampAmp {
+                    future_amp,
+                    ramp_end_ts,
+                })
+            }
+            Self::SET_LAUNCH_GUARD => {
+                if data.len() != size_of::<i64>() + 1 + size_of::<u64>() * 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let window = read_i64(data, 0)?;
+                let x_to_y = read_bool(data, 8)?;
+                let wallet_cap = read_u64(data, 9)?;
+                let slot_cap = read_u64(data, 17)?;
+
+                if !(0..=MAX_LAUNCH_WINDOW_SECS).contains(&window) {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                Ok(Self::SetLaunchGuard {
+                    window,
+                    guard: LaunchGuard {
+                        x_to_y,
+                        wallet_cap,
+                        slot_cap,
+                    },
+                })
+            }
+            Self::SET_ROYALTY => {
+                if data.len() != size_of::<u16>() + size_of::<Pubkey>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let bps = read_u16(data, 0)?;
+                let destination = read_pubkey(data, 2)?;
+
+                if bps > MAX_ROYALTY_BPS {
+                    return Err(PinocchioError::InvalidFee.into());
+                }
+                // a royalty needs somewhere to go
+                if bps != 0 && destination == Pubkey::default() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                Ok(Self::SetRoyalty { bps, destination })
+            }
+            Self::SET_MAX_FEE_CHANGE => {
+                if data.len() != size_of::<u16>() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+
+                let max_fee_change_bps = read_u16(data, 0)?;
+
+                if max_fee_change_bps > MAX_FEE_BPS {
+                    return Err(PinocchioError::InvalidFee.into());
+                }
+
+                Ok(Self::SetMaxFeeChange { max_fee_change_bps })
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+pub struct Admin<'a> {
+    pub accounts: AdminAccounts<'a>,
+    // must equal the config's current admin nonce, so a resubmitted
+    // governance transaction can't replay once any admin op has landed
+    pub nonce: u64,
+    pub op: AdminOp,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Admin<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = AdminAccounts::try_from(accounts)?;
+
+        let (nonce, op) = data
+            .split_at_checked(size_of::<u64>())
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        let nonce = read_u64(nonce, 0)?;
+        let op = AdminOp::try_from(op)?;
+
+        Ok(Self {
+            accounts,
+            nonce,
+            op,
+        })
+    }
+}
+
+impl<'a> Admin<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &4;
+
+    pub fn process(&self) -> ProgramResult {
+        let mut config = Config::load_mut(self.accounts.config)?;
+
+        // every admin op shares the same authority gate
+        config.require_authority(self.accounts.authority)?;
+
+        if self.nonce != config.admin_nonce() {
+            return Err(PinocchioError::StaleAdminNonce.into());
+        }
+        config.increment_admin_nonce()?;
+
+        match self.op {
+            AdminOp::UpdateTradingStart { trading_start_ts } => {
+                // the launch time can only be pulled forward, never pushed back on traders
+                if trading_start_ts >= config.trading_start_ts() {
+                    return Err(PinocchioError::InvalidTradingStart.into());
+                }
+
+                config.set_trading_start_ts(trading_start_ts);
+            }
+            AdminOp::SetNoCpi { no_cpi } => config.set_no_cpi(no_cpi),
+            AdminOp::SetMaxLpSupply { max_lp_supply } => {
+                // expects the lp mint as the first remaining account
+                let [lp_mint, ..] = self.accounts.remaining else {
+                    return Err(ProgramError::NotEnoughAccountKeys);
+                };
+
+                if lp_mint.key().ne(config.lp_mint()) {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+
+                // 0 lifts the cap, any other value must leave room for the current supply
+                if max_lp_supply != 0
+                    && max_lp_supply < MintData::from_account_info(lp_mint)?.supply()
+                {
+                    return Err(PinocchioError::InvalidLpSupplyCap.into());
+                }
+
+                config.set_max_lp_supply(max_lp_supply);
+            }
+            AdminOp::SetPriceBand { price_band } => {
+                config.schedule_price_band(price_band, Clock::get()?.slot)?;
+            }
+            AdminOp::Pause => config.set_state(AmmState::Disabled),
+            AdminOp::Unpause => config.set_state(AmmState::Initialized),
+            AdminOp::SetWithdrawOnly => config.set_state(AmmState::WithdrawOnly),
+            AdminOp::SetProtocolFee { protocol_fee_bps } => {
+                config.set_protocol_fee_bps(protocol_fee_bps)?
+            }
+            AdminOp::SetReferralFee { referral_fee_bps } => {
+                config.set_referral_fee_bps(referral_fee_bps)?
+            }
+            AdminOp::UpdateFee { fee } => config.schedule_fee(fee, Clock::get()?.slot)?,
+            AdminOp::SetWhitelistAuthority {
+                whitelist_authority,
+            } => config.set_whitelist_authority(whitelist_authority),
+            AdminOp::RampAmp {
+                future_amp,
+                ramp_end_ts,
+            } => config.schedule_amp_ramp(future_amp, ramp_end_ts, Clock::get()?.unix_timestamp)?,
+            AdminOp::SetLaunchGuard { window, guard } => {
+                config.set_launch_guard(window, guard, Clock::get()?.unix_timestamp)?
+            }
+            AdminOp::SetRoyalty { bps, destination } => config.set_royalty(bps, destination),
+            AdminOp::SetMaxFeeChange { max_fee_change_bps } => {
+                config.set_max_fee_change_bps(max_fee_change_bps)
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, AccountCheck, LpMetadataAccounts, SignerAccount, WritableAccount,
+    },
+    state::Config,
+};
+
+// accounts are expected in the same order as the fields below
+pub struct BackfillLpMetadataAccounts<'a> {
+    // anyone may pay for the metadata, its name and symbol follow the same fixed
+    // template as at initialization
+    pub payer: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub system_program: &'a AccountInfo,
+
+    pub lp_metadata: LpMetadataAccounts<'a>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for BackfillLpMetadataAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, config, mint_x, mint_y, lp_mint, system_program, remaining @ ..] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+        WritableAccount::check(payer)?;
+
+        assert_unique_accounts(&[payer, config, mint_x, mint_y, lp_mint])?;
+
+        let lp_metadata = LpMetadataAccounts::try_from(remaining)?;
+
+        // a pool that already has lp metadata keeps it
+        if lp_metadata.lp_metadata.data_len() != 0 {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+
+        Ok(Self {
+            payer,
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            system_program,
+            lp_metadata,
+        })
+    }
+}
+
+pub const BACKFILL_LP_METADATA_DATA_LEN: usize = 0;
+
+pub struct BackfillLpMetadata<'a> {
+    pub accounts: BackfillLpMetadataAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for BackfillLpMetadata<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if data.len() != BACKFILL_LP_METADATA_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = BackfillLpMetadataAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> BackfillLpMetadata<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &23;
+
+    // gives the lp mint of a pool created without metadata the metadata
+    // initialization would have created, signed by the config pda as mint authority
+    pub fn process(&self) -> ProgramResult {
+        let (config_bump, seed) = {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+
+            if config.mint_x().ne(self.accounts.mint_x.key())
+                || config.mint_y().ne(self.accounts.mint_y.key())
+                || config.lp_mint().ne(self.accounts.lp_mint.key())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            (config.config_bump(), config.seed())
+        };
+
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        self.accounts.lp_metadata.create(
+            self.accounts.lp_mint,
+            (self.accounts.mint_x, self.accounts.mint_y),
+            self.accounts.config,
+            self.accounts.payer,
+            self.accounts.system_program,
+            &[Signer::from(&config_seeds)],
+        )
+    }
+}
@@ -0,0 +1,136 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, trailing_token_2022_program, AccountCheck, CloseAccount,
+        SignerAccount, TokenAccountData, TransferChecked, WritableAccount,
+    },
+    pdas::DCA_ORDER_SEED,
+    state::DcaOrder,
+};
+
+// accounts are expected in the same order as the fields below
+pub struct CancelDcaOrderAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub order: &'a AccountInfo,
+    // input mint of the order, the mint of the escrow
+    pub mint_in: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    // receives whatever input is left in the escrow
+    pub owner_in_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CancelDcaOrderAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, order, mint_in, escrow, owner_in_ata, token_program, remaining @ ..] = accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+        WritableAccount::check(owner)?;
+        WritableAccount::check(order)?;
+        WritableAccount::check(escrow)?;
+        WritableAccount::check(owner_in_ata)?;
+
+        trailing_token_2022_program(remaining)?;
+
+        assert_unique_accounts(&[owner, order, mint_in, escrow, owner_in_ata])?;
+
+        Ok(Self {
+            owner,
+            order,
+            mint_in,
+            escrow,
+            owner_in_ata,
+            token_program,
+        })
+    }
+}
+
+pub struct CancelDcaOrder<'a> {
+    pub accounts: CancelDcaOrderAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for CancelDcaOrder<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if !data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = CancelDcaOrderAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CancelDcaOrder<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &10;
+
+    // refunds the escrow to the owner and closes both the escrow and the order,
+    // the rent of each goes back to the owner
+    pub fn process(&self) -> ProgramResult {
+        let (config, order_seed, order_bump) = {
+            let order = DcaOrder::load(self.accounts.order)?;
+            order.verify_address(self.accounts.order)?;
+            order.require_owner(self.accounts.owner)?;
+
+            (*order.config(), order.seed(), order.bump())
+        };
+
+        let remaining = {
+            let escrow = TokenAccountData::from_account_info(self.accounts.escrow)?;
+
+            if escrow.owner().ne(self.accounts.order.key())
+                || escrow.mint().ne(self.accounts.mint_in.key())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            escrow.amount()
+        };
+
+        let order_seed = order_seed.to_le_bytes();
+        let order_bump = [order_bump];
+        let order_seeds = [
+            Seed::from(DCA_ORDER_SEED),
+            Seed::from(config.as_ref()),
+            Seed::from(self.accounts.owner.key().as_ref()),
+            Seed::from(&order_seed),
+            Seed::from(&order_bump),
+        ];
+        let order_signer = [Signer::from(&order_seeds)];
+
+        if remaining != 0 {
+            TransferChecked {
+                from: self.accounts.escrow,
+                mint: self.accounts.mint_in,
+                to: self.accounts.owner_in_ata,
+                authority: self.accounts.order,
+                amount: remaining,
+            }
+            .invoke_signed(&order_signer)?;
+        }
+
+        CloseAccount {
+            account: self.accounts.escrow,
+            destination: self.accounts.owner,
+            authority: self.accounts.order,
+        }
+        .invoke_signed(&order_signer)?;
+
+        // the program owns the order, so its lamports can be moved directly
+        *self.accounts.owner.try_borrow_mut_lamports()? += self.accounts.order.lamports();
+        self.accounts.order.close()
+    }
+}
@@ -0,0 +1,208 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_unique_accounts, trailing_token_2022_program, vault_amount, AccountCheck,
+        AssociatedTokenAccount, AssociatedTokenAccountCheck, CloseAccount, MintData,
+        TransferChecked, WritableAccount,
+    },
+    state::Config,
+};
+
+// accounts are expected in the same order as the fields below
+pub struct CloseConfigAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    // receive whatever dust is left in the vaults
+    pub authority_x_ata: &'a AccountInfo,
+    pub authority_y_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CloseConfigAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, mint_x, mint_y, lp_mint, vault_x, vault_y, authority_x_ata, authority_y_ata, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        // the signer check happens in Config::require_authority
+        WritableAccount::check(authority)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(authority_x_ata)?;
+        WritableAccount::check(authority_y_ata)?;
+
+        trailing_token_2022_program(remaining)?;
+
+        assert_unique_accounts(&[
+            authority,
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            vault_x,
+            vault_y,
+            authority_x_ata,
+            authority_y_ata,
+        ])?;
+
+        AssociatedTokenAccount::check(authority_x_ata, authority, mint_x)?;
+        AssociatedTokenAccount::check(authority_y_ata, authority, mint_y)?;
+
+        Ok(Self {
+            authority,
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            vault_x,
+            vault_y,
+            authority_x_ata,
+            authority_y_ata,
+            token_program,
+        })
+    }
+}
+
+pub struct CloseConfig<'a> {
+    pub accounts: CloseConfigAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for CloseConfig<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if !data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = CloseConfigAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CloseConfig<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &11;
+
+    // closes a pool with no lp left and returns the rent of the config and both vaults
+    // to the authority. with no lp outstanding nobody else has a claim on the vaults,
+    // so rounding dust and donations still in them are swept to the authority first.
+    // the legacy token program can't close mints, so the lp mint stays
+    pub fn process(&self) -> ProgramResult {
+        let (config_bump, seed) = {
+            let config = Config::load(self.accounts.config)?;
+
+            config.verify_address(self.accounts.config)?;
+            config.require_authority(self.accounts.authority)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+            config.require_no_flash_loan()?;
+
+            if config.lp_mint().ne(self.accounts.lp_mint.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            // lp locked by a first deposit has a claim on the vaults as well
+            if !config.locked_lp().is_zero() {
+                return Err(PinocchioError::PoolNotEmpty.into());
+            }
+
+            (config.config_bump(), config.seed())
+        };
+
+        if MintData::from_account_info(self.accounts.lp_mint)?.supply() != 0 {
+            return Err(PinocchioError::PoolNotEmpty.into());
+        }
+
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        self.sweep_vault(
+            self.accounts.vault_x,
+            self.accounts.mint_x,
+            self.accounts.authority_x_ata,
+            &signer,
+        )?;
+        self.sweep_vault(
+            self.accounts.vault_y,
+            self.accounts.mint_y,
+            self.accounts.authority_y_ata,
+            &signer,
+        )?;
+
+        CloseAccount {
+            account: self.accounts.vault_x,
+            destination: self.accounts.authority,
+            authority: self.accounts.config,
+        }
+        .invoke_signed(&signer)?;
+
+        CloseAccount {
+            account: self.accounts.vault_y,
+            destination: self.accounts.authority,
+            authority: self.accounts.config,
+        }
+        .invoke_signed(&signer)?;
+
+        // the program owns the config, so its lamports can be moved directly
+        *self.accounts.authority.try_borrow_mut_lamports()? += self.accounts.config.lamports();
+        self.accounts.config.close()
+    }
+
+    // moves the whole balance of `vault` to the authority's ata, a token account
+    // can only be closed once it is empty
+    fn sweep_vault(
+        &self,
+        vault: &AccountInfo,
+        mint: &AccountInfo,
+        authority_ata: &AccountInfo,
+        signer: &[Signer],
+    ) -> ProgramResult {
+        let amount = vault_amount(vault, self.accounts.config, mint)?;
+        if amount == 0 {
+            return Ok(());
+        }
+
+        TransferChecked {
+            from: vault,
+            mint,
+            to: authority_ata,
+            authority: self.accounts.config,
+            amount,
+        }
+        .invoke_signed(signer)
+    }
+}
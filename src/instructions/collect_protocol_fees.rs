@@ -0,0 +1,157 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, trailing_token_2022_program, AccountCheck, AssociatedTokenAccount,
+        AssociatedTokenAccountCheck, TransferChecked, WritableAccount,
+    },
+    state::Config,
+};
+
+// accounts are expected in the same order as the fields below
+pub struct CollectProtocolFeesAccounts<'a> {
+    pub authority: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    pub authority_x_ata: &'a AccountInfo,
+    pub authority_y_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CollectProtocolFeesAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, config, mint_x, mint_y, vault_x, vault_y, authority_x_ata, authority_y_ata, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        trailing_token_2022_program(remaining)?;
+
+        // the signer check happens in Config::require_authority
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(authority_x_ata)?;
+        WritableAccount::check(authority_y_ata)?;
+
+        assert_unique_accounts(&[
+            authority,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            authority_x_ata,
+            authority_y_ata,
+        ])?;
+
+        AssociatedTokenAccount::check(vault_x, config, mint_x)?;
+        AssociatedTokenAccount::check(vault_y, config, mint_y)?;
+        AssociatedTokenAccount::check(authority_x_ata, authority, mint_x)?;
+        AssociatedTokenAccount::check(authority_y_ata, authority, mint_y)?;
+
+        Ok(Self {
+            authority,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            authority_x_ata,
+            authority_y_ata,
+            token_program,
+        })
+    }
+}
+
+pub struct CollectProtocolFees<'a> {
+    pub accounts: CollectProtocolFeesAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for CollectProtocolFees<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if !data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = CollectProtocolFeesAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> CollectProtocolFees<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &7;
+
+    // pays the accrued protocol fees out of the vaults to the authority's atas
+    pub fn process(&self) -> ProgramResult {
+        let (fee_x, fee_y, config_bump, seed) = {
+            let mut config = Config::load_mut(self.accounts.config)?;
+
+            config.verify_address(self.accounts.config)?;
+            config.require_authority(self.accounts.authority)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+            config.require_no_flash_loan()?;
+
+            let (fee_x, fee_y) = config.take_protocol_fees();
+            (fee_x, fee_y, config.config_bump(), config.seed())
+        };
+
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        if fee_x != 0 {
+            TransferChecked {
+                from: self.accounts.vault_x,
+                mint: self.accounts.mint_x,
+                to: self.accounts.authority_x_ata,
+                authority: self.accounts.config,
+                amount: fee_x,
+            }
+            .invoke_signed(&signer)?;
+        }
+
+        if fee_y != 0 {
+            TransferChecked {
+                from: self.accounts.vault_y,
+                mint: self.accounts.mint_y,
+                to: self.accounts.authority_y_ata,
+                authority: self.accounts.config,
+                amount: fee_y,
+            }
+            .invoke_signed(&signer)?;
+        }
+
+        Ok(())
+    }
+}
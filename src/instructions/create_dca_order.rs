@@ -0,0 +1,233 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Seed,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_unique_accounts, read_bool, read_i64, read_u64, token_program_account,
+        trailing_token_2022_program, AccountCheck, AssociatedTokenAccount,
+        AssociatedTokenAccountCheck, AssociatedTokenAccountInit, MintInterface, ProgramAccount,
+        ProgramAccountInit, SignerAccount, TransferChecked, WritableAccount,
+    },
+    pdas::{dca_order_address, DCA_ORDER_SEED},
+    state::{Config, DcaOrder, DcaOrderParams},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct CreateDcaOrderAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub mint_in: &'a AccountInfo,
+
+    pub order: &'a AccountInfo,
+    // the order's ata for mint_in, holds the tokens still to be swapped
+    pub escrow: &'a AccountInfo,
+    pub owner_in_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub associated_token_program: &'a AccountInfo,
+
+    // trailing account, needed when mint_in is under the token-2022 program
+    pub token_2022_program: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for CreateDcaOrderAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, config, mint_in, order, escrow, owner_in_ata, token_program, system_program, associated_token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+        WritableAccount::check(owner)?;
+        WritableAccount::check(order)?;
+        WritableAccount::check(escrow)?;
+        WritableAccount::check(owner_in_ata)?;
+
+        MintInterface::check(mint_in)?;
+        let token_2022_program = trailing_token_2022_program(remaining)?;
+
+        assert_unique_accounts(&[owner, config, mint_in, order, escrow, owner_in_ata])?;
+
+        AssociatedTokenAccount::check(owner_in_ata, owner, mint_in)?;
+
+        Ok(Self {
+            owner,
+            config,
+            mint_in,
+            order,
+            escrow,
+            owner_in_ata,
+            token_program,
+            system_program,
+            associated_token_program,
+            token_2022_program,
+        })
+    }
+}
+
+pub struct CreateDcaOrderInstruction {
+    pub seed: u64,
+    pub x_to_y: bool,
+    pub amount_per_tick: u64,
+    pub min_out_per_tick: u64,
+    pub tip_per_tick: u64,
+    // seconds between two ticks
+    pub interval: i64,
+    // total input moved into the escrow
+    pub amount: u64,
+}
+
+pub const CREATE_DCA_ORDER_DATA_LEN: usize = 49;
+
+impl<'a> TryFrom<&'a [u8]> for CreateDcaOrderInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != CREATE_DCA_ORDER_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = read_u64(data, 0)?;
+        let x_to_y = read_bool(data, 8)?;
+        let amount_per_tick = read_u64(data, 9)?;
+        let min_out_per_tick = read_u64(data, 17)?;
+        let tip_per_tick = read_u64(data, 25)?;
+        let interval = read_i64(data, 33)?;
+        let amount = read_u64(data, 41)?;
+
+        // a zero min_out would let keepers execute ticks at any price
+        if amount_per_tick == 0 || min_out_per_tick == 0 || amount == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        if interval <= 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            x_to_y,
+            amount_per_tick,
+            min_out_per_tick,
+            tip_per_tick,
+            interval,
+            amount,
+        })
+    }
+}
+
+pub struct CreateDcaOrder<'a> {
+    pub accounts: CreateDcaOrderAccounts<'a>,
+    pub instruction: CreateDcaOrderInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for CreateDcaOrder<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = CreateDcaOrderAccounts::try_from(accounts)?;
+        let instruction = CreateDcaOrderInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> CreateDcaOrder<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &8;
+
+    pub fn process(&self) -> ProgramResult {
+        {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.require_active()?;
+
+            let mint_in = if self.instruction.x_to_y {
+                config.mint_x()
+            } else {
+                config.mint_y()
+            };
+            if mint_in.ne(self.accounts.mint_in.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        // searched for once here, the order stores the canonical bump for every later
+        // instruction to sign with
+        let (order, order_bump) = dca_order_address(
+            self.accounts.config.key(),
+            self.accounts.owner.key(),
+            self.instruction.seed,
+        );
+        if order.ne(self.accounts.order.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let seed = self.instruction.seed.to_le_bytes();
+        let bump = [order_bump];
+        let order_seeds = [
+            Seed::from(DCA_ORDER_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(self.accounts.owner.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&bump),
+        ];
+
+        ProgramAccount::init::<DcaOrder>(
+            self.accounts.owner,
+            self.accounts.order,
+            &order_seeds,
+            DcaOrder::LEN,
+        )?;
+
+        AssociatedTokenAccount::init(
+            self.accounts.escrow,
+            self.accounts.mint_in,
+            self.accounts.owner,
+            self.accounts.order,
+            self.accounts.system_program,
+            token_program_account(
+                self.accounts.mint_in,
+                self.accounts.token_program,
+                self.accounts.token_2022_program,
+            )?,
+        )?;
+
+        TransferChecked {
+            from: self.accounts.owner_in_ata,
+            mint: self.accounts.mint_in,
+            to: self.accounts.escrow,
+            authority: self.accounts.owner,
+            amount: self.instruction.amount,
+        }
+        .invoke()?;
+
+        // the first tick can run right away
+        DcaOrder::load_mut(self.accounts.order)?.set_inner(DcaOrderParams {
+            owner: *self.accounts.owner.key(),
+            config: *self.accounts.config.key(),
+            seed: self.instruction.seed,
+            bump: order_bump,
+            x_to_y: self.instruction.x_to_y,
+            amount_per_tick: self.instruction.amount_per_tick,
+            min_out_per_tick: self.instruction.min_out_per_tick,
+            tip_per_tick: self.instruction.tip_per_tick,
+            interval: self.instruction.interval,
+            next_tick_ts: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
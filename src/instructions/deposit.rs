@@ -1,22 +1,26 @@
-use core::cmp;
-
 use pinocchio::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::find_program_address,
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
-use pinocchio_token::{
-    instructions::{MintTo, Transfer},
-    state::TokenAccount,
-};
 
 use crate::{
     error::PinocchioError,
+    events::DepositEvent,
     instructions::{
-        AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
-        AssociatedTokenAccountInit, MintInterface, SignerAccount,
+        account_is_rent_exempt, assert_top_level, assert_unique_accounts, is_native_mint,
+        is_temporary_wsol, read_i64, read_u16, read_u64, split_token_2022_program, unwrap_sol,
+        vault_amount, wrap_sol, AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
+        AssociatedTokenAccountInit, MintData, MintInterface, MintTo, SignerAccount,
+        TransferChecked, WritableAccount,
     },
+    math::{LpAmount, TokenAmount},
+    state::{price_within_tolerance, Config, BPS_DENOMINATOR, MINIMUM_LIQUIDITY},
 };
 
+// accounts are expected in the same order as the fields below
 pub struct DepositAccounts<'a> {
     pub user: &'a AccountInfo,
 
@@ -28,21 +32,26 @@ pub struct DepositAccounts<'a> {
 
     pub vault_x: &'a AccountInfo,
     pub vault_y: &'a AccountInfo,
-    pub vault_lp: &'a AccountInfo,
 
     pub user_x_ata: &'a AccountInfo,
     pub user_y_ata: &'a AccountInfo,
+    pub vault_lp: &'a AccountInfo,
 
     pub token_program: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub associated_token_program: &'a AccountInfo,
+
+    // trailing accounts, the user's deposit permit is only required when the pool
+    // is permissioned, see Config::require_deposit_permit. the token-2022 program,
+    // when a pool mint is under it, comes last
+    pub deposit_permit: Option<&'a AccountInfo>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_x_ata, user_y_ata, vault_lp, token_program, system_program, associated_token_program] =
+        let [user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_x_ata, user_y_ata, vault_lp, token_program, system_program, associated_token_program, remaining @ ..] =
             accounts
         else {
             return Err(ProgramError::InvalidAccountData);
@@ -50,23 +59,43 @@ impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
 
         // account checks
         SignerAccount::check(user)?;
+        WritableAccount::check(user)?;
+        // the first deposit records the locked lp, and every deposit the twap
+        WritableAccount::check(config)?;
+        WritableAccount::check(lp_mint)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(user_x_ata)?;
+        WritableAccount::check(user_y_ata)?;
+        WritableAccount::check(vault_lp)?;
+
         MintInterface::check(mint_x)?;
         MintInterface::check(mint_y)?;
 
-        AssociatedTokenAccount::check(user_x_ata, user, mint_x)?;
-        AssociatedTokenAccount::check(user_y_ata, user, mint_y)?;
-
-        let seeds = &[b"lp_mint", config.key().as_ref()];
-        let (expected_lp_mint, _) = find_program_address(seeds, &crate::ID);
-
-        if expected_lp_mint != *lp_mint.key() {
-            return Err(ProgramError::InvalidAccountData);
+        // a temporary wsol ata is created by the deposit, which checks its address
+        if !is_temporary_wsol(user_x_ata, mint_x) {
+            AssociatedTokenAccount::check(user_x_ata, user, mint_x)?;
+        }
+        if !is_temporary_wsol(user_y_ata, mint_y) {
+            AssociatedTokenAccount::check(user_y_ata, user, mint_y)?;
         }
 
         if mint_x.key() == mint_y.key() {
             return Err(PinocchioError::IdenticalTokenMints.into());
         }
 
+        assert_unique_accounts(&[
+            user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_x_ata, user_y_ata,
+            vault_lp,
+        ])?;
+
+        let (_, remaining) = split_token_2022_program(remaining);
+        let deposit_permit = match remaining {
+            [] => None,
+            [deposit_permit] => Some(deposit_permit),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
         Ok(Self {
             user,
             mint_x,
@@ -81,42 +110,76 @@ impl<'a> TryFrom<&'a [AccountInfo]> for DepositAccounts<'a> {
             token_program,
             system_program,
             associated_token_program,
+            deposit_permit,
         })
     }
 }
 
 pub struct DepositInstructions {
-    pub mint_x: u64,
-    pub mint_y: u64,
-    pub min_lp_amount: u64,
+    pub mint_x: TokenAmount,
+    pub mint_y: TokenAmount,
+    pub min_lp_amount: LpAmount,
+    // unix timestamp after which the deposit is rejected
+    pub expiration: i64,
+    // optional guard for the first deposit, which sets the pool price
+    pub bootstrap_price: Option<BootstrapPrice>,
+}
+
+// price the creator expects the bootstrap deposit to set, in y per x scaled by
+// PRICE_SCALE, and how far the deposited ratio may stray from it
+pub struct BootstrapPrice {
+    pub expected_price: u64,
+    pub tolerance_bps: u16,
 }
 
+// instruction data length without and with the bootstrap price guard
+pub const DEPOSIT_DATA_LEN: usize = 32;
+pub const DEPOSIT_BOOTSTRAP_DATA_LEN: usize = 42;
+
 impl<'a> TryFrom<&'a [u8]> for DepositInstructions {
     type Error = ProgramError;
 
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        if data.len() != 24 {
+        // a trailing expected price (u64) and tolerance (u16) enable the bootstrap guard
+        if data.len() != DEPOSIT_DATA_LEN && data.len() != DEPOSIT_BOOTSTRAP_DATA_LEN {
             return Err(ProgramError::InvalidInstructionData);
         };
 
-        let mint_x = u64::from_le_bytes([
-            data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
-        ]);
-        let mint_y = u64::from_le_bytes([
-            data[8], data[9], data[10], data[11], data[12], data[13], data[14], data[15],
-        ]);
-        let min_lp_amount = u64::from_le_bytes([
-            data[16], data[17], data[18], data[19], data[20], data[21], data[22], data[23],
-        ]);
+        let mint_x = read_u64(data, 0)?;
+        let mint_y = read_u64(data, 8)?;
+        let min_lp_amount = read_u64(data, 16)?;
+        let expiration = read_i64(data, 24)?;
 
         if mint_x == 0 || mint_y == 0 {
             return Err(PinocchioError::InvalidMintAmount.into());
         }
 
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(PinocchioError::Expired.into());
+        }
+
+        let bootstrap_price = if data.len() == DEPOSIT_BOOTSTRAP_DATA_LEN {
+            let expected_price = read_u64(data, 32)?;
+            let tolerance_bps = read_u16(data, 40)?;
+
+            if expected_price == 0 || tolerance_bps as u64 > BPS_DENOMINATOR {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            Some(BootstrapPrice {
+                expected_price,
+                tolerance_bps,
+            })
+        } else {
+            None
+        };
+
         Ok(Self {
-            mint_x,
-            mint_y,
-            min_lp_amount,
+            mint_x: TokenAmount::new(mint_x),
+            mint_y: TokenAmount::new(mint_y),
+            min_lp_amount: LpAmount::new(min_lp_amount),
+            expiration,
+            bootstrap_price,
         })
     }
 }
@@ -124,8 +187,6 @@ impl<'a> TryFrom<&'a [u8]> for DepositInstructions {
 pub struct Deposit<'a> {
     pub accounts: DepositAccounts<'a>,
     pub instructions: DepositInstructions,
-    pub config_bump: u8,
-    pub lp_bump: u8,
 }
 
 impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Deposit<'a> {
@@ -135,23 +196,9 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Deposit<'a> {
         let accounts = DepositAccounts::try_from(accounts)?;
         let instructions = DepositInstructions::try_from(data)?;
 
-        AssociatedTokenAccount::init_if_needed(
-            accounts.vault_x,
-            accounts.mint_x,
-            accounts.user,
-            accounts.user,
-            accounts.system_program,
-            accounts.token_program,
-        )?;
-
-        AssociatedTokenAccount::init_if_needed(
-            accounts.vault_y,
-            accounts.mint_y,
-            accounts.user,
-            accounts.user,
-            accounts.system_program,
-            accounts.token_program,
-        )?;
+        // the vaults are created with the pool, a deposit never creates them
+        AssociatedTokenAccount::check(accounts.vault_x, accounts.config, accounts.mint_x)?;
+        AssociatedTokenAccount::check(accounts.vault_y, accounts.config, accounts.mint_y)?;
 
         AssociatedTokenAccount::init_if_needed(
             accounts.vault_lp,
@@ -162,27 +209,9 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Deposit<'a> {
             accounts.token_program,
         )?;
 
-        let seeds_slice = &[
-            b"config",
-            accounts.mint_x.key().as_ref(),
-            accounts.mint_y.key().as_ref(),
-        ];
-        let (_, config_bump) = find_program_address(seeds_slice, &crate::ID);
-
-        let (_, lp_bump) = find_program_address(
-            &[
-                b"lp_mint",
-                accounts.mint_x.key().as_ref(),
-                accounts.mint_y.key().as_ref(),
-            ],
-            &crate::ID,
-        );
-
         Ok(Self {
             accounts,
             instructions,
-            config_bump,
-            lp_bump,
         })
     }
 }
@@ -190,100 +219,262 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Deposit<'a> {
 impl<'a> Deposit<'a> {
     pub const DISCRIMINATOR: &'a u8 = &1;
     pub fn process(&self) -> ProgramResult {
-        // getting the vault datas
-        let vault_x_data = self.accounts.vault_x.try_borrow_data()?;
-        let vault_x = unsafe { TokenAccount::from_bytes_unchecked(&vault_x_data) };
-
-        let vault_y_data = self.accounts.vault_y.try_borrow_data()?;
-        let vault_y = unsafe { TokenAccount::from_bytes_unchecked(&vault_y_data) };
-
-        let vault_lp_data = self.accounts.vault_lp.try_borrow_data()?;
-        let vault_lp = unsafe { TokenAccount::from_bytes_unchecked(&vault_lp_data) };
-
-        if vault_x.owner() != self.accounts.config.key()
-            || vault_y.owner() != self.accounts.config.key()
+        let rent = Rent::get()?;
+        if !account_is_rent_exempt(self.accounts.config, &rent)
+            || !account_is_rent_exempt(self.accounts.vault_x, &rent)
+            || !account_is_rent_exempt(self.accounts.vault_y, &rent)
         {
-            return Err(PinocchioError::InvalidOwner.into());
+            return Err(PinocchioError::NotRentExempt.into());
         }
 
-        if vault_x.mint() != self.accounts.mint_x.key()
-            || vault_y.mint() != self.accounts.mint_y.key()
-        {
-            return Err(ProgramError::InvalidAccountData);
+        let clock = Clock::get()?;
+
+        let (
+            min_lp_mint,
+            max_lp_supply,
+            config_bump,
+            seed,
+            no_cpi,
+            curve_type,
+            fee,
+            protocol_fees,
+            locked_lp,
+        ) = {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+            config.require_active()?;
+            config.require_deposit_permit(
+                self.accounts.config,
+                self.accounts.user,
+                self.accounts.deposit_permit,
+            )?;
+
+            // the stored lp mint was derived with the stored bump at initialization,
+            // comparing against it avoids re-deriving the pda on every deposit
+            if config.lp_mint().ne(self.accounts.lp_mint.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            (
+                config.min_lp_mint(),
+                config.max_lp_supply(),
+                config.config_bump(),
+                config.seed(),
+                config.no_cpi(),
+                config.curve_type(clock.unix_timestamp)?,
+                config.fee(clock.slot),
+                config.protocol_fees(),
+                config.locked_lp(),
+            )
         };
 
-        let reserve_mint_x = vault_x.amount();
-        let reserve_mint_y = vault_y.amount();
+        if no_cpi {
+            assert_top_level()?;
+        }
 
-        let lp_supply = vault_lp.amount();
+        // reserves before the transfers, every borrow is dropped before the cpis below
+        let (vault_x_before, vault_y_before) = self.vault_reserves()?;
+        let minted_lp_supply =
+            LpAmount::new(MintData::from_account_info(self.accounts.lp_mint)?.supply());
+        let lp_supply = minted_lp_supply
+            .checked_add(locked_lp)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let temporary_x = self.wrap_native(
+            self.accounts.mint_x,
+            self.accounts.user_x_ata,
+            self.instructions.mint_x.get(),
+        )?;
+        let temporary_y = self.wrap_native(
+            self.accounts.mint_y,
+            self.accounts.user_y_ata,
+            self.instructions.mint_y.get(),
+        )?;
 
-        let lp_mint_tokens_supply = if reserve_mint_x == 0 && reserve_mint_y == 0 {
-            let product = (self.instructions.mint_x as u128)
-                .checked_mul(self.instructions.mint_y as u128)
-                .ok_or_else(|| PinocchioError::MathOverflow)?;
+        TransferChecked {
+            from: self.accounts.user_x_ata,
+            mint: self.accounts.mint_x,
+            to: self.accounts.vault_x,
+            amount: self.instructions.mint_x.get(),
+            authority: self.accounts.user,
+        }
+        .invoke()?;
 
-            if product == 0 {
-                return Err(PinocchioError::InvalidMintSupply.into());
-            }
+        TransferChecked {
+            from: self.accounts.user_y_ata,
+            mint: self.accounts.mint_y,
+            to: self.accounts.vault_y,
+            amount: self.instructions.mint_y.get(),
+            authority: self.accounts.user,
+        }
+        .invoke()?;
 
-            let sqrt_result = product.isqrt() as u64;
+        // a temporary wsol ata is empty again, closing it refunds its rent
+        if temporary_x {
+            unwrap_sol(self.accounts.user, self.accounts.user_x_ata)?;
+        }
+        if temporary_y {
+            unwrap_sol(self.accounts.user, self.accounts.user_y_ata)?;
+        }
 
-            if sqrt_result < 1000 {
-                return Err(PinocchioError::InvalidMintSupply.into());
+        // lp is priced from what the vaults actually received, so rounding or
+        // transfer fees can never mint more lp than the value contributed
+        let (vault_x_after, vault_y_after) = self.vault_reserves()?;
+        let deposited_x = vault_x_after
+            .checked_sub(vault_x_before)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let deposited_y = vault_y_after
+            .checked_sub(vault_y_before)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        // accrued protocol fees sit in the vaults but don't belong to the lps
+        let reserve_mint_x = vault_x_before
+            .checked_sub(protocol_fees.0)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let reserve_mint_y = vault_y_before
+            .checked_sub(protocol_fees.1)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        // a typo'd first deposit would hand the pool to the first arbitrageur, so the
+        // creator can pin the price the deposited ratio is expected to set
+        if let Some(bootstrap_price) = &self.instructions.bootstrap_price {
+            if reserve_mint_x == 0 && reserve_mint_y == 0 {
+                let price = curve_type.spot_price(deposited_x, deposited_y)?;
+
+                if !price_within_tolerance(
+                    price,
+                    bootstrap_price.expected_price,
+                    bootstrap_price.tolerance_bps,
+                ) {
+                    return Err(PinocchioError::BootstrapPriceMismatch.into());
+                }
             }
+        }
 
-            sqrt_result
+        let lp_amount = curve_type.deposit_lp_amount(
+            TokenAmount::new(deposited_x),
+            TokenAmount::new(deposited_y),
+            TokenAmount::new(reserve_mint_x),
+            TokenAmount::new(reserve_mint_y),
+            lp_supply,
+            fee,
+        )?;
+
+        // the first deposit into an empty pool gives up MINIMUM_LIQUIDITY of its lp,
+        // it is only recorded in the config so the reserves behind it stay locked
+        let locks_liquidity = reserve_mint_x == 0 && reserve_mint_y == 0 && locked_lp.is_zero();
+        let lp_mint_tokens_supply = if locks_liquidity {
+            lp_amount
+                .checked_sub(MINIMUM_LIQUIDITY)
+                .ok_or(PinocchioError::LessThanMinimum)?
         } else {
-            if reserve_mint_x == 0 || reserve_mint_y == 0 || lp_supply == 0 {
-                return Err(PinocchioError::InvalidMintSupply.into());
-            };
-
-            let lp_from_x = (self.instructions.mint_x as u128)
-                .checked_mul(lp_supply as u128)
-                .ok_or_else(|| PinocchioError::MathOverflow)?
-                .checked_div(reserve_mint_x as u128)
-                .ok_or_else(|| PinocchioError::MathOverflow)? as u64;
-
-            let lp_from_y = (self.instructions.mint_y as u128)
-                .checked_mul(lp_supply as u128)
-                .ok_or_else(|| PinocchioError::MathOverflow)?
-                .checked_div(reserve_mint_y as u128)
-                .ok_or_else(|| PinocchioError::MathOverflow)? as u64;
-
-            cmp::min(lp_from_x, lp_from_y)
+            lp_amount
         };
 
-        if lp_mint_tokens_supply == 0 {
+        if lp_mint_tokens_supply.is_zero() {
             return Err(PinocchioError::InvalidAmount.into());
         }
 
+        // dust deposits bloat the lp supply, so each mint has a pool wide floor
+        if lp_mint_tokens_supply < min_lp_mint {
+            return Err(PinocchioError::LessThanMinimum.into());
+        }
+
         if lp_mint_tokens_supply < self.instructions.min_lp_amount {
             return Err(PinocchioError::SlipageExceeded.into());
         }
 
-        Transfer {
-            from: self.accounts.user_x_ata,
-            to: self.accounts.vault_x,
-            amount: self.instructions.mint_x,
-            authority: self.accounts.user,
-        }
-        .invoke()?;
+        if let Some(max_lp_supply) = max_lp_supply {
+            let new_supply = minted_lp_supply
+                .checked_add(lp_mint_tokens_supply)
+                .ok_or(PinocchioError::MathOverflow)?;
 
-        Transfer {
-            from: self.accounts.user_y_ata,
-            to: self.accounts.vault_y,
-            amount: self.instructions.mint_y,
-            authority: self.accounts.user,
+            if new_supply > max_lp_supply {
+                return Err(PinocchioError::LpSupplyCapExceeded.into());
+            }
         }
-        .invoke()?;
+
+        let event_seq = {
+            let mut config = Config::load_mut(self.accounts.config)?;
+            config.update_price_cumulative(reserve_mint_x, reserve_mint_y, clock.unix_timestamp)?;
+
+            if locks_liquidity {
+                config.lock_lp(MINIMUM_LIQUIDITY)?;
+            }
+
+            config.next_event_seq()?
+        };
+
+        // the config pda is the lp mint authority
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
 
         MintTo {
-            account: self.accounts.lp_mint,
+            account: self.accounts.vault_lp,
             mint: self.accounts.lp_mint,
-            amount: lp_mint_tokens_supply,
+            amount: lp_mint_tokens_supply.get(),
             mint_authority: self.accounts.config,
         }
-        .invoke()?;
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        DepositEvent {
+            user: *self.accounts.user.key(),
+            amount_x: deposited_x,
+            amount_y: deposited_y,
+            lp_amount: lp_mint_tokens_supply.get(),
+        }
+        .emit(self.accounts.config.key(), event_seq);
+
         Ok(())
     }
+
+    // a native side is paid in sol, see wrap_sol. returns whether the wsol ata is
+    // temporary
+    fn wrap_native(
+        &self,
+        mint: &AccountInfo,
+        ata: &AccountInfo,
+        amount: u64,
+    ) -> Result<bool, ProgramError> {
+        if !is_native_mint(mint) {
+            return Ok(false);
+        }
+
+        wrap_sol(
+            self.accounts.user,
+            ata,
+            mint,
+            amount,
+            Some(self.accounts.system_program),
+            self.accounts.token_program,
+        )
+    }
+
+    // current vault balances, after checking both vaults belong to this pool
+    fn vault_reserves(&self) -> Result<(u64, u64), ProgramError> {
+        Ok((
+            vault_amount(
+                self.accounts.vault_x,
+                self.accounts.config,
+                self.accounts.mint_x,
+            )?,
+            vault_amount(
+                self.accounts.vault_y,
+                self.accounts.config,
+                self.accounts.mint_y,
+            )?,
+        ))
+    }
 }
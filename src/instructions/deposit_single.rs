@@ -0,0 +1,395 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    events::DepositEvent,
+    instructions::{
+        assert_unique_accounts, is_native_mint, is_temporary_wsol, read_bool, read_i64, read_u64,
+        split_token_2022_program, unwrap_sol, vault_amount, wrap_sol, AccountCheck,
+        AssociatedTokenAccount, AssociatedTokenAccountCheck, AssociatedTokenAccountInit, MintData,
+        MintInterface, MintTo, SignerAccount, SwapPool, TransferChecked, WritableAccount,
+    },
+    math::{LpAmount, TokenAmount},
+    state::{fee_share, Config, DepositPermit},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct DepositSingleAccounts<'a> {
+    pub user: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub config: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    // the user's ata of the deposited mint
+    pub user_ata: &'a AccountInfo,
+    pub user_lp_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub associated_token_program: &'a AccountInfo,
+
+    // trailing accounts, the user's deposit permit is only required when the pool
+    // is permissioned, see Config::require_deposit_permit. the observation buffer
+    // as on swaps, told apart from the permit by its length. the token-2022
+    // program, when a pool mint is under it, comes last
+    pub deposit_permit: Option<&'a AccountInfo>,
+    pub observations: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for DepositSingleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_ata, user_lp_ata, token_program, system_program, associated_token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(user)?;
+        WritableAccount::check(user)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(lp_mint)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(user_ata)?;
+        WritableAccount::check(user_lp_ata)?;
+
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+
+        if mint_x.key() == mint_y.key() {
+            return Err(PinocchioError::IdenticalTokenMints.into());
+        }
+
+        assert_unique_accounts(&[
+            user,
+            mint_x,
+            mint_y,
+            lp_mint,
+            config,
+            vault_x,
+            vault_y,
+            user_ata,
+            user_lp_ata,
+        ])?;
+
+        AssociatedTokenAccount::check(vault_x, config, mint_x)?;
+        AssociatedTokenAccount::check(vault_y, config, mint_y)?;
+
+        let (_, remaining) = split_token_2022_program(remaining);
+        let mut deposit_permit = None;
+        let mut observations = None;
+        for account in remaining {
+            let slot = if account.data_len() == DepositPermit::LEN {
+                &mut deposit_permit
+            } else {
+                WritableAccount::check(account)?;
+                &mut observations
+            };
+            if slot.replace(account).is_some() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        Ok(Self {
+            user,
+            mint_x,
+            mint_y,
+            lp_mint,
+            config,
+            vault_x,
+            vault_y,
+            user_ata,
+            user_lp_ata,
+            token_program,
+            system_program,
+            associated_token_program,
+            deposit_permit,
+            observations,
+        })
+    }
+}
+
+pub struct DepositSingleInstruction {
+    pub amount: TokenAmount,
+    pub min_lp_amount: LpAmount,
+    // true to deposit x, false to deposit y
+    pub x: bool,
+    // unix timestamp after which the deposit is rejected
+    pub expiration: i64,
+}
+
+pub const DEPOSIT_SINGLE_DATA_LEN: usize = size_of::<u64>() * 2 + 1 + size_of::<i64>();
+
+impl<'a> TryFrom<&'a [u8]> for DepositSingleInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != DEPOSIT_SINGLE_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount = read_u64(data, 0)?;
+        let min_lp_amount = read_u64(data, 8)?;
+        let x = read_bool(data, 16)?;
+        let expiration = read_i64(data, 17)?;
+
+        if amount == 0 {
+            return Err(PinocchioError::InvalidMintAmount.into());
+        }
+
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(PinocchioError::Expired.into());
+        }
+
+        Ok(Self {
+            amount: TokenAmount::new(amount),
+            min_lp_amount: LpAmount::new(min_lp_amount),
+            x,
+            expiration,
+        })
+    }
+}
+
+pub struct DepositSingle<'a> {
+    pub accounts: DepositSingleAccounts<'a>,
+    pub instruction: DepositSingleInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for DepositSingle<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = DepositSingleAccounts::try_from(accounts)?;
+        let instruction = DepositSingleInstruction::try_from(data)?;
+
+        // a temporary wsol ata is created by wrap_sol, which checks its address
+        let mint = if instruction.x {
+            accounts.mint_x
+        } else {
+            accounts.mint_y
+        };
+        if !is_temporary_wsol(accounts.user_ata, mint) {
+            AssociatedTokenAccount::check(accounts.user_ata, accounts.user, mint)?;
+        }
+
+        AssociatedTokenAccount::init_if_needed(
+            accounts.user_lp_ata,
+            accounts.lp_mint,
+            accounts.user,
+            accounts.user,
+            accounts.system_program,
+            accounts.token_program,
+        )?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> DepositSingle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &15;
+
+    // zap in: deposits only one of the pool tokens. the part that balances the deposit
+    // is swapped through the pool's curve without leaving the vaults, then both sides
+    // are deposited at the post swap reserves
+    pub fn process(&self) -> ProgramResult {
+        let x_to_y = self.instruction.x;
+
+        // the swap leg is gated like any other swap
+        let pool = SwapPool::load(
+            self.accounts.config,
+            self.accounts.mint_x,
+            self.accounts.mint_y,
+            self.accounts.lp_mint,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.observations,
+        )?;
+        pool.require_no_launch_guard()?;
+        pool.require_no_royalty()?;
+
+        let (min_lp_mint, max_lp_supply, locked_lp) = {
+            let config = Config::load(self.accounts.config)?;
+            config.require_deposit_permit(
+                self.accounts.config,
+                self.accounts.user,
+                self.accounts.deposit_permit,
+            )?;
+
+            (
+                config.min_lp_mint(),
+                config.max_lp_supply(),
+                config.locked_lp(),
+            )
+        };
+
+        // an empty pool has no ratio to deposit at, it is bootstrapped by Deposit
+        if pool.reserve_x == 0 || pool.reserve_y == 0 {
+            return Err(PinocchioError::InsufficientLiquidity.into());
+        }
+
+        let minted_lp_supply =
+            LpAmount::new(MintData::from_account_info(self.accounts.lp_mint)?.supply());
+        let lp_supply = minted_lp_supply
+            .checked_add(locked_lp)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let (mint_in, vault_in) = if x_to_y {
+            (self.accounts.mint_x, self.accounts.vault_x)
+        } else {
+            (self.accounts.mint_y, self.accounts.vault_y)
+        };
+
+        let temporary = if is_native_mint(mint_in) {
+            wrap_sol(
+                self.accounts.user,
+                self.accounts.user_ata,
+                mint_in,
+                self.instruction.amount.get(),
+                Some(self.accounts.system_program),
+                self.accounts.token_program,
+            )?
+        } else {
+            false
+        };
+
+        let before = vault_amount(vault_in, self.accounts.config, mint_in)?;
+
+        TransferChecked {
+            from: self.accounts.user_ata,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.accounts.user,
+            amount: self.instruction.amount.get(),
+        }
+        .invoke()?;
+
+        if temporary {
+            unwrap_sol(self.accounts.user, self.accounts.user_ata)?;
+        }
+
+        // priced on what the vault received, never on the requested amount
+        let received = vault_amount(vault_in, self.accounts.config, mint_in)?
+            .checked_sub(before)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let (reserve_in, reserve_out) = pool.reserves(x_to_y);
+        let swap_amount = pool.curve_type.optimal_swap_amount_for_single_deposit(
+            reserve_in,
+            reserve_out,
+            received,
+            pool.fee,
+        )?;
+
+        let (swap_out, protocol_fee) = if swap_amount > 0 {
+            let swap_out =
+                pool.curve_type
+                    .swap_exact_in(reserve_in, reserve_out, swap_amount, pool.fee)?;
+            pool.check_price_band(x_to_y, swap_amount, swap_out)?;
+
+            (
+                swap_out,
+                fee_share(swap_amount, pool.fee, pool.protocol_fee_bps)?,
+            )
+        } else {
+            (0, 0)
+        };
+
+        // the swap output never leaves the vault, it is deposited right back
+        let deposit_in = received - swap_amount;
+        let reserve_in = reserve_in
+            .checked_add(swap_amount - protocol_fee)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let reserve_out = reserve_out - swap_out;
+
+        let (amount_x, amount_y, reserve_x, reserve_y) = if x_to_y {
+            (deposit_in, swap_out, reserve_in, reserve_out)
+        } else {
+            (swap_out, deposit_in, reserve_out, reserve_in)
+        };
+
+        let lp_amount = pool.curve_type.deposit_lp_amount(
+            TokenAmount::new(amount_x),
+            TokenAmount::new(amount_y),
+            TokenAmount::new(reserve_x),
+            TokenAmount::new(reserve_y),
+            lp_supply,
+            pool.fee,
+        )?;
+
+        if lp_amount.is_zero() {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        if lp_amount < min_lp_mint {
+            return Err(PinocchioError::LessThanMinimum.into());
+        }
+
+        if lp_amount < self.instruction.min_lp_amount {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        if let Some(max_lp_supply) = max_lp_supply {
+            let new_supply = minted_lp_supply
+                .checked_add(lp_amount)
+                .ok_or(PinocchioError::MathOverflow)?;
+
+            if new_supply > max_lp_supply {
+                return Err(PinocchioError::LpSupplyCapExceeded.into());
+            }
+        }
+
+        if protocol_fee > 0 {
+            Config::load_mut(self.accounts.config)?.accrue_protocol_fee(x_to_y, protocol_fee)?;
+        }
+        pool.record_swap(self.accounts.config, x_to_y, swap_amount, false)?;
+
+        // the config pda is the lp mint authority
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        MintTo {
+            account: self.accounts.user_lp_ata,
+            mint: self.accounts.lp_mint,
+            amount: lp_amount.get(),
+            mint_authority: self.accounts.config,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        // the swap leg never leaves the vaults, only the one sided input came in
+        let (amount_x, amount_y) = if x_to_y { (received, 0) } else { (0, received) };
+        let event_seq = Config::load_mut(self.accounts.config)?.next_event_seq()?;
+        DepositEvent {
+            user: *self.accounts.user.key(),
+            amount_x,
+            amount_y,
+            lp_amount: lp_amount.get(),
+        }
+        .emit(self.accounts.config.key(), event_seq);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    events::SwapEvent,
+    instructions::{
+        assert_unique_accounts, net_transfer_amount, split_token_2022_program, vault_amount,
+        AccountCheck, SignerAccount, SwapPool, TokenAccountData, TransferChecked, WritableAccount,
+    },
+    math::mul_div_ceil,
+    pdas::DCA_ORDER_SEED,
+    state::{fee_share, Config, DcaOrder},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct ExecuteDcaTickAccounts<'a> {
+    // anyone may run a due tick, the tip pays for it
+    pub keeper: &'a AccountInfo,
+    pub order: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    pub escrow: &'a AccountInfo,
+    // token account of the order owner for the output mint
+    pub owner_out_ata: &'a AccountInfo,
+    // token account of the keeper for the input mint, receives the tip
+    pub keeper_in_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+
+    // trailing accounts, the observation buffer as on swaps and the token-2022
+    // program, when a pool mint is under it, last
+    pub observations: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for ExecuteDcaTickAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [keeper, order, config, mint_x, mint_y, lp_mint, vault_x, vault_y, escrow, owner_out_ata, keeper_in_ata, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(keeper)?;
+        WritableAccount::check(order)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(escrow)?;
+        WritableAccount::check(owner_out_ata)?;
+        WritableAccount::check(keeper_in_ata)?;
+
+        assert_unique_accounts(&[
+            keeper,
+            order,
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            vault_x,
+            vault_y,
+            escrow,
+            owner_out_ata,
+            keeper_in_ata,
+        ])?;
+
+        let (_, remaining) = split_token_2022_program(remaining);
+        let observations = match remaining {
+            [] => None,
+            [observations] => {
+                WritableAccount::check(observations)?;
+                Some(observations)
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        Ok(Self {
+            keeper,
+            order,
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            vault_x,
+            vault_y,
+            escrow,
+            owner_out_ata,
+            keeper_in_ata,
+            token_program,
+            observations,
+        })
+    }
+}
+
+pub struct ExecuteDcaTick<'a> {
+    pub accounts: ExecuteDcaTickAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for ExecuteDcaTick<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if !data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = ExecuteDcaTickAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> ExecuteDcaTick<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &9;
+
+    // swaps one tick of the order through the pool at the current price and
+    // pays the output to the order owner
+    pub fn process(&self) -> ProgramResult {
+        let now = Clock::get()?.unix_timestamp;
+
+        let (
+            owner,
+            order_seed,
+            order_bump,
+            x_to_y,
+            amount_per_tick,
+            min_out_per_tick,
+            tip_per_tick,
+        ) = {
+            let order = DcaOrder::load(self.accounts.order)?;
+            order.verify_address(self.accounts.order)?;
+            order.require_due(now)?;
+
+            if order.config().ne(self.accounts.config.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            (
+                *order.owner(),
+                order.seed(),
+                order.bump(),
+                order.x_to_y(),
+                order.amount_per_tick(),
+                order.min_out_per_tick(),
+                order.tip_per_tick(),
+            )
+        };
+
+        let pool = SwapPool::load(
+            self.accounts.config,
+            self.accounts.mint_x,
+            self.accounts.mint_y,
+            self.accounts.lp_mint,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.observations,
+        )?;
+        pool.require_no_launch_guard()?;
+        pool.require_no_royalty()?;
+        let (reserve_in, reserve_out) = pool.reserves(x_to_y);
+        let (mint_in, mint_out, vault_in, vault_out) = if x_to_y {
+            (
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )
+        } else {
+            (
+                self.accounts.mint_y,
+                self.accounts.mint_x,
+                self.accounts.vault_y,
+                self.accounts.vault_x,
+            )
+        };
+
+        let balance = self.escrow_balance(mint_in)?;
+        self.check_destination(&owner, mint_out)?;
+
+        // the last tick swaps whatever is left, the tip comes out of what remains after it
+        let amount_in = amount_per_tick.min(balance);
+        if amount_in == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+        let tip = tip_per_tick.min(balance - amount_in);
+
+        let order_seed = order_seed.to_le_bytes();
+        let order_bump = [order_bump];
+        let order_seeds = [
+            Seed::from(DCA_ORDER_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(owner.as_ref()),
+            Seed::from(&order_seed),
+            Seed::from(&order_bump),
+        ];
+        let order_signer = [Signer::from(&order_seeds)];
+
+        // priced on what the vault received, never on the requested amount
+        let before = vault_amount(vault_in, self.accounts.config, mint_in)?;
+
+        TransferChecked {
+            from: self.accounts.escrow,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.accounts.order,
+            amount: amount_in,
+        }
+        .invoke_signed(&order_signer)?;
+
+        let received = vault_amount(vault_in, self.accounts.config, mint_in)?
+            .checked_sub(before)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let amount_out =
+            pool.curve_type
+                .swap_exact_in(reserve_in, reserve_out, received, pool.fee)?;
+
+        // a partial last tick is held to the same price as a full one
+        let min_out = mul_div_ceil(
+            min_out_per_tick as u128,
+            amount_in as u128,
+            amount_per_tick as u128,
+        )
+        .ok_or(PinocchioError::MathOverflow)?;
+
+        if (net_transfer_amount(mint_out, pool.epoch, amount_out)? as u128) < min_out {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        pool.check_price_band(x_to_y, received, amount_out)?;
+
+        if !pool.protocol_fee_bps.is_zero() {
+            let protocol_fee = fee_share(received, pool.fee, pool.protocol_fee_bps)?;
+            Config::load_mut(self.accounts.config)?.accrue_protocol_fee(x_to_y, protocol_fee)?;
+        }
+        pool.record_swap(self.accounts.config, x_to_y, received, false)?;
+
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to: self.accounts.owner_out_ata,
+            authority: self.accounts.config,
+            amount: amount_out,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        if tip != 0 {
+            TransferChecked {
+                from: self.accounts.escrow,
+                mint: mint_in,
+                to: self.accounts.keeper_in_ata,
+                authority: self.accounts.order,
+                amount: tip,
+            }
+            .invoke_signed(&order_signer)?;
+        }
+
+        DcaOrder::load_mut(self.accounts.order)?.advance(now)?;
+
+        let event_seq = Config::load_mut(self.accounts.config)?.next_event_seq()?;
+        SwapEvent {
+            user: owner,
+            x_to_y,
+            amount_in: received,
+            amount_out,
+        }
+        .emit(self.accounts.config.key(), event_seq);
+
+        Ok(())
+    }
+
+    // balance left in the escrow, after checking it belongs to the order for `mint_in`
+    fn escrow_balance(&self, mint_in: &AccountInfo) -> Result<u64, ProgramError> {
+        let escrow = TokenAccountData::from_account_info(self.accounts.escrow)?;
+
+        if escrow.owner().ne(self.accounts.order.key()) || escrow.mint().ne(mint_in.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(escrow.amount())
+    }
+
+    // the output may only go to the order owner
+    fn check_destination(
+        &self,
+        owner: &Pubkey,
+        mint_out: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        let destination = TokenAccountData::from_account_info(self.accounts.owner_out_ata)?;
+
+        if destination.owner().ne(owner) || destination.mint().ne(mint_out.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, instructions::Instructions, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_top_level, assert_unique_accounts, read_bool, read_u64, trailing_token_2022_program,
+        vault_amount, AccountCheck, FlashRepay, SignerAccount, TokenAccount, TransferChecked,
+        WritableAccount,
+    },
+    math::mul_div_ceil,
+    state::{Config, BPS_DENOMINATOR},
+};
+
+// accounts are expected in the same order as the fields below, FlashRepay takes
+// the config at the same position
+pub struct FlashBorrowAccounts<'a> {
+    pub borrower: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    // token account of the borrowed mint that receives the loan
+    pub destination: &'a AccountInfo,
+
+    pub instructions_sysvar: &'a AccountInfo,
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for FlashBorrowAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [borrower, config, mint_x, mint_y, vault_x, vault_y, destination, instructions_sysvar, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(borrower)?;
+        // records the outstanding loan
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(destination)?;
+
+        TokenAccount::check(destination)?;
+        trailing_token_2022_program(remaining)?;
+
+        assert_unique_accounts(&[
+            borrower,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            destination,
+        ])?;
+
+        Ok(Self {
+            borrower,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            destination,
+            instructions_sysvar,
+            token_program,
+        })
+    }
+}
+
+pub struct FlashBorrowInstruction {
+    pub amount: u64,
+    // true to borrow x, false to borrow y
+    pub x: bool,
+}
+
+pub const FLASH_BORROW_DATA_LEN: usize = 9;
+
+impl<'a> TryFrom<&'a [u8]> for FlashBorrowInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != FLASH_BORROW_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount = read_u64(data, 0)?;
+        let x = read_bool(data, 8)?;
+
+        if amount == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        Ok(Self { amount, x })
+    }
+}
+
+pub struct FlashBorrow<'a> {
+    pub accounts: FlashBorrowAccounts<'a>,
+    pub instruction: FlashBorrowInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for FlashBorrow<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = FlashBorrowAccounts::try_from(accounts)?;
+        let instruction = FlashBorrowInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> FlashBorrow<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &13;
+
+    // lends `amount` of one vault for the rest of the transaction. the loan fee is
+    // the pool's swap fee on the amount and stays in the vault for the lps
+    pub fn process(&self) -> ProgramResult {
+        // through a cpi the lookup below would inspect the caller's instruction
+        assert_top_level()?;
+        self.require_repay()?;
+
+        let (fee, protocol_fees, config_bump, seed) = {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+            config.require_active()?;
+
+            (
+                config.fee(Clock::get()?.slot),
+                config.protocol_fees(),
+                config.config_bump(),
+                config.seed(),
+            )
+        };
+
+        let (vault, mint, protocol_fee) = if self.instruction.x {
+            (self.accounts.vault_x, self.accounts.mint_x, protocol_fees.0)
+        } else {
+            (self.accounts.vault_y, self.accounts.mint_y, protocol_fees.1)
+        };
+
+        // only the lps' reserves are lent, accrued protocol fees stay put
+        let balance = vault_amount(vault, self.accounts.config, mint)?;
+        let reserve = balance
+            .checked_sub(protocol_fee)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        if self.instruction.amount > reserve {
+            return Err(PinocchioError::InsufficientLiquidity.into());
+        }
+
+        let loan_fee = mul_div_ceil(
+            self.instruction.amount as u128,
+            fee.get() as u128,
+            BPS_DENOMINATOR as u128,
+        )
+        .ok_or(PinocchioError::MathOverflow)? as u64;
+
+        let vault_target = balance
+            .checked_add(loan_fee)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        Config::load_mut(self.accounts.config)?.start_flash_loan(self.instruction.x, vault_target);
+
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault,
+            mint,
+            to: self.accounts.destination,
+            authority: self.accounts.config,
+            amount: self.instruction.amount,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        Ok(())
+    }
+
+    // a FlashRepay for the same pool has to follow later in the transaction, if it
+    // fails or comes up short the whole transaction and the loan with it revert
+    fn require_repay(&self) -> Result<(), ProgramError> {
+        let instructions = Instructions::try_from(self.accounts.instructions_sysvar)?;
+        let current = instructions.load_current_index() as usize;
+
+        for index in current + 1..instructions.num_instructions() {
+            let instruction = instructions.load_instruction_at(index)?;
+
+            if instruction.get_program_id().ne(&crate::ID)
+                || instruction.get_instruction_data().first() != Some(FlashRepay::DISCRIMINATOR)
+            {
+                continue;
+            }
+
+            if instruction
+                .get_account_meta_at(1)?
+                .key
+                .eq(self.accounts.config.key())
+            {
+                return Ok(());
+            }
+        }
+
+        Err(PinocchioError::FlashRepayMissing.into())
+    }
+}
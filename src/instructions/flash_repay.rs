@@ -0,0 +1,141 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_unique_accounts, gross_transfer_amount, trailing_token_2022_program, vault_amount,
+        AccountCheck, SignerAccount, TokenAccount, TransferChecked, WritableAccount,
+    },
+    state::Config,
+};
+
+// accounts are expected in the same order as the fields below. FlashBorrow finds
+// the repay by the config at index 1, so it must not move
+pub struct FlashRepayAccounts<'a> {
+    pub payer: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    // token account of the borrowed mint the repayment is taken from
+    pub source: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for FlashRepayAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, config, mint_x, mint_y, vault_x, vault_y, source, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(source)?;
+
+        TokenAccount::check(source)?;
+        trailing_token_2022_program(remaining)?;
+
+        assert_unique_accounts(&[payer, config, mint_x, mint_y, vault_x, vault_y, source])?;
+
+        Ok(Self {
+            payer,
+            config,
+            mint_x,
+            mint_y,
+            vault_x,
+            vault_y,
+            source,
+            token_program,
+        })
+    }
+}
+
+pub struct FlashRepay<'a> {
+    pub accounts: FlashRepayAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for FlashRepay<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if !data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = FlashRepayAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> FlashRepay<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &14;
+
+    // tops the lent vault back up to its balance before the borrow plus the loan
+    // fee and clears the loan. whatever already went back to the vault in between
+    // counts towards the repayment
+    pub fn process(&self) -> ProgramResult {
+        let (x, vault_target) = {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+
+            config
+                .flash_loan()
+                .ok_or(ProgramError::InvalidInstructionData)?
+        };
+
+        let (vault, mint) = if x {
+            (self.accounts.vault_x, self.accounts.mint_x)
+        } else {
+            (self.accounts.vault_y, self.accounts.mint_y)
+        };
+
+        let shortfall =
+            vault_target.saturating_sub(vault_amount(vault, self.accounts.config, mint)?);
+
+        if shortfall > 0 {
+            // a transfer fee on the mint comes out of the repayment, so send enough
+            // for the vault to receive the full shortfall
+            let amount = gross_transfer_amount(mint, Clock::get()?.epoch, shortfall)?;
+
+            TransferChecked {
+                from: self.accounts.source,
+                mint,
+                to: vault,
+                authority: self.accounts.payer,
+                amount,
+            }
+            .invoke()?;
+        }
+
+        if vault_amount(vault, self.accounts.config, mint)? < vault_target {
+            return Err(PinocchioError::FlashLoanNotRepaid.into());
+        }
+
+        Config::load_mut(self.accounts.config)?.end_flash_loan();
+
+        Ok(())
+    }
+}
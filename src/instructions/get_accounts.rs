@@ -0,0 +1,256 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::set_return_data,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        find_associated_token_address, read_pubkey, read_u8, token_program_of, AccountCheck,
+        Deposit, MintInterface, Swap, SwapExactOut, Withdraw, NATIVE_MINT, TOKEN_2022_PROGRAM_ID,
+    },
+    pdas::{deposit_permit_address, launch_buys_address, observations_address},
+    state::Config,
+};
+
+// every entry is written as pubkey (32) | is_signer (1) | is_writable (1),
+// user atas are derived under the program owning their mint
+pub const ACCOUNT_META_LEN: usize = 34;
+pub const MAX_ACCOUNT_METAS: usize = 18;
+
+pub struct GetAccountsAccounts<'a> {
+    pub config: &'a AccountInfo,
+    // the mints tell which token program each side of the pool lives under
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetAccountsAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [config, mint_x, mint_y] = accounts else {
+            return Err(ProgramError::InvalidAccountData);
+        };
+
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+
+        Ok(Self {
+            config,
+            mint_x,
+            mint_y,
+        })
+    }
+}
+
+pub struct GetAccountsInstruction {
+    // discriminator of the instruction the account list is built for
+    pub operation: u8,
+    pub user: Pubkey,
+}
+
+pub const GET_ACCOUNTS_DATA_LEN: usize = 33;
+
+impl<'a> TryFrom<&'a [u8]> for GetAccountsInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != GET_ACCOUNTS_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let operation = read_u8(data, 0)?;
+        let user = read_pubkey(data, 1)?;
+
+        Ok(Self { operation, user })
+    }
+}
+
+pub struct GetAccounts<'a> {
+    pub accounts: GetAccountsAccounts<'a>,
+    pub instruction: GetAccountsInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for GetAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = GetAccountsAccounts::try_from(accounts)?;
+        let instruction = GetAccountsInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+struct AccountMetaList {
+    buffer: [u8; ACCOUNT_META_LEN * MAX_ACCOUNT_METAS],
+    len: usize,
+}
+
+impl AccountMetaList {
+    fn new() -> Self {
+        Self {
+            buffer: [0u8; ACCOUNT_META_LEN * MAX_ACCOUNT_METAS],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, key: &Pubkey, is_signer: bool, is_writable: bool) {
+        let entry = &mut self.buffer[self.len..self.len + ACCOUNT_META_LEN];
+        entry[0..32].copy_from_slice(key);
+        entry[32] = is_signer as u8;
+        entry[33] = is_writable as u8;
+        self.len += ACCOUNT_META_LEN;
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl<'a> GetAccounts<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &5;
+
+    // view instruction, writes the ordered account list the requested
+    // instruction expects to the return data so clients don't re-derive pdas
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        let user = &self.instruction.user;
+
+        if config.mint_x().ne(self.accounts.mint_x.key())
+            || config.mint_y().ne(self.accounts.mint_y.key())
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let token_program_x = token_program_of(self.accounts.mint_x);
+        let token_program_y = token_program_of(self.accounts.mint_y);
+        let user_x_ata = find_associated_token_address(user, config.mint_x(), token_program_x);
+        let user_y_ata = find_associated_token_address(user, config.mint_y(), token_program_y);
+        // the lp mint always lives under the legacy token program
+        let user_lp_ata =
+            find_associated_token_address(user, config.lp_mint(), &pinocchio_token::ID);
+
+        let mut metas = AccountMetaList::new();
+
+        match &self.instruction.operation {
+            Deposit::DISCRIMINATOR => {
+                metas.push(user, true, true);
+                metas.push(config.mint_x(), false, false);
+                metas.push(config.mint_y(), false, false);
+                metas.push(config.lp_mint(), false, true);
+                metas.push(self.accounts.config.key(), false, true);
+                metas.push(config.mint_x_vault(), false, true);
+                metas.push(config.mint_y_vault(), false, true);
+                metas.push(&user_x_ata, false, true);
+                metas.push(&user_y_ata, false, true);
+                metas.push(&user_lp_ata, false, true);
+                metas.push(&pinocchio_token::ID, false, false);
+                metas.push(&pinocchio_system::ID, false, false);
+                metas.push(&pinocchio_associated_token_account::ID, false, false);
+
+                if config.whitelist_authority().is_some() {
+                    metas.push(
+                        &deposit_permit_address(self.accounts.config.key(), user).0,
+                        false,
+                        false,
+                    );
+                }
+            }
+            Withdraw::DISCRIMINATOR => {
+                metas.push(user, true, true);
+                metas.push(config.mint_x(), false, false);
+                metas.push(config.mint_y(), false, false);
+                metas.push(config.lp_mint(), false, true);
+                metas.push(config.mint_x_vault(), false, true);
+                metas.push(config.mint_y_vault(), false, true);
+                metas.push(&user_x_ata, false, true);
+                metas.push(&user_y_ata, false, true);
+                metas.push(&user_lp_ata, false, true);
+                metas.push(self.accounts.config.key(), false, true);
+                metas.push(&pinocchio_token::ID, false, false);
+                metas.push(&pinocchio_system::ID, false, false);
+                metas.push(&pinocchio_associated_token_account::ID, false, false);
+            }
+            Swap::DISCRIMINATOR | SwapExactOut::DISCRIMINATOR => {
+                // sol is paid in and out of the user's wallet on a native mint pool
+                let native = config.mint_x() == &NATIVE_MINT || config.mint_y() == &NATIVE_MINT;
+                // buys in a capped launch window are counted in the user's LaunchBuys
+                // account, which the user pays for on their first buy
+                let launch_buys = config
+                    .launch_guard(Clock::get()?.unix_timestamp)
+                    .is_some_and(|launch_guard| launch_guard.wallet_cap != 0);
+
+                metas.push(user, true, native || launch_buys);
+                metas.push(config.mint_x(), false, false);
+                metas.push(config.mint_y(), false, false);
+                metas.push(config.lp_mint(), false, false);
+                metas.push(self.accounts.config.key(), false, true);
+                metas.push(config.mint_x_vault(), false, true);
+                metas.push(config.mint_y_vault(), false, true);
+                metas.push(&user_x_ata, false, true);
+                metas.push(&user_y_ata, false, true);
+                metas.push(&pinocchio_token::ID, false, false);
+
+                if native || launch_buys {
+                    metas.push(&pinocchio_system::ID, false, false);
+                }
+                if native {
+                    metas.push(&pinocchio_associated_token_account::ID, false, false);
+                }
+                if launch_buys {
+                    metas.push(
+                        &launch_buys_address(self.accounts.config.key(), user).0,
+                        false,
+                        true,
+                    );
+                }
+                if config.observations_required() {
+                    metas.push(
+                        &observations_address(self.accounts.config.key()).0,
+                        false,
+                        true,
+                    );
+                }
+                // the royalty is paid in whichever token goes in
+                if let Some(destination) = config.royalty_destination() {
+                    metas.push(
+                        &find_associated_token_address(
+                            &destination,
+                            config.mint_x(),
+                            token_program_x,
+                        ),
+                        false,
+                        true,
+                    );
+                    metas.push(
+                        &find_associated_token_address(
+                            &destination,
+                            config.mint_y(),
+                            token_program_y,
+                        ),
+                        false,
+                        true,
+                    );
+                }
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+
+        // instructions moving a token-2022 mint take its program as their last account
+        if token_program_x == &TOKEN_2022_PROGRAM_ID || token_program_y == &TOKEN_2022_PROGRAM_ID {
+            metas.push(&TOKEN_2022_PROGRAM_ID, false, false);
+        }
+
+        set_return_data(metas.as_bytes());
+
+        Ok(())
+    }
+}
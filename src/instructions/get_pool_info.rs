@@ -0,0 +1,68 @@
+use pinocchio::{
+    account_info::AccountInfo, cpi::set_return_data, program_error::ProgramError, ProgramResult,
+};
+
+use crate::state::Config;
+
+// the return data is written little endian as fee_growth_x (16) | fee_growth_y (16)
+// | locked_lp_amount (8) | locked_lp (8). the fee growth is the Q64 fees per lp unit,
+// see Config::fee_growth. locked_lp_amount is the lp held in LpLocks and locked_lp
+// the liquidity the first deposit locked for good, both are part of the lp supply
+pub const POOL_INFO_LEN: usize = 48;
+
+pub struct GetPoolInfoAccounts<'a> {
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for GetPoolInfoAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        Ok(Self { config })
+    }
+}
+
+pub const GET_POOL_INFO_DATA_LEN: usize = 0;
+
+pub struct GetPoolInfo<'a> {
+    pub accounts: GetPoolInfoAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for GetPoolInfo<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if data.len() != GET_POOL_INFO_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = GetPoolInfoAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> GetPoolInfo<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &19;
+
+    // view instruction, writes the pool's fee growth checkpoints and locked lp to the
+    // return data so integrations can value lp from the chain without replaying swaps
+    pub fn process(&self) -> ProgramResult {
+        let config = Config::load(self.accounts.config)?;
+        let (fee_growth_x, fee_growth_y) = config.fee_growth();
+
+        let mut info = [0u8; POOL_INFO_LEN];
+        info[0..16].copy_from_slice(&fee_growth_x.to_le_bytes());
+        info[16..32].copy_from_slice(&fee_growth_y.to_le_bytes());
+        info[32..40].copy_from_slice(&config.locked_lp_amount().get().to_le_bytes());
+        info[40..48].copy_from_slice(&config.locked_lp().get().to_le_bytes());
+
+        set_return_data(&info);
+
+        Ok(())
+    }
+}
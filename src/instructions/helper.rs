@@ -1,12 +1,94 @@
 use pinocchio::{
     account_info::AccountInfo,
+    cpi::get_stack_height,
     instruction::{Seed, Signer},
     program_error::ProgramError,
-    pubkey::find_program_address,
+    pubkey::{find_program_address, Pubkey},
     sysvars::{rent::Rent, Sysvar},
 };
 use pinocchio_system::instructions::CreateAccount;
-use pinocchio_token::state::Mint;
+use pinocchio_token::{instructions::SyncNative, state::Mint};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{CloseAccount, TokenAccountData},
+};
+
+// Token-2022 program id (TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb)
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = [
+    0x06, 0xdd, 0xf6, 0xe1, 0xee, 0x75, 0x8f, 0xde, 0x18, 0x42, 0x5d, 0xbc, 0xe4, 0x6c, 0xcd, 0xda,
+    0xb6, 0x1a, 0xfc, 0x4d, 0x83, 0xb9, 0x0d, 0x27, 0xfe, 0xbd, 0xf9, 0x28, 0xd8, 0xa1, 0x8b, 0xfc,
+];
+
+// native mint (So11111111111111111111111111111111111111112), wrapped sol only exists
+// under the legacy token program
+pub const NATIVE_MINT: Pubkey = [
+    0x06, 0x9b, 0x88, 0x57, 0xfe, 0xab, 0x81, 0x84, 0xfb, 0x68, 0x7f, 0x63, 0x46, 0x18, 0xc0, 0x35,
+    0xda, 0xc4, 0x39, 0xdc, 0x1a, 0xeb, 0x3b, 0x55, 0x98, 0xa0, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x01,
+];
+
+// SlotHashes sysvar id (SysvarS1otHashes111111111111111111111111111)
+pub const SLOT_HASHES_SYSVAR_ID: Pubkey = [
+    0x06, 0xa7, 0xd5, 0x17, 0x19, 0x2f, 0x0a, 0xaf, 0xc6, 0xf2, 0x65, 0xe3, 0xfb, 0x77, 0xcc, 0x7a,
+    0xda, 0x82, 0xc5, 0x29, 0xd0, 0xbe, 0x3b, 0x13, 0x6e, 0x2d, 0x00, 0x55, 0x20, 0x00, 0x00, 0x00,
+];
+
+// SlotHashes data is a u64 entry count followed by (slot u64, hash [u8; 32])
+// entries, newest slot first
+const SLOT_HASH_ENTRY_LEN: usize = 8 + 32;
+
+// offset of the is_initialized flag in the base mint layout
+const MINT_IS_INITIALIZED_OFFSET: usize = 45;
+// account type byte of an extended token-2022 mint
+const MINT_ACCOUNT_TYPE_OFFSET: usize = pinocchio_token::state::TokenAccount::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+
+// token-2022 extensions follow the base layout padded to the token account size,
+// then a single account type byte
+const EXTENSIONS_OFFSET: usize = pinocchio_token::state::TokenAccount::LEN + 1;
+// extension type of TransferFeeConfig in the token-2022 tlv list
+const TRANSFER_FEE_CONFIG_EXTENSION: u16 = 1;
+// TransferFeeConfig: two authorities and the withheld amount precede the fees
+const OLDER_TRANSFER_FEE_OFFSET: usize = 32 + 32 + 8;
+const TRANSFER_FEE_LEN: usize = 8 + 8 + 2;
+const MAX_FEE_BASIS_POINTS: u64 = 10_000;
+
+// whether the SlotHashes sysvar holds a hash for `slot`, which only the last 512
+// slots of the fork the transaction lands on do
+pub fn slot_hash_exists(slot_hashes: &AccountInfo, slot: u64) -> Result<bool, ProgramError> {
+    if slot_hashes.key() != &SLOT_HASHES_SYSVAR_ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    slot_hashes_contain(&slot_hashes.try_borrow_data()?, slot)
+}
+
+// binary search over the raw sysvar data, see SLOT_HASH_ENTRY_LEN
+pub fn slot_hashes_contain(data: &[u8], slot: u64) -> Result<bool, ProgramError> {
+    let read_slot = |offset: usize| {
+        data.get(offset..offset + size_of::<u64>())
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(ProgramError::InvalidAccountData)
+    };
+
+    let (mut low, mut high) = (0, read_slot(0)? as usize);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        let entry_slot = read_slot(size_of::<u64>() + mid * SLOT_HASH_ENTRY_LEN)?;
+
+        if entry_slot == slot {
+            return Ok(true);
+        }
+        // newest first, older slots sit further in
+        if entry_slot > slot {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    Ok(false)
+}
 
 pub trait AccountCheck {
     fn check(account: &AccountInfo) -> Result<(), ProgramError>;
@@ -23,30 +105,340 @@ impl AccountCheck for SignerAccount {
     }
 }
 
+pub struct WritableAccount;
+// account checks for accounts the instruction mutates
+impl AccountCheck for WritableAccount {
+    fn check(account: &AccountInfo) -> Result<(), ProgramError> {
+        if !account.is_writable() {
+            return Err(ProgramError::Immutable);
+        }
+        Ok(())
+    }
+}
+
 pub struct MintInterface;
-// mint accounts checks
+// mint accounts checks, accepts legacy mints and token-2022 mints (which may
+// carry extensions past the base layout)
 impl AccountCheck for MintInterface {
     fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if account.data_len() != Mint::LEN {
-            return Err(ProgramError::InvalidAccountData);
+        if account.is_owned_by(&pinocchio_token::ID) {
+            if account.data_len() != Mint::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if account.is_owned_by(&TOKEN_2022_PROGRAM_ID) {
+            // an extended mint is padded to the token account length and tagged right
+            // after it, which keeps a token account from passing as a mint
+            let len = account.data_len();
+            if len != Mint::LEN
+                && (len <= pinocchio_token::state::TokenAccount::LEN
+                    || account.try_borrow_data()?[MINT_ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_MINT)
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        if account.try_borrow_data()?[MINT_IS_INITIALIZED_OFFSET] != 1 {
+            return Err(ProgramError::UninitializedAccount);
         }
         Ok(())
     }
 }
 
 pub struct TokenAccount;
-// token accounts checks
+// token accounts checks, token-2022 accounts may carry extensions past the base layout
 impl AccountCheck for TokenAccount {
     fn check(account: &AccountInfo) -> Result<(), ProgramError> {
-        if !account.is_owned_by(&pinocchio_token::ID) {
-            return Err(ProgramError::IllegalOwner.into());
+        if account.is_owned_by(&pinocchio_token::ID) {
+            if account.data_len() != pinocchio_token::state::TokenAccount::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else if account.is_owned_by(&TOKEN_2022_PROGRAM_ID) {
+            if account.data_len() < pinocchio_token::state::TokenAccount::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        } else {
+            return Err(ProgramError::IllegalOwner);
         }
+        Ok(())
+    }
+}
 
-        if account.data_len() != pinocchio_token::state::TokenAccount::LEN {
-            return Err(ProgramError::InvalidAccountData);
+// the token program owning an account that already passed a token account or mint check
+#[inline(always)]
+pub fn token_program_of(account: &AccountInfo) -> &'static Pubkey {
+    if account.is_owned_by(&TOKEN_2022_PROGRAM_ID) {
+        &TOKEN_2022_PROGRAM_ID
+    } else {
+        &pinocchio_token::ID
+    }
+}
+
+// transfer fee a token-2022 mint charges in the current epoch
+pub struct TransferFee {
+    pub maximum_fee: u64,
+    pub basis_points: u16,
+}
+
+impl TransferFee {
+    // amount to send so that `net` arrives after the fee is withheld
+    pub fn gross_amount(&self, net: u64) -> Result<u64, ProgramError> {
+        if self.basis_points == 0 || net == 0 {
+            return Ok(net);
         }
-        Ok(())
+
+        let fee = if self.basis_points as u64 >= MAX_FEE_BASIS_POINTS {
+            self.maximum_fee
+        } else {
+            let fee = (net as u128)
+                .checked_mul(self.basis_points as u128)
+                .ok_or(PinocchioError::MathOverflow)?
+                .div_ceil((MAX_FEE_BASIS_POINTS - self.basis_points as u64) as u128);
+
+            u64::try_from(fee)
+                .map_err(|_| PinocchioError::MathOverflow)?
+                .min(self.maximum_fee)
+        };
+
+        Ok(net.checked_add(fee).ok_or(PinocchioError::MathOverflow)?)
+    }
+
+    // amount that arrives when `gross` is sent, the token program rounds its fee up
+    pub fn net_amount(&self, gross: u64) -> Result<u64, ProgramError> {
+        if self.basis_points == 0 || gross == 0 {
+            return Ok(gross);
+        }
+
+        let fee = (gross as u128)
+            .checked_mul(self.basis_points as u128)
+            .ok_or(PinocchioError::MathOverflow)?
+            .div_ceil(MAX_FEE_BASIS_POINTS as u128);
+        let fee = u64::try_from(fee)
+            .map_err(|_| PinocchioError::MathOverflow)?
+            .min(self.maximum_fee);
+
+        Ok(gross.saturating_sub(fee))
+    }
+}
+
+// walks the token-2022 extension list of `mint` for a TransferFeeConfig and returns the
+// fee in force at `epoch`, legacy mints and mints without the extension have no fee
+pub fn transfer_fee(mint: &AccountInfo, epoch: u64) -> Result<Option<TransferFee>, ProgramError> {
+    if !mint.is_owned_by(&TOKEN_2022_PROGRAM_ID) {
+        return Ok(None);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let mut offset = EXTENSIONS_OFFSET;
+
+    while let (Some(extension_type), Some(length)) = (
+        read_bytes(&data, offset).map(u16::from_le_bytes),
+        read_bytes(&data, offset + 2).map(u16::from_le_bytes),
+    ) {
+        let length = length as usize;
+        // an uninitialized entry marks the end of the list
+        if extension_type == 0 {
+            break;
+        }
+
+        let value = data
+            .get(offset + 4..offset + 4 + length)
+            .ok_or(ProgramError::InvalidAccountData)?;
+
+        if extension_type == TRANSFER_FEE_CONFIG_EXTENSION {
+            if value.len() < OLDER_TRANSFER_FEE_OFFSET + TRANSFER_FEE_LEN * 2 {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            // the newer fee replaces the older one from its epoch onwards
+            let newer = &value[OLDER_TRANSFER_FEE_OFFSET + TRANSFER_FEE_LEN..];
+            let newer_epoch = read_bytes(newer, 0).map(u64::from_le_bytes);
+            let fee = if epoch >= newer_epoch.ok_or(ProgramError::InvalidAccountData)? {
+                newer
+            } else {
+                &value[OLDER_TRANSFER_FEE_OFFSET..]
+            };
+
+            return Ok(Some(TransferFee {
+                maximum_fee: read_bytes(fee, 8)
+                    .map(u64::from_le_bytes)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+                basis_points: read_bytes(fee, 16)
+                    .map(u16::from_le_bytes)
+                    .ok_or(ProgramError::InvalidAccountData)?,
+            }));
+        }
+
+        offset += 4 + length;
+    }
+
+    Ok(None)
+}
+
+// amount to send from a `mint` account so that `net` arrives after any transfer fee
+pub fn gross_transfer_amount(
+    mint: &AccountInfo,
+    epoch: u64,
+    net: u64,
+) -> Result<u64, ProgramError> {
+    match transfer_fee(mint, epoch)? {
+        Some(transfer_fee) => transfer_fee.gross_amount(net),
+        None => Ok(net),
+    }
+}
+
+// amount that arrives when `gross` is sent from a `mint` account
+pub fn net_transfer_amount(
+    mint: &AccountInfo,
+    epoch: u64,
+    gross: u64,
+) -> Result<u64, ProgramError> {
+    match transfer_fee(mint, epoch)? {
+        Some(transfer_fee) => transfer_fee.net_amount(gross),
+        None => Ok(gross),
+    }
+}
+
+// the same account passed for two roles (e.g. user ata == vault) must never reach
+// the processors, every account in the list has to be distinct
+pub fn assert_unique_accounts(accounts: &[&AccountInfo]) -> Result<(), ProgramError> {
+    for (i, account) in accounts.iter().enumerate() {
+        if accounts[i + 1..]
+            .iter()
+            .any(|other| other.key() == account.key())
+        {
+            return Err(PinocchioError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+// balance of a pool vault, after checking it belongs to `config` for `mint`. a vault
+// frozen by its mint's freeze authority can't move tokens, so that is reported up
+// front instead of as an opaque token program failure on the transfer
+pub fn vault_amount(
+    vault: &AccountInfo,
+    config: &AccountInfo,
+    mint: &AccountInfo,
+) -> Result<u64, ProgramError> {
+    let vault = TokenAccountData::from_account_info(vault)?;
+
+    if vault.owner() != config.key() {
+        return Err(PinocchioError::InvalidOwner.into());
+    }
+
+    if vault.mint() != mint.key() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if vault.is_frozen() {
+        return Err(PinocchioError::VaultFrozen.into());
+    }
+
+    Ok(vault.amount())
+}
+
+// pool accounts that dropped below rent exemption are about to be purged,
+// so handlers refuse to keep operating on them
+#[inline(always)]
+pub fn account_is_rent_exempt(account: &AccountInfo, rent: &Rent) -> bool {
+    rent.is_exempt(account.lamports(), account.data_len())
+}
+
+// stack height of an instruction invoked directly by the transaction, every cpi
+// adds one
+pub const TRANSACTION_LEVEL_STACK_HEIGHT: usize = 1;
+
+// the handler has to run as a top level instruction. the instructions sysvar only
+// says which program the transaction called, which is still this program when a
+// cpi back into it comes from another of its instructions
+pub fn assert_top_level() -> Result<(), ProgramError> {
+    if get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(PinocchioError::CpiNotAllowed.into());
+    }
+    Ok(())
+}
+
+// fixed size read at `offset`, None when `data` is too short
+#[inline(always)]
+pub(crate) fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    data.get(offset..offset.checked_add(N)?)?.try_into().ok()
+}
+
+// little endian readers for instruction data. a read past the end is reported as
+// InvalidInstructionData instead of panicking, so no byte string a client sends can
+// abort the program with an opaque error
+#[inline(always)]
+pub fn read_u8(data: &[u8], offset: usize) -> Result<u8, ProgramError> {
+    data.get(offset)
+        .copied()
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+// a flag byte, anything but 0 or 1 is rejected
+#[inline(always)]
+pub fn read_bool(data: &[u8], offset: usize) -> Result<bool, ProgramError> {
+    match read_u8(data, offset)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+#[inline(always)]
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16, ProgramError> {
+    read_bytes(data, offset)
+        .map(u16::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+#[inline(always)]
+pub fn read_u64(data: &[u8], offset: usize) -> Result<u64, ProgramError> {
+    read_bytes(data, offset)
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+#[inline(always)]
+pub fn read_i64(data: &[u8], offset: usize) -> Result<i64, ProgramError> {
+    read_bytes(data, offset)
+        .map(i64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+#[inline(always)]
+pub fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey, ProgramError> {
+    read_bytes(data, offset).ok_or(ProgramError::InvalidInstructionData)
+}
+
+// optional features append their accounts after the fixed ones so the base path
+// stays as small as possible. bit i of `flags` (taken from the instruction data)
+// says whether feature i is used, present accounts follow in bit order and every
+// trailing account must be claimed by a set bit
+pub fn optional_accounts<const N: usize>(
+    remaining: &[AccountInfo],
+    flags: u8,
+) -> Result<[Option<&AccountInfo>; N], ProgramError> {
+    if flags.checked_shr(N as u32).unwrap_or(0) != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if remaining.len() < flags.count_ones() as usize {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    if remaining.len() > flags.count_ones() as usize {
+        return Err(ProgramError::InvalidAccountData);
     }
+
+    let mut accounts = remaining.iter();
+    let mut optional = [None; N];
+    for (bit, account) in optional.iter_mut().enumerate() {
+        if flags.checked_shr(bit as u32).unwrap_or(0) & 1 != 0 {
+            *account = accounts.next();
+        }
+    }
+    Ok(optional)
 }
 
 pub trait ProgramAccountInit {
@@ -79,7 +471,7 @@ impl ProgramAccountInit for ProgramAccount {
             to: account,
             lamports: rent_excempt,
             space: space as u64,
-            owner: account.owner(),
+            owner: &crate::ID,
         }
         .invoke_signed(&signer)?;
         Ok(())
@@ -118,6 +510,16 @@ pub trait AssociatedTokenAccountInit {
 
 pub struct AssociatedTokenAccount;
 
+// ata address of `wallet` for `mint` under the given token program
+pub fn find_associated_token_address(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    let seeds: &[&[u8]] = &[wallet, token_program, mint];
+    find_program_address(seeds, &pinocchio_associated_token_account::ID).0
+}
+
 impl AssociatedTokenAccountCheck for AssociatedTokenAccount {
     fn check(
         account: &AccountInfo,
@@ -126,10 +528,10 @@ impl AssociatedTokenAccountCheck for AssociatedTokenAccount {
     ) -> Result<(), ProgramError> {
         TokenAccount::check(account)?;
 
-        let seeds: &[&[u8]] = &[authority.key(), &pinocchio_token::ID, mint.key()];
+        // the ata seeds include the token program, so derive with whichever one owns it
+        let token_program = token_program_of(account);
 
-        if find_program_address(seeds, &pinocchio_associated_token_account::ID)
-            .0
+        if find_associated_token_address(authority.key(), mint.key(), token_program)
             .ne(account.key())
         {
             return Err(ProgramError::InvalidAccountData);
@@ -167,11 +569,89 @@ impl AssociatedTokenAccountInit for AssociatedTokenAccount {
         system_program: &AccountInfo,
         token_program: &AccountInfo,
     ) -> Result<(), ProgramError> {
-        // checking the ata is initialized or not
-        match Self::check(ata, authority, mint) {
-            Ok(_) => Ok(()),
-            Err(_) => Self::init(ata, mint, authority, owner, system_program, token_program),
-        }?;
-        Ok(())
+        // only an account that was never created (no data, still system owned) gets
+        // created, an existing account that fails the ata check is rejected instead
+        // of masking a wrong address behind a failing create
+        if ata.data_len() == 0 && ata.is_owned_by(&pinocchio_system::ID) {
+            return Self::init(ata, mint, authority, owner, system_program, token_program);
+        }
+
+        // the ata is derived from the wallet that owns it, not from the payer
+        Self::check(ata, owner, mint).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+#[inline(always)]
+pub fn is_native_mint(mint: &AccountInfo) -> bool {
+    mint.key() == &NATIVE_MINT
+}
+
+// a wsol ata the user doesn't have yet only lives for the instruction: it is created,
+// wraps or receives the sol, and is closed back to the user at the end so sol moves in
+// and out directly. an existing wsol ata is left open
+#[inline(always)]
+pub fn is_temporary_wsol(ata: &AccountInfo, mint: &AccountInfo) -> bool {
+    is_native_mint(mint) && ata.data_len() == 0 && ata.is_owned_by(&pinocchio_system::ID)
+}
+
+// pays the native side of an instruction in sol. a temporary wsol ata is created
+// first, then the user's lamports cover whatever the ata holds short of `amount`.
+// the system program is only needed when either happens. returns whether the ata is
+// temporary and has to be closed with unwrap_sol
+pub fn wrap_sol(
+    user: &AccountInfo,
+    ata: &AccountInfo,
+    mint: &AccountInfo,
+    amount: u64,
+    system_program: Option<&AccountInfo>,
+    token_program: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    let temporary = create_temporary_wsol(user, ata, mint, system_program, token_program)?;
+
+    let balance = TokenAccountData::from_account_info(ata)?.amount();
+    if balance < amount {
+        WritableAccount::check(user)?;
+        system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        pinocchio_system::instructions::Transfer {
+            from: user,
+            to: ata,
+            lamports: amount - balance,
+        }
+        .invoke()?;
+
+        SyncNative { native_token: ata }.invoke()?;
+    }
+
+    Ok(temporary)
+}
+
+// creates the user's wsol ata when it is temporary, returns whether it was
+pub fn create_temporary_wsol(
+    user: &AccountInfo,
+    ata: &AccountInfo,
+    mint: &AccountInfo,
+    system_program: Option<&AccountInfo>,
+    token_program: &AccountInfo,
+) -> Result<bool, ProgramError> {
+    if !is_temporary_wsol(ata, mint) {
+        return Ok(false);
+    }
+
+    // the user pays the rent and gets it back when the ata is closed
+    WritableAccount::check(user)?;
+    let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    AssociatedTokenAccount::init(ata, mint, user, user, system_program, token_program)?;
+    Ok(true)
+}
+
+// closes a temporary wsol ata, its balance and rent go back to the user as sol
+pub fn unwrap_sol(user: &AccountInfo, ata: &AccountInfo) -> Result<(), ProgramError> {
+    CloseAccount {
+        account: ata,
+        destination: user,
+        authority: user,
     }
+    .invoke()
 }
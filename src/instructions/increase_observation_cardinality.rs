@@ -0,0 +1,170 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Seed,
+    program_error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
+};
+use pinocchio_system::instructions::Transfer;
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, read_u16, AccountCheck, ProgramAccount, ProgramAccountInit,
+        SignerAccount, WritableAccount,
+    },
+    pdas::{observations_address, OBSERVATIONS_SEED},
+    state::{Config, Observations, MAX_OBSERVATIONS},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct IncreaseObservationCardinalityAccounts<'a> {
+    // anyone may pay for a longer oracle window
+    pub payer: &'a AccountInfo,
+    // records that trades have to pass the buffer from now on
+    pub config: &'a AccountInfo,
+    pub observations: &'a AccountInfo,
+
+    pub system_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for IncreaseObservationCardinalityAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [payer, config, observations, system_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(payer)?;
+        WritableAccount::check(payer)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(observations)?;
+
+        assert_unique_accounts(&[payer, config, observations])?;
+
+        Ok(Self {
+            payer,
+            config,
+            observations,
+            system_program,
+        })
+    }
+}
+
+pub struct IncreaseObservationCardinalityInstruction {
+    pub cardinality: u16,
+}
+
+pub const INCREASE_OBSERVATION_CARDINALITY_DATA_LEN: usize = 2;
+
+impl<'a> TryFrom<&'a [u8]> for IncreaseObservationCardinalityInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != INCREASE_OBSERVATION_CARDINALITY_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let cardinality = read_u16(data, 0)?;
+
+        if cardinality == 0 || cardinality > MAX_OBSERVATIONS {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self { cardinality })
+    }
+}
+
+pub struct IncreaseObservationCardinality<'a> {
+    pub accounts: IncreaseObservationCardinalityAccounts<'a>,
+    pub instruction: IncreaseObservationCardinalityInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for IncreaseObservationCardinality<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = IncreaseObservationCardinalityAccounts::try_from(accounts)?;
+        let instruction = IncreaseObservationCardinalityInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> IncreaseObservationCardinality<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &12;
+
+    // creates the pool's observation buffer, or grows it to `cardinality` slots.
+    // either way every later trade on the pool has to pass the buffer
+    pub fn process(&self) -> ProgramResult {
+        {
+            let mut config = Config::load_mut(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+            config.require_observations();
+        }
+
+        if self.accounts.observations.data_is_empty() {
+            return self.create();
+        }
+
+        {
+            let mut observations = Observations::load_mut(self.accounts.observations)?;
+            observations.verify_address(self.accounts.observations, self.accounts.config)?;
+
+            if self.instruction.cardinality <= observations.cardinality_next() {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            observations.set_cardinality_next(self.instruction.cardinality);
+        }
+
+        // the new slots are zeroed, which reads as never written
+        let space = Observations::space(self.instruction.cardinality);
+        self.accounts.observations.resize(space)?;
+
+        let rent_exempt = Rent::get()?.minimum_balance(space);
+        let lamports = self.accounts.observations.lamports();
+        if lamports < rent_exempt {
+            Transfer {
+                from: self.accounts.payer,
+                to: self.accounts.observations,
+                lamports: rent_exempt - lamports,
+            }
+            .invoke()?;
+        }
+
+        Ok(())
+    }
+
+    fn create(&self) -> ProgramResult {
+        // searched for once here, the buffer stores the canonical bump
+        let (address, bump) = observations_address(self.accounts.config.key());
+        if address.ne(self.accounts.observations.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let bump_bindings = [bump];
+        let seeds = [
+            Seed::from(OBSERVATIONS_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(&bump_bindings),
+        ];
+
+        ProgramAccount::init::<Observations>(
+            self.accounts.payer,
+            self.accounts.observations,
+            &seeds,
+            Observations::space(self.instruction.cardinality),
+        )?;
+
+        Observations::init(
+            self.accounts.observations,
+            *self.accounts.config.key(),
+            bump,
+            self.instruction.cardinality,
+        )
+    }
+}
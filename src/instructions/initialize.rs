@@ -1,15 +1,27 @@
 use pinocchio::{
-    account_info::AccountInfo, instruction::Seed, program_error::ProgramError, ProgramResult,
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{rent::Rent, Sysvar},
+    ProgramResult,
 };
+use pinocchio_system::instructions::CreateAccount;
+use pinocchio_token::{instructions::InitializeMint2, state::Mint};
 
 use crate::{
     instructions::{
-        AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountInit, MintInterface,
-        ProgramAccount, ProgramAccountInit, SignerAccount,
+        assert_unique_accounts, read_i64, read_u16, read_u64, read_u8, split_token_2022_program,
+        token_program_account, AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
+        AssociatedTokenAccountInit, LpMetadataAccounts, MintInterface, ProgramAccount,
+        ProgramAccountInit, SignerAccount, WritableAccount,
     },
-    state::Config,
+    pdas::{lp_mint_address, LP_MINT_SEED},
+    state::{Config, CurveType, PriceBand, MAX_FEE_BPS},
 };
 
+pub const LP_MINT_DECIMALS: u8 = 6;
+
+// accounts are expected in the same order as the fields below
 pub struct InitializeConfigAccounts<'a> {
     pub authority: &'a AccountInfo,
     pub config: &'a AccountInfo,
@@ -25,19 +37,31 @@ pub struct InitializeConfigAccounts<'a> {
     pub token_program: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
     pub associated_token_program: &'a AccountInfo,
+
+    // optional trailing accounts, when supplied the lp mint gets metaplex metadata
+    // named after the pool mints' symbols
+    pub lp_metadata: Option<LpMetadataAccounts<'a>>,
+    // comes last, needed when a pool mint is under the token-2022 program
+    pub token_2022_program: Option<&'a AccountInfo>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for InitializeConfigAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [authority, config, mint_x, mint_y, vault_x, vault_y, lp_mint, token_program, system_program, associated_token_program] =
+        let [authority, config, mint_x, mint_y, vault_x, vault_y, lp_mint, token_program, system_program, associated_token_program, remaining @ ..] =
             accounts
         else {
             return Err(ProgramError::InvalidAccountData);
         };
 
         SignerAccount::check(authority)?;
+        WritableAccount::check(authority)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(lp_mint)?;
+
         MintInterface::check(mint_x)?;
         MintInterface::check(mint_y)?;
 
@@ -45,6 +69,15 @@ impl<'a> TryFrom<&'a [AccountInfo]> for InitializeConfigAccounts<'a> {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        assert_unique_accounts(&[authority, config, mint_x, mint_y, vault_x, vault_y, lp_mint])?;
+
+        let (token_2022_program, remaining) = split_token_2022_program(remaining);
+        let lp_metadata = if remaining.is_empty() {
+            None
+        } else {
+            Some(LpMetadataAccounts::try_from(remaining)?)
+        };
+
         Ok(Self {
             authority,
             config,
@@ -56,6 +89,8 @@ impl<'a> TryFrom<&'a [AccountInfo]> for InitializeConfigAccounts<'a> {
             token_program,
             system_program,
             associated_token_program,
+            lp_metadata,
+            token_2022_program,
         })
     }
 }
@@ -63,29 +98,83 @@ impl<'a> TryFrom<&'a [AccountInfo]> for InitializeConfigAccounts<'a> {
 pub struct InitializeConfigInstruction {
     pub fee: u16,
     pub config_bump: u8,
+    pub min_lp_mint: u64,
+    pub min_lp_burn: u64,
+    pub trading_start_ts: i64,
+    pub max_lp_supply: u64,
+    pub seed: u64,
+    pub curve_type: CurveType,
+    pub price_band: PriceBand,
 }
 
+// instruction data length with no optional fields, with the curve fields, and with
+// the curve fields followed by a price band
+pub const INITIALIZE_CONFIG_DATA_LEN: usize = 43;
+pub const INITIALIZE_CONFIG_CURVE_DATA_LEN: usize = 52;
+pub const INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN: usize = 68;
+
 impl<'a> TryFrom<&'a [u8]> for InitializeConfigInstruction {
     type Error = ProgramError;
 
     fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
-        if data.len() < 2 {
-            return Err(ProgramError::InvalidAccountData);
+        // the curve type (u8) and amplification (u64) are optional, pools created
+        // without them use the constant product curve. a price band (min, max as u64)
+        // may follow the curve fields, without it the price is unbounded
+        if data.len() != INITIALIZE_CONFIG_DATA_LEN
+            && data.len() != INITIALIZE_CONFIG_CURVE_DATA_LEN
+            && data.len() != INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN
+        {
+            return Err(ProgramError::InvalidInstructionData);
         };
 
-        let fee = u16::from_le_bytes(data[0..2].try_into().unwrap());
-        let config_bump = u8::from_le_bytes([data[2]]);
+        let fee = read_u16(data, 0)?;
+        let config_bump = read_u8(data, 2)?;
+        let min_lp_mint = read_u64(data, 3)?;
+        let min_lp_burn = read_u64(data, 11)?;
+        let trading_start_ts = read_i64(data, 19)?;
+        let max_lp_supply = read_u64(data, 27)?;
+        let seed = read_u64(data, 35)?;
+
+        let curve_type = if data.len() >= INITIALIZE_CONFIG_CURVE_DATA_LEN {
+            CurveType::new(read_u8(data, 43)?, read_u64(data, 44)?)?
+        } else {
+            CurveType::ConstantProduct
+        };
 
-        if fee > 1000 {
+        let price_band = if data.len() == INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN {
+            PriceBand {
+                min_price: read_u64(data, 52)?,
+                max_price: read_u64(data, 60)?,
+            }
+        } else {
+            PriceBand {
+                min_price: 0,
+                max_price: 0,
+            }
+        };
+        price_band.validate()?;
+
+        if fee > MAX_FEE_BPS {
             return Err(ProgramError::InvalidAccountData);
         };
-        Ok(Self { fee, config_bump })
+        Ok(Self {
+            fee,
+            config_bump,
+            min_lp_mint,
+            min_lp_burn,
+            trading_start_ts,
+            max_lp_supply,
+            seed,
+            curve_type,
+            price_band,
+        })
     }
 }
 
 pub struct InitializeConfig<'a> {
     pub accounts: InitializeConfigAccounts<'a>,
     pub instruction: InitializeConfigInstruction,
+    pub lp_bump: u8,
 }
 
 impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for InitializeConfig<'a> {
@@ -96,8 +185,15 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for InitializeConfig<'a> {
         let instruction = InitializeConfigInstruction::try_from(value.1)?;
 
         // seeds for the config account
+        let seed_bindings = instruction.seed.to_le_bytes();
         let config_bindings = instruction.config_bump.to_le_bytes();
-        let config_seeds = [Seed::from(b"config"), Seed::from(&config_bindings)];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(accounts.mint_x.key().as_ref()),
+            Seed::from(accounts.mint_y.key().as_ref()),
+            Seed::from(&seed_bindings),
+            Seed::from(&config_bindings),
+        ];
 
         // creation of the config account
         ProgramAccount::init::<Config>(
@@ -107,19 +203,50 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for InitializeConfig<'a> {
             Config::LEN,
         )?;
 
+        // searched for once here, every later instruction uses the stored bump
+        let (expected_lp_mint, lp_bump) = lp_mint_address(accounts.config.key());
+        if expected_lp_mint.ne(accounts.lp_mint.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
         // seeds for the lp mint account
+        let lp_bump_bindings = [lp_bump];
         let lp_mint_seeds = [
-            Seed::from(b"lp_mint"),
+            Seed::from(LP_MINT_SEED),
             Seed::from(accounts.config.key().as_ref()),
+            Seed::from(&lp_bump_bindings),
         ];
 
-        // creation of the lp mint account
-        ProgramAccount::init::<pinocchio_token::state::Mint>(
-            accounts.authority,
-            accounts.lp_mint,
-            &lp_mint_seeds,
-            pinocchio_token::state::Mint::LEN,
-        )?;
+        // creation of the lp mint account, owned by the token program with the
+        // config pda as its mint authority
+        CreateAccount {
+            from: accounts.authority,
+            to: accounts.lp_mint,
+            lamports: Rent::get()?.minimum_balance(Mint::LEN),
+            space: Mint::LEN as u64,
+            owner: &pinocchio_token::ID,
+        }
+        .invoke_signed(&[Signer::from(&lp_mint_seeds)])?;
+
+        InitializeMint2 {
+            mint: accounts.lp_mint,
+            decimals: LP_MINT_DECIMALS,
+            mint_authority: accounts.config.key(),
+            freeze_authority: None,
+        }
+        .invoke()?;
+
+        // the config pda is the lp mint authority, so it signs for the metadata too
+        if let Some(lp_metadata) = &accounts.lp_metadata {
+            lp_metadata.create(
+                accounts.lp_mint,
+                (accounts.mint_x, accounts.mint_y),
+                accounts.config,
+                accounts.authority,
+                accounts.system_program,
+                &[Signer::from(&config_seeds)],
+            )?;
+        }
 
         // creation of vault_x associated token account
         AssociatedTokenAccount::init(
@@ -128,7 +255,11 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for InitializeConfig<'a> {
             accounts.authority,
             accounts.config,
             accounts.system_program,
-            accounts.token_program,
+            token_program_account(
+                accounts.mint_x,
+                accounts.token_program,
+                accounts.token_2022_program,
+            )?,
         )?;
 
         // creation of vault_y associated token account
@@ -138,12 +269,17 @@ impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for InitializeConfig<'a> {
             accounts.authority,
             accounts.config,
             accounts.system_program,
-            accounts.token_program,
+            token_program_account(
+                accounts.mint_y,
+                accounts.token_program,
+                accounts.token_2022_program,
+            )?,
         )?;
 
         Ok(Self {
             accounts,
             instruction,
+            lp_bump,
         })
     }
 }
@@ -152,6 +288,18 @@ impl<'a> InitializeConfig<'a> {
     pub const DISCRIMINATOR: &'a u8 = &0;
 
     pub fn process(&self) -> ProgramResult {
+        // the vaults must be the config's atas for each mint before they get stored
+        AssociatedTokenAccount::check(
+            self.accounts.vault_x,
+            self.accounts.config,
+            self.accounts.mint_x,
+        )?;
+        AssociatedTokenAccount::check(
+            self.accounts.vault_y,
+            self.accounts.config,
+            self.accounts.mint_y,
+        )?;
+
         // get the config account mutable data
         let mut config_data = Config::load_mut(self.accounts.config)?;
 
@@ -163,8 +311,16 @@ impl<'a> InitializeConfig<'a> {
             *self.accounts.vault_x.key(),
             *self.accounts.vault_y.key(),
             *self.accounts.lp_mint.key(),
+            self.instruction.min_lp_mint,
+            self.instruction.min_lp_burn,
+            self.instruction.trading_start_ts,
+            self.instruction.max_lp_supply,
+            self.instruction.seed,
             self.instruction.fee,
             self.instruction.config_bump,
+            self.lp_bump,
+            self.instruction.curve_type,
+            self.instruction.price_band,
         )?;
 
         Ok(())
@@ -0,0 +1,201 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::Seed,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{
+        assert_unique_accounts, read_i64, read_u64, AccountCheck, AssociatedTokenAccount,
+        AssociatedTokenAccountCheck, AssociatedTokenAccountInit, ProgramAccount,
+        ProgramAccountInit, SignerAccount, TransferChecked, WritableAccount,
+    },
+    math::LpAmount,
+    pdas::{lp_lock_address, LP_LOCK_SEED},
+    state::{Config, LpLock},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct LockLpAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    // counts the locked lp
+    pub config: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub lock: &'a AccountInfo,
+    // the lock's lp ata, holds the lp until UnlockLp
+    pub escrow: &'a AccountInfo,
+    pub owner_lp_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub associated_token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for LockLpAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, config, lp_mint, lock, escrow, owner_lp_ata, token_program, system_program, associated_token_program] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+        WritableAccount::check(owner)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(lock)?;
+        WritableAccount::check(escrow)?;
+        WritableAccount::check(owner_lp_ata)?;
+
+        assert_unique_accounts(&[owner, config, lp_mint, lock, escrow, owner_lp_ata])?;
+
+        AssociatedTokenAccount::check(owner_lp_ata, owner, lp_mint)?;
+
+        Ok(Self {
+            owner,
+            config,
+            lp_mint,
+            lock,
+            escrow,
+            owner_lp_ata,
+            token_program,
+            system_program,
+            associated_token_program,
+        })
+    }
+}
+
+pub struct LockLpInstruction {
+    pub seed: u64,
+    pub amount: LpAmount,
+    pub unlock_ts: i64,
+}
+
+pub const LOCK_LP_DATA_LEN: usize = size_of::<u64>() * 2 + size_of::<i64>();
+
+impl<'a> TryFrom<&'a [u8]> for LockLpInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != LOCK_LP_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let seed = read_u64(data, 0)?;
+        let amount = read_u64(data, 8)?;
+        let unlock_ts = read_i64(data, 16)?;
+
+        if amount == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        // a lock that is already open would count as locked for nothing
+        if unlock_ts <= Clock::get()?.unix_timestamp {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        Ok(Self {
+            seed,
+            amount: LpAmount::new(amount),
+            unlock_ts,
+        })
+    }
+}
+
+pub struct LockLp<'a> {
+    pub accounts: LockLpAccounts<'a>,
+    pub instruction: LockLpInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for LockLp<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = LockLpAccounts::try_from(accounts)?;
+        let instruction = LockLpInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> LockLp<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &21;
+
+    // moves `amount` of the owner's lp into a new lock until `unlock_ts`
+    pub fn process(&self) -> ProgramResult {
+        {
+            let config = Config::load(self.accounts.config)?;
+            config.verify_address(self.accounts.config)?;
+
+            if config.lp_mint().ne(self.accounts.lp_mint.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        // searched for once here, the lock stores the canonical bump for UnlockLp to
+        // sign with
+        let (lock, lock_bump) = lp_lock_address(
+            self.accounts.config.key(),
+            self.accounts.owner.key(),
+            self.instruction.seed,
+        );
+        if lock.ne(self.accounts.lock.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+
+        let seed = self.instruction.seed.to_le_bytes();
+        let bump = [lock_bump];
+        let lock_seeds = [
+            Seed::from(LP_LOCK_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(self.accounts.owner.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&bump),
+        ];
+
+        ProgramAccount::init::<LpLock>(
+            self.accounts.owner,
+            self.accounts.lock,
+            &lock_seeds,
+            LpLock::LEN,
+        )?;
+
+        // the lp mint always lives under the legacy token program
+        AssociatedTokenAccount::init(
+            self.accounts.escrow,
+            self.accounts.lp_mint,
+            self.accounts.owner,
+            self.accounts.lock,
+            self.accounts.system_program,
+            self.accounts.token_program,
+        )?;
+
+        TransferChecked {
+            from: self.accounts.owner_lp_ata,
+            mint: self.accounts.lp_mint,
+            to: self.accounts.escrow,
+            authority: self.accounts.owner,
+            amount: self.instruction.amount.get(),
+        }
+        .invoke()?;
+
+        LpLock::load_mut(self.accounts.lock)?.set_inner(
+            *self.accounts.owner.key(),
+            *self.accounts.config.key(),
+            self.instruction.seed,
+            lock_bump,
+            self.instruction.amount.get(),
+            self.instruction.unlock_ts,
+        );
+
+        Config::load_mut(self.accounts.config)?.add_locked_lp_amount(self.instruction.amount)
+    }
+}
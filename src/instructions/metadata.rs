@@ -0,0 +1,204 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+
+use crate::instructions::{AccountCheck, WritableAccount};
+
+// metaplex token metadata program (metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s)
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = [
+    0x0b, 0x70, 0x65, 0xb1, 0xe3, 0xd1, 0x7c, 0x45, 0x38, 0x9d, 0x52, 0x7f, 0x6b, 0x04, 0xc3, 0xcd,
+    0x58, 0xb8, 0x6c, 0x73, 0x1a, 0xa0, 0xfd, 0xb5, 0x49, 0xb6, 0xd1, 0xbc, 0x03, 0xf8, 0x29, 0x46,
+];
+
+// limits enforced by the metadata program
+pub const MAX_METADATA_NAME_LEN: usize = 32;
+pub const MAX_METADATA_SYMBOL_LEN: usize = 10;
+
+const CREATE_METADATA_ACCOUNT_V3: u8 = 33;
+
+// metadata account layout: key (1) | update authority (32) | mint (32) | name | symbol | ..
+// with every string borsh encoded as a u32 length followed by the bytes
+const METADATA_V1_KEY: u8 = 4;
+const METADATA_MINT_OFFSET: usize = 33;
+const METADATA_NAME_OFFSET: usize = 65;
+
+// discriminator, name, symbol, empty uri, seller fee, three empty options,
+// is_mutable and the empty collection details
+const CREATE_METADATA_DATA_MAX_LEN: usize =
+    1 + 4 + MAX_METADATA_NAME_LEN + 4 + MAX_METADATA_SYMBOL_LEN + 4 + 2 + 3 + 1 + 1;
+
+// fixed capacity byte buffer, the program has no allocator
+struct ByteBuffer<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> ByteBuffer<N> {
+    fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    // appends as much of `data` as still fits
+    fn push(&mut self, data: &[u8]) {
+        let len = data.len().min(N - self.len);
+        self.bytes[self.len..self.len + len].copy_from_slice(&data[..len]);
+        self.len += len;
+    }
+
+    fn push_string(&mut self, data: &[u8]) {
+        self.push(&(data.len() as u32).to_le_bytes());
+        self.push(data);
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+// symbol stored in the metaplex metadata account of `mint`, without the nul padding
+fn metadata_symbol(
+    metadata: &AccountInfo,
+    mint: &AccountInfo,
+) -> Result<ByteBuffer<MAX_METADATA_SYMBOL_LEN>, ProgramError> {
+    if !metadata.is_owned_by(&TOKEN_METADATA_PROGRAM_ID) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let data = metadata.try_borrow_data()?;
+
+    if data.first() != Some(&METADATA_V1_KEY)
+        || data.get(METADATA_MINT_OFFSET..METADATA_NAME_OFFSET) != Some(&mint.key()[..])
+    {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let string_len = |offset: usize| -> Result<usize, ProgramError> {
+        data.get(offset..offset + 4)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(|bytes| u32::from_le_bytes(bytes) as usize)
+            .ok_or(ProgramError::InvalidAccountData)
+    };
+
+    let symbol_offset = METADATA_NAME_OFFSET + 4 + string_len(METADATA_NAME_OFFSET)?;
+    let symbol = data
+        .get(symbol_offset + 4..symbol_offset + 4 + string_len(symbol_offset)?)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let end = symbol
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(symbol.len());
+
+    let mut buffer = ByteBuffer::new();
+    buffer.push(&symbol[..end]);
+    Ok(buffer)
+}
+
+// accounts needed to give the lp mint metaplex metadata
+pub struct LpMetadataAccounts<'a> {
+    // metadata pda of the lp mint, the metadata program checks its address
+    pub lp_metadata: &'a AccountInfo,
+    pub metadata_program: &'a AccountInfo,
+
+    // metadata of the pool mints, their symbols name the lp token
+    pub metadata_x: &'a AccountInfo,
+    pub metadata_y: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for LpMetadataAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [lp_metadata, metadata_program, metadata_x, metadata_y] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        WritableAccount::check(lp_metadata)?;
+
+        if metadata_program.key().ne(&TOKEN_METADATA_PROGRAM_ID) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        Ok(Self {
+            lp_metadata,
+            metadata_program,
+            metadata_x,
+            metadata_y,
+        })
+    }
+}
+
+impl<'a> LpMetadataAccounts<'a> {
+    // creates the lp mint metadata as "X-Y LP" / "X-Y", the symbol cut to the metaplex
+    // limit. `mint_authority` is the config pda, it signs and becomes the update authority
+    pub fn create(
+        &self,
+        lp_mint: &AccountInfo,
+        mints: (&AccountInfo, &AccountInfo),
+        mint_authority: &AccountInfo,
+        payer: &AccountInfo,
+        system_program: &AccountInfo,
+        signers: &[Signer],
+    ) -> ProgramResult {
+        let symbol_x = metadata_symbol(self.metadata_x, mints.0)?;
+        let symbol_y = metadata_symbol(self.metadata_y, mints.1)?;
+
+        let mut symbol = ByteBuffer::<MAX_METADATA_SYMBOL_LEN>::new();
+        symbol.push(symbol_x.as_bytes());
+        symbol.push(b"-");
+        symbol.push(symbol_y.as_bytes());
+
+        let mut name = ByteBuffer::<MAX_METADATA_NAME_LEN>::new();
+        name.push(symbol_x.as_bytes());
+        name.push(b"-");
+        name.push(symbol_y.as_bytes());
+        name.push(b" LP");
+
+        let mut data = ByteBuffer::<CREATE_METADATA_DATA_MAX_LEN>::new();
+        data.push(&[CREATE_METADATA_ACCOUNT_V3]);
+        data.push_string(name.as_bytes());
+        data.push_string(symbol.as_bytes());
+        // no off chain json, wallets read the name and symbol from the account
+        data.push_string(b"");
+        // seller fee bps, creators, collection, uses
+        data.push(&0u16.to_le_bytes());
+        data.push(&[0, 0, 0]);
+        // immutable, the program has no instruction to update it
+        data.push(&[0]);
+        // collection details
+        data.push(&[0]);
+
+        let account_metas = [
+            AccountMeta::writable(self.lp_metadata.key()),
+            AccountMeta::readonly(lp_mint.key()),
+            AccountMeta::readonly_signer(mint_authority.key()),
+            AccountMeta::writable_signer(payer.key()),
+            AccountMeta::readonly(mint_authority.key()),
+            AccountMeta::readonly(system_program.key()),
+        ];
+
+        invoke_signed(
+            &Instruction {
+                program_id: &TOKEN_METADATA_PROGRAM_ID,
+                accounts: &account_metas,
+                data: data.as_bytes(),
+            },
+            &[
+                self.lp_metadata,
+                lp_mint,
+                mint_authority,
+                payer,
+                mint_authority,
+                system_program,
+            ],
+            signers,
+        )
+    }
+}
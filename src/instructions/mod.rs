@@ -1,11 +1,285 @@
+pub mod add_to_whitelist;
+pub mod admin;
+pub mod backfill_lp_metadata;
+pub mod cancel_dca_order;
+pub mod close_config;
+pub mod collect_protocol_fees;
+pub mod create_dca_order;
 pub mod deposit;
+pub mod deposit_single;
+pub mod execute_dca_tick;
+pub mod flash_borrow;
+pub mod flash_repay;
+pub mod get_accounts;
+pub mod get_pool_info;
 pub mod helper;
+pub mod increase_observation_cardinality;
 pub mod initialize;
+pub mod lock_lp;
+pub mod metadata;
+pub mod rebalance_swap;
+pub mod rollover_epoch_stats;
 pub mod swap;
+pub mod swap_exact_out;
+pub mod token_interface;
+pub mod unlock_lp;
 pub mod withdraw;
+pub mod withdraw_single;
 
+pub use add_to_whitelist::*;
+pub use admin::*;
+pub use backfill_lp_metadata::*;
+pub use cancel_dca_order::*;
+pub use close_config::*;
+pub use collect_protocol_fees::*;
+pub use create_dca_order::*;
 pub use deposit::*;
+pub use deposit_single::*;
+pub use execute_dca_tick::*;
+pub use flash_borrow::*;
+pub use flash_repay::*;
+pub use get_accounts::*;
+pub use get_pool_info::*;
 pub use helper::*;
+pub use increase_observation_cardinality::*;
 pub use initialize::*;
+pub use lock_lp::*;
+pub use metadata::*;
+pub use rebalance_swap::*;
+pub use rollover_epoch_stats::*;
 pub use swap::*;
+pub use swap_exact_out::*;
+pub use token_interface::*;
+pub use unlock_lp::*;
 pub use withdraw::*;
+pub use withdraw_single::*;
+
+#[cfg(test)]
+mod tests {
+    use pinocchio::program_error::ProgramError;
+
+    use super::*;
+
+    type Parse = fn(&[u8]) -> Result<(), ProgramError>;
+
+    // every instruction data parser with the payload lengths it accepts
+    const PARSERS: [(&str, Parse, &[usize]); 13] = [
+        (
+            "initialize_config",
+            |data| InitializeConfigInstruction::try_from(data).map(|_| ()),
+            &[
+                INITIALIZE_CONFIG_DATA_LEN,
+                INITIALIZE_CONFIG_CURVE_DATA_LEN,
+                INITIALIZE_CONFIG_PRICE_BAND_DATA_LEN,
+            ],
+        ),
+        (
+            "deposit",
+            |data| DepositInstructions::try_from(data).map(|_| ()),
+            &[DEPOSIT_DATA_LEN, DEPOSIT_BOOTSTRAP_DATA_LEN],
+        ),
+        (
+            "withdraw",
+            |data| WithdrawInstructions::try_from(data).map(|_| ()),
+            &[WITHDRAW_DATA_LEN, WITHDRAW_SLOT_DATA_LEN],
+        ),
+        (
+            "swap",
+            |data| SwapInstruction::try_from(data).map(|_| ()),
+            &[SWAP_DATA_LEN, SWAP_EXPECTED_POOL_DATA_LEN],
+        ),
+        (
+            "get_accounts",
+            |data| GetAccountsInstruction::try_from(data).map(|_| ()),
+            &[GET_ACCOUNTS_DATA_LEN],
+        ),
+        (
+            "swap_exact_out",
+            |data| SwapExactOutInstruction::try_from(data).map(|_| ()),
+            &[SWAP_EXACT_OUT_DATA_LEN],
+        ),
+        (
+            "create_dca_order",
+            |data| CreateDcaOrderInstruction::try_from(data).map(|_| ()),
+            &[CREATE_DCA_ORDER_DATA_LEN],
+        ),
+        (
+            "increase_observation_cardinality",
+            |data| IncreaseObservationCardinalityInstruction::try_from(data).map(|_| ()),
+            &[INCREASE_OBSERVATION_CARDINALITY_DATA_LEN],
+        ),
+        (
+            "flash_borrow",
+            |data| FlashBorrowInstruction::try_from(data).map(|_| ()),
+            &[FLASH_BORROW_DATA_LEN],
+        ),
+        (
+            "deposit_single",
+            |data| DepositSingleInstruction::try_from(data).map(|_| ()),
+            &[DEPOSIT_SINGLE_DATA_LEN],
+        ),
+        (
+            "withdraw_single",
+            |data| WithdrawSingleInstruction::try_from(data).map(|_| ()),
+            &[WITHDRAW_SINGLE_DATA_LEN],
+        ),
+        (
+            "lock_lp",
+            |data| LockLpInstruction::try_from(data).map(|_| ()),
+            &[LOCK_LP_DATA_LEN],
+        ),
+        (
+            "rebalance_swap",
+            |data| RebalanceSwapInstruction::try_from(data).map(|_| ()),
+            &[REBALANCE_SWAP_DATA_LEN],
+        ),
+    ];
+
+    // admin ops with the payload length each accepts after the op byte
+    const ADMIN_OPS: [(u8, usize); 15] = [
+        (AdminOp::UPDATE_TRADING_START, 8),
+        (AdminOp::SET_NO_CPI, 1),
+        (AdminOp::SET_MAX_LP_SUPPLY, 8),
+        (AdminOp::SET_PRICE_BAND, 16),
+        (AdminOp::PAUSE, 0),
+        (AdminOp::UNPAUSE, 0),
+        (AdminOp::SET_WITHDRAW_ONLY, 0),
+        (AdminOp::SET_PROTOCOL_FEE, 2),
+        (AdminOp::UPDATE_FEE, 2),
+        (AdminOp::SET_REFERRAL_FEE, 2),
+        (AdminOp::SET_WHITELIST_AUTHORITY, 32),
+        (AdminOp::RAMP_AMP, 16),
+        (AdminOp::SET_LAUNCH_GUARD, 25),
+        (AdminOp::SET_ROYALTY, 34),
+        (AdminOp::SET_MAX_FEE_CHANGE, 2),
+    ];
+
+    // longer than any payload, so every parser also sees oversized input
+    const MAX_TEST_LEN: usize = 96;
+
+    #[test]
+    fn parsers_reject_truncated_and_oversized_data() {
+        let data = [0u8; MAX_TEST_LEN];
+
+        for (name, parse, valid_lens) in PARSERS {
+            for len in (0..=MAX_TEST_LEN).filter(|len| !valid_lens.contains(len)) {
+                assert_eq!(
+                    parse(&data[..len]),
+                    Err(ProgramError::InvalidInstructionData),
+                    "{name} accepted {len} bytes"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn admin_ops_reject_truncated_and_oversized_data() {
+        let mut data = [0u8; MAX_TEST_LEN];
+
+        assert_eq!(
+            AdminOp::try_from(&data[..0]).map(|_| ()),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        for (op, valid_len) in ADMIN_OPS {
+            data[0] = op;
+            for len in (0..MAX_TEST_LEN).filter(|len| *len != valid_len) {
+                assert_eq!(
+                    AdminOp::try_from(&data[..1 + len]).map(|_| ()),
+                    Err(ProgramError::InvalidInstructionData),
+                    "admin op {op} accepted {len} bytes"
+                );
+            }
+        }
+
+        // an op number past the last one
+        data[0] = ADMIN_OPS.len() as u8;
+        assert_eq!(
+            AdminOp::try_from(&data[..1]).map(|_| ()),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn slot_hashes_lookup_finds_only_listed_slots() {
+        // three entries, newest first, the hashes don't matter
+        let mut data = [0u8; 8 + 3 * 40];
+        data[..8].copy_from_slice(&3u64.to_le_bytes());
+        for (i, slot) in [120u64, 110, 100].into_iter().enumerate() {
+            data[8 + i * 40..16 + i * 40].copy_from_slice(&slot.to_le_bytes());
+        }
+
+        for slot in [100, 110, 120] {
+            assert_eq!(slot_hashes_contain(&data, slot), Ok(true));
+        }
+        for slot in [0, 99, 105, 121, u64::MAX] {
+            assert_eq!(slot_hashes_contain(&data, slot), Ok(false));
+        }
+
+        // a count running past the data
+        assert_eq!(
+            slot_hashes_contain(&data[..8 + 40], 100),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn readers_reject_reads_past_the_end() {
+        let data = [0u8; 8];
+
+        assert_eq!(read_u8(&data, 8), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(
+            read_bool(&data, 8),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_u16(&data, 7),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_u64(&data, 1),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_i64(&data, 1),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_pubkey(&data, 0),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        // an offset that would overflow the end index
+        assert_eq!(
+            read_u64(&data, usize::MAX),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            read_bool(&[2], 0),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        assert_eq!(read_u64(&data, 0), Ok(0));
+    }
+
+    // no byte string may panic a parser, whatever it returns
+    #[test]
+    fn parsers_survive_random_data() {
+        let mut state = 0x853c_49e6_748f_ea9bu64;
+        let mut data = [0u8; MAX_TEST_LEN];
+
+        for _ in 0..20_000 {
+            for byte in data.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *byte = state as u8;
+            }
+            let len = state as usize % (MAX_TEST_LEN + 1);
+
+            for (_, parse, _) in PARSERS {
+                let _ = parse(&data[..len]);
+            }
+            let _ = AdminOp::try_from(&data[..len]);
+        }
+    }
+}
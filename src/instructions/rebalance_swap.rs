@@ -0,0 +1,392 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    events::SwapEvent,
+    instructions::{
+        assert_unique_accounts, net_transfer_amount, read_bool, read_u64, split_token_2022_program,
+        vault_amount, AccountCheck, SignerAccount, SwapPool, TransferChecked, WritableAccount,
+    },
+    math::Bps,
+    state::{fee_share, Config, Observations},
+};
+
+// one side of the rebalance, the accounts of a pool in the order SwapPool::load
+// takes them
+pub struct RebalancePool<'a> {
+    pub config: &'a AccountInfo,
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+    pub observations: Option<&'a AccountInfo>,
+    // whether the pool takes in its x token
+    pub x_to_y: bool,
+}
+
+impl<'a> RebalancePool<'a> {
+    // orders the pool's accounts by its config, `mint_in` is the token it takes in
+    fn new(
+        config: &'a AccountInfo,
+        lp_mint: &'a AccountInfo,
+        (mint_in, mint_out): (&'a AccountInfo, &'a AccountInfo),
+        (vault_in, vault_out): (&'a AccountInfo, &'a AccountInfo),
+    ) -> Result<Self, ProgramError> {
+        let x_to_y = Config::load(config)?.mint_x().eq(mint_in.key());
+
+        let (mint_x, mint_y, vault_x, vault_y) = if x_to_y {
+            (mint_in, mint_out, vault_in, vault_out)
+        } else {
+            (mint_out, mint_in, vault_out, vault_in)
+        };
+
+        Ok(Self {
+            config,
+            mint_x,
+            mint_y,
+            lp_mint,
+            vault_x,
+            vault_y,
+            observations: None,
+            x_to_y,
+        })
+    }
+
+    fn load(&self) -> Result<SwapPool, ProgramError> {
+        SwapPool::load(
+            self.config,
+            self.mint_x,
+            self.mint_y,
+            self.lp_mint,
+            self.vault_x,
+            self.vault_y,
+            self.observations,
+        )
+    }
+
+    // (mint_in, mint_out, vault_in, vault_out)
+    fn sides(
+        &self,
+    ) -> (
+        &'a AccountInfo,
+        &'a AccountInfo,
+        &'a AccountInfo,
+        &'a AccountInfo,
+    ) {
+        if self.x_to_y {
+            (self.mint_x, self.mint_y, self.vault_x, self.vault_y)
+        } else {
+            (self.mint_y, self.mint_x, self.vault_y, self.vault_x)
+        }
+    }
+}
+
+// accounts are expected in the same order as the fields below. the middle token is
+// bought from the first pool and sold to the second, it moves vault to vault
+pub struct RebalanceSwapAccounts<'a> {
+    // authority of both pools
+    pub authority: &'a AccountInfo,
+
+    pub mint_in: &'a AccountInfo,
+    pub mint_mid: &'a AccountInfo,
+    pub mint_out: &'a AccountInfo,
+
+    // pool the input is sold to, config | lp mint | vault in | vault mid
+    pub pool_in: RebalancePool<'a>,
+    // pool the middle token is sold to, config | lp mint | vault mid | vault out
+    pub pool_out: RebalancePool<'a>,
+
+    pub authority_in_ata: &'a AccountInfo,
+    pub authority_out_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RebalanceSwapAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [authority, mint_in, mint_mid, mint_out, config_in, lp_mint_in, vault_in, vault_mid_in, config_out, lp_mint_out, vault_mid_out, vault_out, authority_in_ata, authority_out_ata, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(authority)?;
+        WritableAccount::check(config_in)?;
+        WritableAccount::check(config_out)?;
+        WritableAccount::check(vault_in)?;
+        WritableAccount::check(vault_mid_in)?;
+        WritableAccount::check(vault_mid_out)?;
+        WritableAccount::check(vault_out)?;
+        WritableAccount::check(authority_in_ata)?;
+        WritableAccount::check(authority_out_ata)?;
+
+        assert_unique_accounts(&[
+            authority,
+            mint_in,
+            mint_mid,
+            mint_out,
+            config_in,
+            lp_mint_in,
+            vault_in,
+            vault_mid_in,
+            config_out,
+            lp_mint_out,
+            vault_mid_out,
+            vault_out,
+            authority_in_ata,
+            authority_out_ata,
+        ])?;
+
+        let mut pool_in = RebalancePool::new(
+            config_in,
+            lp_mint_in,
+            (mint_in, mint_mid),
+            (vault_in, vault_mid_in),
+        )?;
+        let mut pool_out = RebalancePool::new(
+            config_out,
+            lp_mint_out,
+            (mint_mid, mint_out),
+            (vault_mid_out, vault_out),
+        )?;
+
+        // trailing accounts, the observation buffers of either pool, told apart by
+        // the pool they snapshot, and the token-2022 program last
+        let (_, remaining) = split_token_2022_program(remaining);
+        for observations in remaining {
+            WritableAccount::check(observations)?;
+
+            let config = *Observations::load(observations)?.config();
+            let pool = if config.eq(config_in.key()) {
+                &mut pool_in
+            } else if config.eq(config_out.key()) {
+                &mut pool_out
+            } else {
+                return Err(ProgramError::InvalidAccountData);
+            };
+            if pool.observations.replace(observations).is_some() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        Ok(Self {
+            authority,
+            mint_in,
+            mint_mid,
+            mint_out,
+            pool_in,
+            pool_out,
+            authority_in_ata,
+            authority_out_ata,
+            token_program,
+        })
+    }
+}
+
+pub struct RebalanceSwapInstruction {
+    // exact amount the authority sends
+    pub amount_in: u64,
+    // least the authority is willing to receive for it
+    pub min_out: u64,
+    // charge the protocol share of each pool's fee, the lp share is never charged
+    pub protocol_fee: bool,
+}
+
+pub const REBALANCE_SWAP_DATA_LEN: usize = size_of::<u64>() * 2 + 1;
+
+impl<'a> TryFrom<&'a [u8]> for RebalanceSwapInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != REBALANCE_SWAP_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount_in = read_u64(data, 0)?;
+        let min_out = read_u64(data, 8)?;
+        let protocol_fee = read_bool(data, 16)?;
+
+        if amount_in == 0 || min_out == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        Ok(Self {
+            amount_in,
+            min_out,
+            protocol_fee,
+        })
+    }
+}
+
+pub struct RebalanceSwap<'a> {
+    pub accounts: RebalanceSwapAccounts<'a>,
+    pub instruction: RebalanceSwapInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for RebalanceSwap<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = RebalanceSwapAccounts::try_from(accounts)?;
+        let instruction = RebalanceSwapInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> RebalanceSwap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &24;
+
+    // moves the authority's inventory across two of its pools at their curve
+    // prices: the input is sold to the first pool for the middle token, which goes
+    // straight to the second pool for the output. no lp fee or royalty is charged
+    // and the lps' fee growth is left alone, so a treasury rebalancing its own
+    // liquidity doesn't pay fees to itself
+    pub fn process(&self) -> ProgramResult {
+        let pool_in = &self.accounts.pool_in;
+        let pool_out = &self.accounts.pool_out;
+
+        Config::load(pool_in.config)?.require_authority(self.accounts.authority)?;
+        Config::load(pool_out.config)?.require_authority(self.accounts.authority)?;
+
+        let swap_in = pool_in.load()?;
+        let swap_out = pool_out.load()?;
+        for swap in [&swap_in, &swap_out] {
+            swap.require_no_launch_guard()?;
+        }
+
+        let (_, _, vault_in, _) = pool_in.sides();
+        let before = vault_amount(vault_in, pool_in.config, self.accounts.mint_in)?;
+
+        TransferChecked {
+            from: self.accounts.authority_in_ata,
+            mint: self.accounts.mint_in,
+            to: vault_in,
+            authority: self.accounts.authority,
+            amount: self.instruction.amount_in,
+        }
+        .invoke()?;
+
+        // priced on what the vault received, never on the requested amount
+        let received = vault_amount(vault_in, pool_in.config, self.accounts.mint_in)?
+            .checked_sub(before)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let amount_mid = self.swap(pool_in, &swap_in, received)?;
+        let received_mid = self.pay_out(pool_in, &swap_in, amount_mid, Some(pool_out))?;
+        let amount_out = self.swap(pool_out, &swap_out, received_mid)?;
+
+        // the bound is on what reaches the authority, any transfer fee on the way out counts
+        if net_transfer_amount(self.accounts.mint_out, swap_out.epoch, amount_out)?
+            < self.instruction.min_out
+        {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        self.pay_out(pool_out, &swap_out, amount_out, None)?;
+
+        Ok(())
+    }
+
+    // prices `amount_in` on the pool's curve with no lp fee, taking the protocol
+    // share of the fee first when asked to, and books the trade on the pool
+    fn swap(
+        &self,
+        pool: &RebalancePool<'a>,
+        swap: &SwapPool,
+        amount_in: u64,
+    ) -> Result<u64, ProgramError> {
+        let protocol_fee = if self.instruction.protocol_fee {
+            fee_share(amount_in, swap.fee, swap.protocol_fee_bps)?
+        } else {
+            0
+        };
+        let priced = amount_in
+            .checked_sub(protocol_fee)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let (reserve_in, reserve_out) = swap.reserves(pool.x_to_y);
+        let amount_out =
+            swap.curve_type
+                .swap_exact_in(reserve_in, reserve_out, priced, Bps::new(0))?;
+
+        swap.check_price_band(pool.x_to_y, priced, amount_out)?;
+
+        let mut config = Config::load_mut(pool.config)?;
+        if protocol_fee != 0 {
+            config.accrue_protocol_fee(pool.x_to_y, protocol_fee)?;
+        }
+        config
+            .stats_mut()
+            .record_swap(pool.x_to_y, amount_in, protocol_fee);
+
+        let event_seq = config.next_event_seq()?;
+        SwapEvent {
+            user: *self.accounts.authority.key(),
+            x_to_y: pool.x_to_y,
+            amount_in,
+            amount_out,
+        }
+        .emit(pool.config.key(), event_seq);
+
+        Ok(amount_out)
+    }
+
+    // pays `amount` out of the pool, into the next pool's vault when there is one
+    // and the authority's ata otherwise. returns what the next pool received
+    fn pay_out(
+        &self,
+        pool: &RebalancePool<'a>,
+        swap: &SwapPool,
+        amount: u64,
+        next: Option<&RebalancePool<'a>>,
+    ) -> Result<u64, ProgramError> {
+        let (_, mint, _, from) = pool.sides();
+        let to = match next {
+            Some(next) => next.sides().2,
+            None => self.accounts.authority_out_ata,
+        };
+
+        let before = match next {
+            Some(next) => vault_amount(to, next.config, mint)?,
+            None => 0,
+        };
+
+        let seed = swap.seed.to_le_bytes();
+        let config_bump = [swap.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(pool.mint_x.key().as_ref()),
+            Seed::from(pool.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from,
+            mint,
+            to,
+            authority: pool.config,
+            amount,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        let Some(next) = next else {
+            return Ok(amount);
+        };
+        Ok(vault_amount(to, next.config, mint)?
+            .checked_sub(before)
+            .ok_or(PinocchioError::MathOverflow)?)
+    }
+}
@@ -0,0 +1,79 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    events::EpochStatsEvent,
+    instructions::{AccountCheck, WritableAccount},
+    state::Config,
+};
+
+pub struct RolloverEpochStatsAccounts<'a> {
+    // anyone may close a finished epoch
+    pub config: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for RolloverEpochStatsAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [config] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        WritableAccount::check(config)?;
+
+        Ok(Self { config })
+    }
+}
+
+pub const ROLLOVER_EPOCH_STATS_DATA_LEN: usize = 0;
+
+pub struct RolloverEpochStats<'a> {
+    pub accounts: RolloverEpochStatsAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for RolloverEpochStats<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if data.len() != ROLLOVER_EPOCH_STATS_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = RolloverEpochStatsAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> RolloverEpochStats<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &20;
+
+    // closes the pool's open stats bucket into its history once the epoch it started
+    // in is over and logs the closed bucket
+    pub fn process(&self) -> ProgramResult {
+        let epoch = Clock::get()?.epoch;
+
+        let mut config = Config::load_mut(self.accounts.config)?;
+        config.verify_address(self.accounts.config)?;
+
+        let closed = config.stats_mut().rollover(epoch)?;
+        let event_seq = config.next_event_seq()?;
+
+        EpochStatsEvent {
+            epoch: closed.epoch,
+            end_epoch: epoch,
+            volume_x: closed.volume_x,
+            volume_y: closed.volume_y,
+            fees_x: closed.fees_x,
+            fees_y: closed.fees_y,
+        }
+        .emit(self.accounts.config.key(), event_seq);
+
+        Ok(())
+    }
+}
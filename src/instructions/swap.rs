@@ -1 +1,990 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
+    ProgramResult,
+};
 
+use crate::{
+    error::PinocchioError,
+    events::{RoyaltyEvent, SwapEvent},
+    instructions::{
+        account_is_rent_exempt, assert_top_level, assert_unique_accounts, create_temporary_wsol,
+        is_native_mint, is_temporary_wsol, net_transfer_amount, read_bool, read_i64, read_u16,
+        read_u64, unwrap_sol, vault_amount, wrap_sol, AccountCheck, AssociatedTokenAccount,
+        AssociatedTokenAccountCheck, MintData, MintInterface, ProgramAccount, ProgramAccountInit,
+        SignerAccount, TokenAccountData, TransferChecked, WritableAccount, TOKEN_2022_PROGRAM_ID,
+    },
+    math::{Bps, LpAmount},
+    pdas::{launch_buys_address, LAUNCH_BUYS_SEED},
+    state::{
+        fee_amount, fee_share, royalty_amount, Config, CurveType, LaunchBuys, LaunchGuard,
+        Observations, PriceBand,
+    },
+};
+
+// accounts are expected in the same order as the fields below, shared by every swap mode
+pub struct SwapAccounts<'a> {
+    pub user: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    // read for the lp supply the fee growth is spread over
+    pub lp_mint: &'a AccountInfo,
+
+    pub config: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    pub user_x_ata: &'a AccountInfo,
+    pub user_y_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+
+    // trailing accounts, in any order. the pool's observation buffer gets a snapshot
+    // of the reserves and is required once the pool has one. the system and
+    // associated token programs are needed to swap sol on a native mint pool, see
+    // wrap_native. the token-2022 program has to be passed when a pool mint is under
+    // it. token accounts are told apart by mint, see recipient and referrer, those
+    // of the pool's royalty destination by owner, see pay_royalty. the user's
+    // LaunchBuys account is needed for buys in a capped launch window, see
+    // record_launch_buy, and is told apart from the buffer by its length
+    pub observations: Option<&'a AccountInfo>,
+    pub launch_buys: Option<&'a AccountInfo>,
+    pub system_program: Option<&'a AccountInfo>,
+    pub token_2022_program: Option<&'a AccountInfo>,
+    pub associated_token_program: Option<&'a AccountInfo>,
+    pub token_account_x: Option<&'a AccountInfo>,
+    pub token_account_y: Option<&'a AccountInfo>,
+    pub royalty_account_x: Option<&'a AccountInfo>,
+    pub royalty_account_y: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for SwapAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_x_ata, user_y_ata, token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(user)?;
+        // the config accrues the protocol share of every swap fee
+        WritableAccount::check(config)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(user_x_ata)?;
+        WritableAccount::check(user_y_ata)?;
+
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+
+        if mint_x.key() == mint_y.key() {
+            return Err(PinocchioError::IdenticalTokenMints.into());
+        }
+
+        assert_unique_accounts(&[
+            user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_x_ata, user_y_ata,
+        ])?;
+
+        AssociatedTokenAccount::check(vault_x, config, mint_x)?;
+        AssociatedTokenAccount::check(vault_y, config, mint_y)?;
+        // a temporary wsol ata is created by the swap, which checks its address
+        if !is_temporary_wsol(user_x_ata, mint_x) {
+            AssociatedTokenAccount::check(user_x_ata, user, mint_x)?;
+        }
+        if !is_temporary_wsol(user_y_ata, mint_y) {
+            AssociatedTokenAccount::check(user_y_ata, user, mint_y)?;
+        }
+
+        let mut observations = None;
+        let mut launch_buys = None;
+        let mut system_program = None;
+        let mut token_2022_program = None;
+        let mut associated_token_program = None;
+        let mut token_account_x = None;
+        let mut token_account_y = None;
+        let mut royalty_account_x = None;
+        let mut royalty_account_y = None;
+        let royalty_destination = Config::load(config)?.royalty_destination();
+        for account in remaining {
+            let slot = if account.key() == &pinocchio_system::ID {
+                &mut system_program
+            } else if account.key() == &pinocchio_associated_token_account::ID {
+                &mut associated_token_program
+            } else if account.key() == &TOKEN_2022_PROGRAM_ID {
+                &mut token_2022_program
+            } else if (account.is_owned_by(&crate::ID) && account.data_len() == LaunchBuys::LEN)
+                // not created until the user's first buy in the window
+                || (account.is_owned_by(&pinocchio_system::ID) && account.data_is_empty())
+            {
+                &mut launch_buys
+            } else if account.is_owned_by(&crate::ID) {
+                &mut observations
+            } else {
+                WritableAccount::check(account)?;
+
+                if account.key() == vault_x.key() || account.key() == vault_y.key() {
+                    return Err(PinocchioError::DuplicateAccount.into());
+                }
+
+                let (mint, owner) = {
+                    let token_account = TokenAccountData::from_account_info(account)?;
+                    (*token_account.mint(), *token_account.owner())
+                };
+                // the royalty destination can't route or receive through its own
+                // token accounts, they only ever take the royalty
+                let royalty = royalty_destination.is_some_and(|destination| owner == destination);
+                if mint.eq(mint_x.key()) {
+                    if royalty {
+                        &mut royalty_account_x
+                    } else {
+                        &mut token_account_x
+                    }
+                } else if mint.eq(mint_y.key()) {
+                    if royalty {
+                        &mut royalty_account_y
+                    } else {
+                        &mut token_account_y
+                    }
+                } else {
+                    return Err(ProgramError::InvalidAccountData);
+                }
+            };
+            if slot.replace(account).is_some() {
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+
+        if let Some(observations) = observations {
+            WritableAccount::check(observations)?;
+        }
+        if let Some(launch_buys) = launch_buys {
+            WritableAccount::check(launch_buys)?;
+        }
+
+        Ok(Self {
+            user,
+            mint_x,
+            mint_y,
+            lp_mint,
+            config,
+            vault_x,
+            vault_y,
+            user_x_ata,
+            user_y_ata,
+            token_program,
+            observations,
+            launch_buys,
+            system_program,
+            token_2022_program,
+            associated_token_program,
+            token_account_x,
+            token_account_y,
+            royalty_account_x,
+            royalty_account_y,
+        })
+    }
+}
+
+// everything a swap needs from the pool, read once before any cpi borrows the accounts
+pub struct SwapPool {
+    pub curve_type: CurveType,
+    pub fee: Bps,
+    pub protocol_fee_bps: Bps,
+    pub referral_fee_bps: Bps,
+    // creator royalty taken off the input before the swap is priced
+    pub royalty_bps: Bps,
+    pub config_bump: u8,
+    pub seed: u64,
+    pub epoch: u64,
+    pub price_band: PriceBand,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+    // minted plus locked lp, what the fee growth is spread over
+    pub lp_supply: LpAmount,
+    // bumped by every admin op, see ExpectedPool
+    pub admin_nonce: u64,
+    // set while the pool is in its launch window
+    pub launch_guard: Option<LaunchGuard>,
+}
+
+impl SwapPool {
+    // (reserve_in, reserve_out) for the given direction
+    pub fn reserves(&self, x_to_y: bool) -> (u64, u64) {
+        if x_to_y {
+            (self.reserve_x, self.reserve_y)
+        } else {
+            (self.reserve_y, self.reserve_x)
+        }
+    }
+
+    // rejects a trade that would push the spot price past the pool's price band
+    pub fn check_price_band(
+        &self,
+        x_to_y: bool,
+        amount_in: u64,
+        amount_out: u64,
+    ) -> Result<(), ProgramError> {
+        if !self.price_band.is_enabled() {
+            return Ok(());
+        }
+
+        let (reserve_in, reserve_out) = self.reserves(x_to_y);
+        let reserve_in = reserve_in
+            .checked_add(amount_in)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let (reserve_x_after, reserve_y_after) = if x_to_y {
+            (reserve_in, reserve_out)
+        } else {
+            (reserve_out, reserve_in)
+        };
+
+        let price_before = self.curve_type.spot_price(self.reserve_x, self.reserve_y)?;
+        let price_after = self
+            .curve_type
+            .spot_price(reserve_x_after, reserve_y_after)?;
+
+        self.price_band.check_move(price_before, price_after)?;
+        Ok(())
+    }
+
+    // adds the swap to the open epoch bucket and the lps' part of the fee charged on
+    // `amount_in` to the fee growth of the input token, the fee less the protocol
+    // share and the referral share if one is paid
+    pub fn record_swap(
+        &self,
+        config: &AccountInfo,
+        x_to_y: bool,
+        amount_in: u64,
+        referred: bool,
+    ) -> ProgramResult {
+        let protocol_fee = fee_share(amount_in, self.fee, self.protocol_fee_bps)?;
+        let referral_fee = if referred {
+            fee_share(amount_in, self.fee, self.referral_fee_bps)?
+        } else {
+            0
+        };
+
+        let fee = fee_amount(amount_in, self.fee)?;
+        let lp_fee = fee
+            .checked_sub(protocol_fee)
+            .and_then(|fee| fee.checked_sub(referral_fee))
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let mut config = Config::load_mut(config)?;
+        config.stats_mut().record_swap(x_to_y, amount_in, fee);
+        config.accrue_fee_growth(x_to_y, lp_fee, self.lp_supply);
+        Ok(())
+    }
+
+    // trades that aren't a user's own swap (single sided deposits and withdrawals,
+    // dca ticks) have no wallet to meter against the launch caps, they wait out the
+    // launch window
+    pub fn require_no_launch_guard(&self) -> ProgramResult {
+        if self.launch_guard.is_some() {
+            return Err(PinocchioError::LaunchGuardActive.into());
+        }
+        Ok(())
+    }
+
+    // the royalty is only paid by a user's own swap, see SwapAccounts::pay_royalty,
+    // the other trades through the pool are turned away instead of skipping it
+    pub fn require_no_royalty(&self) -> ProgramResult {
+        if !self.royalty_bps.is_zero() {
+            return Err(PinocchioError::RoyaltyNotSupported.into());
+        }
+        Ok(())
+    }
+}
+
+impl SwapPool {
+    // validates the pool against the passed accounts and applies the pool wide
+    // gates (rent, pool state, trading start, no_cpi) shared by every swap mode
+    pub fn load(
+        config: &AccountInfo,
+        mint_x: &AccountInfo,
+        mint_y: &AccountInfo,
+        lp_mint: &AccountInfo,
+        vault_x: &AccountInfo,
+        vault_y: &AccountInfo,
+        observations: Option<&AccountInfo>,
+    ) -> Result<Self, ProgramError> {
+        let rent = Rent::get()?;
+        if !account_is_rent_exempt(config, &rent)
+            || !account_is_rent_exempt(vault_x, &rent)
+            || !account_is_rent_exempt(vault_y, &rent)
+        {
+            return Err(PinocchioError::NotRentExempt.into());
+        }
+
+        let clock = Clock::get()?;
+
+        let (
+            curve_type,
+            fee,
+            protocol_fee_bps,
+            referral_fee_bps,
+            royalty_bps,
+            protocol_fees,
+            config_bump,
+            seed,
+            no_cpi,
+            trading_start_ts,
+            price_band,
+            observations_required,
+            locked_lp,
+            admin_nonce,
+            launch_guard,
+        ) = {
+            let config_data = Config::load(config)?;
+            config_data.verify_address(config)?;
+            config_data.verify_pool_accounts(mint_x, mint_y, vault_x, vault_y)?;
+            config_data.require_active()?;
+
+            if config_data.lp_mint().ne(lp_mint.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            (
+                config_data.curve_type(clock.unix_timestamp)?,
+                config_data.fee(clock.slot),
+                config_data.protocol_fee_bps(),
+                config_data.referral_fee_bps(),
+                config_data.royalty_bps(),
+                config_data.protocol_fees(),
+                config_data.config_bump(),
+                config_data.seed(),
+                config_data.no_cpi(),
+                config_data.trading_start_ts(),
+                config_data.price_band(clock.slot),
+                config_data.observations_required(),
+                config_data.locked_lp(),
+                config_data.admin_nonce(),
+                config_data.launch_guard(clock.unix_timestamp),
+            )
+        };
+
+        if clock.unix_timestamp < trading_start_ts {
+            return Err(PinocchioError::TradingNotStarted.into());
+        }
+
+        if no_cpi {
+            assert_top_level()?;
+        }
+
+        // accrued protocol fees sit in the vaults but don't belong to the lps
+        let reserve_x = vault_amount(vault_x, config, mint_x)?
+            .checked_sub(protocol_fees.0)
+            .ok_or(PinocchioError::MathOverflow)?;
+        let reserve_y = vault_amount(vault_y, config, mint_y)?
+            .checked_sub(protocol_fees.1)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        // the locked lp is part of the supply, it earns its share like any other
+        let lp_supply = LpAmount::new(MintData::from_account_info(lp_mint)?.supply())
+            .checked_add(locked_lp)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        Config::load_mut(config)?.update_price_cumulative(
+            reserve_x,
+            reserve_y,
+            clock.unix_timestamp,
+        )?;
+
+        match observations {
+            Some(observations) => Observations::record(
+                observations,
+                config,
+                clock.unix_timestamp,
+                reserve_x,
+                reserve_y,
+            )?,
+            None if observations_required => return Err(PinocchioError::ObservationsMissing.into()),
+            None => {}
+        }
+
+        Ok(Self {
+            curve_type,
+            fee,
+            protocol_fee_bps,
+            referral_fee_bps,
+            royalty_bps,
+            config_bump,
+            seed,
+            epoch: clock.epoch,
+            price_band,
+            reserve_x,
+            reserve_y,
+            lp_supply,
+            admin_nonce,
+            launch_guard,
+        })
+    }
+}
+
+impl<'a> SwapAccounts<'a> {
+    pub fn load_pool(&self) -> Result<SwapPool, ProgramError> {
+        SwapPool::load(
+            self.config,
+            self.mint_x,
+            self.mint_y,
+            self.lp_mint,
+            self.vault_x,
+            self.vault_y,
+            self.observations,
+        )
+    }
+
+    // (mint_in, mint_out) for the given direction
+    pub fn mints(&self, x_to_y: bool) -> (&'a AccountInfo, &'a AccountInfo) {
+        if x_to_y {
+            (self.mint_x, self.mint_y)
+        } else {
+            (self.mint_y, self.mint_x)
+        }
+    }
+
+    // (user_in, user_out) atas for the given direction
+    pub fn user_atas(&self, x_to_y: bool) -> (&'a AccountInfo, &'a AccountInfo) {
+        if x_to_y {
+            (self.user_x_ata, self.user_y_ata)
+        } else {
+            (self.user_y_ata, self.user_x_ata)
+        }
+    }
+
+    // sol side of a swap on a native mint pool. a native input is wrapped from the
+    // user's lamports and a temporary wsol ata receives a native output, unless a
+    // recipient was passed. returns the temporary atas unwrap_temporary closes once
+    // the swap is done
+    pub fn wrap_native(
+        &self,
+        x_to_y: bool,
+        amount_in: u64,
+    ) -> Result<[Option<&'a AccountInfo>; 2], ProgramError> {
+        let (mint_in, mint_out) = self.mints(x_to_y);
+        let (user_in, user_out) = self.user_atas(x_to_y);
+        let mut temporary = [None; 2];
+
+        if is_native_mint(mint_in)
+            && wrap_sol(
+                self.user,
+                user_in,
+                mint_in,
+                amount_in,
+                self.system_program,
+                self.token_program,
+            )?
+        {
+            temporary[0] = Some(user_in);
+        }
+
+        if self.recipient(x_to_y).is_none()
+            && create_temporary_wsol(
+                self.user,
+                user_out,
+                mint_out,
+                self.system_program,
+                self.token_program,
+            )?
+        {
+            temporary[1] = Some(user_out);
+        }
+
+        Ok(temporary)
+    }
+
+    pub fn unwrap_temporary(
+        &self,
+        temporary: [Option<&'a AccountInfo>; 2],
+    ) -> Result<(), ProgramError> {
+        for ata in temporary.into_iter().flatten() {
+            unwrap_sol(self.user, ata)?;
+        }
+        Ok(())
+    }
+
+    // trailing token account of the output mint, it receives the swap output
+    // instead of the user's ata
+    pub fn recipient(&self, x_to_y: bool) -> Option<&'a AccountInfo> {
+        if x_to_y {
+            self.token_account_y
+        } else {
+            self.token_account_x
+        }
+    }
+
+    // trailing token account of the input mint, it belongs to whoever routed the
+    // swap here and receives the referral share of the fee
+    pub fn referrer(&self, x_to_y: bool) -> Option<&'a AccountInfo> {
+        if x_to_y {
+            self.token_account_x
+        } else {
+            self.token_account_y
+        }
+    }
+
+    // moves `amount` from the user into the pool and returns what the vault actually
+    // received, which is less than `amount` for mints charging a transfer fee
+    pub fn transfer_in(&self, x_to_y: bool, amount: u64) -> Result<u64, ProgramError> {
+        let (user_in, vault_in, mint_in) = if x_to_y {
+            (self.user_x_ata, self.vault_x, self.mint_x)
+        } else {
+            (self.user_y_ata, self.vault_y, self.mint_y)
+        };
+
+        let before = vault_amount(vault_in, self.config, mint_in)?;
+
+        TransferChecked {
+            from: user_in,
+            mint: mint_in,
+            to: vault_in,
+            authority: self.user,
+            amount,
+        }
+        .invoke()?;
+
+        let after = vault_amount(vault_in, self.config, mint_in)?;
+
+        Ok(after
+            .checked_sub(before)
+            .ok_or(PinocchioError::MathOverflow)?)
+    }
+
+    // records the protocol share of the fee charged on `amount_in`
+    pub fn accrue_protocol_fee(
+        &self,
+        pool: &SwapPool,
+        x_to_y: bool,
+        amount_in: u64,
+    ) -> Result<(), ProgramError> {
+        if !pool.protocol_fee_bps.is_zero() {
+            let protocol_fee = fee_share(amount_in, pool.fee, pool.protocol_fee_bps)?;
+            Config::load_mut(self.config)?.accrue_protocol_fee(x_to_y, protocol_fee)?;
+        }
+        Ok(())
+    }
+
+    // see SwapPool::record_swap
+    pub fn record_swap(&self, pool: &SwapPool, x_to_y: bool, amount_in: u64) -> ProgramResult {
+        pool.record_swap(
+            self.config,
+            x_to_y,
+            amount_in,
+            self.referrer(x_to_y).is_some(),
+        )
+    }
+
+    // applies the launch window to a trade paying out `amount_out`: swaps may only
+    // buy the launched token, the buy counts against the pool's per slot cap, and
+    // with a wallet cap it is added to the user's LaunchBuys account, created by
+    // their first buy in the window (the system program has to be passed for it)
+    pub fn record_launch_buy(
+        &self,
+        pool: &SwapPool,
+        x_to_y: bool,
+        amount_out: u64,
+    ) -> ProgramResult {
+        let Some(launch_guard) = pool.launch_guard else {
+            return Ok(());
+        };
+
+        if x_to_y != launch_guard.x_to_y {
+            return Err(PinocchioError::LaunchGuardActive.into());
+        }
+
+        Config::load_mut(self.config)?.record_launch_slot_buy(amount_out, Clock::get()?.slot)?;
+
+        if launch_guard.wallet_cap == 0 {
+            return Ok(());
+        }
+
+        let launch_buys = self.launch_buys.ok_or(PinocchioError::LaunchBuysMissing)?;
+
+        if launch_buys.data_is_empty() {
+            if self.system_program.is_none() {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+
+            let (address, bump) = launch_buys_address(self.config.key(), self.user.key());
+            if address.ne(launch_buys.key()) {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            let bump = [bump];
+            let launch_buys_seeds = [
+                Seed::from(LAUNCH_BUYS_SEED),
+                Seed::from(self.config.key().as_ref()),
+                Seed::from(self.user.key().as_ref()),
+                Seed::from(&bump),
+            ];
+
+            ProgramAccount::init::<LaunchBuys>(
+                self.user,
+                launch_buys,
+                &launch_buys_seeds,
+                LaunchBuys::LEN,
+            )?;
+
+            LaunchBuys::load_mut(launch_buys)?.set_inner(
+                *self.config.key(),
+                *self.user.key(),
+                bump[0],
+            );
+        }
+
+        {
+            let buys = LaunchBuys::load(launch_buys)?;
+            if buys.config().ne(self.config.key()) || buys.user().ne(self.user.key()) {
+                return Err(PinocchioError::LaunchBuysMissing.into());
+            }
+            buys.verify_address(launch_buys)?;
+        }
+
+        LaunchBuys::load_mut(launch_buys)?.record_buy(amount_out, launch_guard.wallet_cap)
+    }
+
+    // logs the trade, stamped with the pool's next event sequence number
+    pub fn emit_swap(&self, x_to_y: bool, amount_in: u64, amount_out: u64) -> ProgramResult {
+        let event_seq = Config::load_mut(self.config)?.next_event_seq()?;
+
+        SwapEvent {
+            user: *self.user.key(),
+            x_to_y,
+            amount_in,
+            amount_out,
+        }
+        .emit(self.config.key(), event_seq);
+
+        Ok(())
+    }
+
+    // pays the referrer its share of the fee charged on `amount_in`, out of the input
+    // vault. the share comes out of the part of the fee the lps would have kept
+    pub fn pay_referral(
+        &self,
+        pool: &SwapPool,
+        x_to_y: bool,
+        amount_in: u64,
+    ) -> Result<(), ProgramError> {
+        let Some(referrer) = self.referrer(x_to_y) else {
+            return Ok(());
+        };
+
+        let referral_fee = fee_share(amount_in, pool.fee, pool.referral_fee_bps)?;
+        if referral_fee == 0 {
+            return Ok(());
+        }
+
+        let (vault_in, mint_in) = if x_to_y {
+            (self.vault_x, self.mint_x)
+        } else {
+            (self.vault_y, self.mint_y)
+        };
+
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.mint_x.key().as_ref()),
+            Seed::from(self.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault_in,
+            mint: mint_in,
+            to: referrer,
+            authority: self.config,
+            amount: referral_fee,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        Ok(())
+    }
+
+    // pays the `royalty` taken off the input out of the input vault to the royalty
+    // destination's token account for the input mint and logs it
+    pub fn pay_royalty(&self, pool: &SwapPool, x_to_y: bool, royalty: u64) -> ProgramResult {
+        if royalty == 0 {
+            return Ok(());
+        }
+
+        let (vault_in, mint_in, royalty_account) = if x_to_y {
+            (self.vault_x, self.mint_x, self.royalty_account_x)
+        } else {
+            (self.vault_y, self.mint_y, self.royalty_account_y)
+        };
+        let royalty_account = royalty_account.ok_or(PinocchioError::RoyaltyAccountMissing)?;
+
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.mint_x.key().as_ref()),
+            Seed::from(self.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault_in,
+            mint: mint_in,
+            to: royalty_account,
+            authority: self.config,
+            amount: royalty,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        let event_seq = Config::load_mut(self.config)?.next_event_seq()?;
+        RoyaltyEvent {
+            user: *self.user.key(),
+            x_to_y,
+            amount: royalty,
+        }
+        .emit(self.config.key(), event_seq);
+
+        Ok(())
+    }
+
+    // pays `amount` out of the pool to the recipient, or the user's ata when none
+    // was passed, the config pda signs for the vault
+    pub fn transfer_out(
+        &self,
+        pool: &SwapPool,
+        x_to_y: bool,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        let (vault_out, user_out, mint_out) = if x_to_y {
+            (self.vault_y, self.user_y_ata, self.mint_y)
+        } else {
+            (self.vault_x, self.user_x_ata, self.mint_x)
+        };
+
+        let to = self.recipient(x_to_y).unwrap_or(user_out);
+
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.mint_x.key().as_ref()),
+            Seed::from(self.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to,
+            authority: self.config,
+            amount,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        Ok(())
+    }
+}
+
+pub struct SwapInstruction {
+    // exact amount the user sends
+    pub amount_in: u64,
+    // least the user is willing to receive for it, after any transfer fee
+    pub min_out: u64,
+    pub x_to_y: bool,
+    // unix timestamp after which the swap is rejected
+    pub expiration: i64,
+    // optional guard for aggregators executing an off chain quote
+    pub expected_pool: Option<ExpectedPool>,
+}
+
+// pool configuration a quote was computed against, the swap is rejected once the
+// live pool no longer matches it instead of executing the stale quote
+pub struct ExpectedPool {
+    // swap fee in force when the quote was taken
+    pub fee: Bps,
+    // pool version the quote saw. every admin op bumps the admin nonce, so a pause,
+    // band or fee share change after the quote invalidates it
+    pub admin_nonce: u64,
+}
+
+impl ExpectedPool {
+    pub fn check(&self, pool: &SwapPool) -> Result<(), ProgramError> {
+        if self.fee != pool.fee || self.admin_nonce != pool.admin_nonce {
+            return Err(PinocchioError::StalePoolQuote.into());
+        }
+        Ok(())
+    }
+}
+
+// instruction data length without and with the expected pool guard
+pub const SWAP_DATA_LEN: usize = size_of::<u64>() * 2 + 1 + size_of::<i64>();
+pub const SWAP_EXPECTED_POOL_DATA_LEN: usize = SWAP_DATA_LEN + size_of::<u16>() + size_of::<u64>();
+
+impl<'a> TryFrom<&'a [u8]> for SwapInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        // a trailing fee (u16) and admin nonce (u64) enable the expected pool guard
+        if data.len() != SWAP_DATA_LEN && data.len() != SWAP_EXPECTED_POOL_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount_in = read_u64(data, 0)?;
+        let min_out = read_u64(data, 8)?;
+        let x_to_y = read_bool(data, 16)?;
+        let expiration = read_i64(data, 17)?;
+
+        if amount_in == 0 || min_out == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(PinocchioError::Expired.into());
+        }
+
+        let expected_pool = if data.len() == SWAP_EXPECTED_POOL_DATA_LEN {
+            Some(ExpectedPool {
+                fee: Bps::new(read_u16(data, SWAP_DATA_LEN)?),
+                admin_nonce: read_u64(data, SWAP_DATA_LEN + size_of::<u16>())?,
+            })
+        } else {
+            None
+        };
+
+        Ok(Self {
+            amount_in,
+            min_out,
+            x_to_y,
+            expiration,
+            expected_pool,
+        })
+    }
+}
+
+pub struct Swap<'a> {
+    pub accounts: SwapAccounts<'a>,
+    pub instruction: SwapInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for Swap<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = SwapAccounts::try_from(accounts)?;
+        let instruction = SwapInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> Swap<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &3;
+
+    pub fn process(&self) -> ProgramResult {
+        let x_to_y = self.instruction.x_to_y;
+
+        let pool = self.accounts.load_pool()?;
+        if let Some(expected_pool) = &self.instruction.expected_pool {
+            expected_pool.check(&pool)?;
+        }
+
+        let (reserve_in, reserve_out) = pool.reserves(x_to_y);
+        let (_, mint_out) = self.accounts.mints(x_to_y);
+
+        let temporary = self
+            .accounts
+            .wrap_native(x_to_y, self.instruction.amount_in)?;
+
+        // priced on what the vault received, never on the requested amount
+        let received = self
+            .accounts
+            .transfer_in(x_to_y, self.instruction.amount_in)?;
+
+        // the royalty comes off the input first, the swap and its fee see the rest
+        let royalty = royalty_amount(received, pool.royalty_bps)?;
+        let amount_in = received
+            .checked_sub(royalty)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let amount_out =
+            pool.curve_type
+                .swap_exact_in(reserve_in, reserve_out, amount_in, pool.fee)?;
+
+        // the bound is on what reaches the user, any transfer fee on the way out counts
+        if net_transfer_amount(mint_out, pool.epoch, amount_out)? < self.instruction.min_out {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        pool.check_price_band(x_to_y, amount_in, amount_out)?;
+        self.accounts.record_launch_buy(&pool, x_to_y, amount_out)?;
+
+        self.accounts
+            .accrue_protocol_fee(&pool, x_to_y, amount_in)?;
+        self.accounts.record_swap(&pool, x_to_y, amount_in)?;
+        self.accounts.pay_referral(&pool, x_to_y, amount_in)?;
+        self.accounts.pay_royalty(&pool, x_to_y, royalty)?;
+        self.accounts.transfer_out(&pool, x_to_y, amount_out)?;
+        self.accounts.unwrap_temporary(temporary)?;
+
+        self.accounts.emit_swap(x_to_y, amount_in, amount_out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(fee: u16, admin_nonce: u64) -> SwapPool {
+        SwapPool {
+            curve_type: CurveType::ConstantProduct,
+            fee: Bps::new(fee),
+            protocol_fee_bps: Bps::new(0),
+            referral_fee_bps: Bps::new(0),
+            royalty_bps: Bps::new(0),
+            config_bump: 255,
+            seed: 0,
+            epoch: 0,
+            price_band: PriceBand {
+                min_price: 0,
+                max_price: 0,
+            },
+            reserve_x: 1000,
+            reserve_y: 1000,
+            lp_supply: LpAmount::new(1000),
+            admin_nonce,
+            launch_guard: None,
+        }
+    }
+
+    #[test]
+    fn expected_pool_rejects_a_changed_pool() {
+        let expected = ExpectedPool {
+            fee: Bps::new(30),
+            admin_nonce: 4,
+        };
+
+        assert!(expected.check(&pool(30, 4)).is_ok());
+        // a scheduled fee that activated after the quote
+        assert_eq!(
+            expected.check(&pool(25, 4)),
+            Err(PinocchioError::StalePoolQuote.into())
+        );
+        // any admin op that landed after the quote
+        assert_eq!(
+            expected.check(&pool(30, 5)),
+            Err(PinocchioError::StalePoolQuote.into())
+        );
+    }
+}
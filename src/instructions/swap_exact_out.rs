@@ -0,0 +1,138 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{gross_transfer_amount, read_bool, read_i64, read_u64, SwapAccounts},
+    state::{amount_with_royalty, royalty_amount},
+};
+
+pub struct SwapExactOutInstruction {
+    // exact amount the user receives
+    pub amount_out: u64,
+    // most the user is willing to pay for it
+    pub max_in: u64,
+    pub x_to_y: bool,
+    // unix timestamp after which the swap is rejected
+    pub expiration: i64,
+}
+
+pub const SWAP_EXACT_OUT_DATA_LEN: usize = size_of::<u64>() * 2 + 1 + size_of::<i64>();
+
+impl<'a> TryFrom<&'a [u8]> for SwapExactOutInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != SWAP_EXACT_OUT_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount_out = read_u64(data, 0)?;
+        let max_in = read_u64(data, 8)?;
+        let x_to_y = read_bool(data, 16)?;
+        let expiration = read_i64(data, 17)?;
+
+        if amount_out == 0 || max_in == 0 {
+            return Err(PinocchioError::InvalidAmount.into());
+        }
+
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(PinocchioError::Expired.into());
+        }
+
+        Ok(Self {
+            amount_out,
+            max_in,
+            x_to_y,
+            expiration,
+        })
+    }
+}
+
+pub struct SwapExactOut<'a> {
+    pub accounts: SwapAccounts<'a>,
+    pub instruction: SwapExactOutInstruction,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for SwapExactOut<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = SwapAccounts::try_from(accounts)?;
+        let instruction = SwapExactOutInstruction::try_from(data)?;
+
+        Ok(Self {
+            accounts,
+            instruction,
+        })
+    }
+}
+
+impl<'a> SwapExactOut<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &6;
+
+    pub fn process(&self) -> ProgramResult {
+        let pool = self.accounts.load_pool()?;
+        let (reserve_in, reserve_out) = pool.reserves(self.instruction.x_to_y);
+        let (mint_in, mint_out) = self.accounts.mints(self.instruction.x_to_y);
+
+        // the vault sends enough for the user to net amount_out after any transfer fee
+        let gross_out = gross_transfer_amount(mint_out, pool.epoch, self.instruction.amount_out)?;
+
+        let amount_in =
+            pool.curve_type
+                .swap_exact_out(reserve_in, reserve_out, gross_out, pool.fee)?;
+
+        // the royalty comes off the input first, so the vault has to net enough to
+        // still cover amount_in once it is taken
+        let amount_in_with_royalty = amount_with_royalty(amount_in, pool.royalty_bps)?;
+
+        // and the user sends enough for the vault to net that
+        let gross_in = gross_transfer_amount(mint_in, pool.epoch, amount_in_with_royalty)?;
+
+        if gross_in > self.instruction.max_in {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        pool.check_price_band(self.instruction.x_to_y, amount_in, gross_out)?;
+        self.accounts
+            .record_launch_buy(&pool, self.instruction.x_to_y, gross_out)?;
+
+        let temporary = self
+            .accounts
+            .wrap_native(self.instruction.x_to_y, gross_in)?;
+
+        // priced on what the vault received, never on the requested amount
+        let received = self
+            .accounts
+            .transfer_in(self.instruction.x_to_y, gross_in)?;
+
+        if received < amount_in_with_royalty {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        let royalty = royalty_amount(received, pool.royalty_bps)?;
+        let received = received
+            .checked_sub(royalty)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        self.accounts
+            .accrue_protocol_fee(&pool, self.instruction.x_to_y, received)?;
+        self.accounts
+            .record_swap(&pool, self.instruction.x_to_y, received)?;
+        self.accounts
+            .pay_referral(&pool, self.instruction.x_to_y, received)?;
+        self.accounts
+            .pay_royalty(&pool, self.instruction.x_to_y, royalty)?;
+        self.accounts
+            .transfer_out(&pool, self.instruction.x_to_y, gross_out)?;
+        self.accounts.unwrap_temporary(temporary)?;
+
+        self.accounts
+            .emit_swap(self.instruction.x_to_y, received, gross_out)
+    }
+}
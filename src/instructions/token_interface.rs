@@ -0,0 +1,304 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    cpi::invoke_signed,
+    instruction::{AccountMeta, Instruction, Signer},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    ProgramResult,
+};
+
+use crate::instructions::{
+    read_bytes, token_program_of, AccountCheck, MintInterface, TokenAccount, TOKEN_2022_PROGRAM_ID,
+};
+
+// base token account layout, the same under both token programs
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const TOKEN_ACCOUNT_MINT_OFFSET: usize = 0;
+const TOKEN_ACCOUNT_OWNER_OFFSET: usize = 32;
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+// base mint layout
+const MINT_SUPPLY_OFFSET: usize = 36;
+const MINT_DECIMALS_OFFSET: usize = 44;
+
+const ACCOUNT_STATE_UNINITIALIZED: u8 = 0;
+const ACCOUNT_STATE_FROZEN: u8 = 2;
+
+// a token-2022 account longer than the base layout is tagged right after it, so an
+// extended mint can't be read as a token account
+const ACCOUNT_TYPE_OFFSET: usize = TOKEN_ACCOUNT_LEN;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+
+// token program instructions this program issues, both programs share the numbering
+const MINT_TO: u8 = 7;
+const BURN: u8 = 8;
+const CLOSE_ACCOUNT: u8 = 9;
+const TRANSFER_CHECKED: u8 = 12;
+
+// base fields of a token account owned by either token program, copied out so no
+// borrow of the account is held across the cpis that follow
+pub struct TokenAccountData {
+    mint: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    state: u8,
+}
+
+impl TokenAccountData {
+    pub fn from_account_info(account: &AccountInfo) -> Result<Self, ProgramError> {
+        TokenAccount::check(account)?;
+
+        let data = account.try_borrow_data()?;
+
+        if data.len() > TOKEN_ACCOUNT_LEN && data[ACCOUNT_TYPE_OFFSET] != ACCOUNT_TYPE_ACCOUNT {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let state = data[TOKEN_ACCOUNT_STATE_OFFSET];
+        if state == ACCOUNT_STATE_UNINITIALIZED {
+            return Err(ProgramError::UninitializedAccount);
+        }
+
+        Ok(Self {
+            mint: read_bytes(&data, TOKEN_ACCOUNT_MINT_OFFSET)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            owner: read_bytes(&data, TOKEN_ACCOUNT_OWNER_OFFSET)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            amount: read_bytes(&data, TOKEN_ACCOUNT_AMOUNT_OFFSET)
+                .map(u64::from_le_bytes)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            state,
+        })
+    }
+
+    pub fn mint(&self) -> &Pubkey {
+        &self.mint
+    }
+
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.state == ACCOUNT_STATE_FROZEN
+    }
+}
+
+// base fields of a mint owned by either token program
+pub struct MintData {
+    supply: u64,
+    decimals: u8,
+}
+
+impl MintData {
+    pub fn from_account_info(mint: &AccountInfo) -> Result<Self, ProgramError> {
+        MintInterface::check(mint)?;
+
+        let data = mint.try_borrow_data()?;
+
+        Ok(Self {
+            supply: read_bytes(&data, MINT_SUPPLY_OFFSET)
+                .map(u64::from_le_bytes)
+                .ok_or(ProgramError::InvalidAccountData)?,
+            decimals: read_bytes::<1>(&data, MINT_DECIMALS_OFFSET)
+                .ok_or(ProgramError::InvalidAccountData)?[0],
+        })
+    }
+
+    pub fn supply(&self) -> u64 {
+        self.supply
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+}
+
+// a cpi only reaches a program passed to the instruction. the legacy token program
+// has its own slot everywhere (the lp mint lives under it), instructions moving a
+// token-2022 mint take the token-2022 program as their last account
+pub fn split_token_2022_program(
+    accounts: &[AccountInfo],
+) -> (Option<&AccountInfo>, &[AccountInfo]) {
+    match accounts.split_last() {
+        Some((last, rest)) if last.key() == &TOKEN_2022_PROGRAM_ID => (Some(last), rest),
+        _ => (None, accounts),
+    }
+}
+
+// for instructions with no other trailing accounts, anything but the token-2022
+// program after the fixed accounts is rejected
+pub fn trailing_token_2022_program(
+    remaining: &[AccountInfo],
+) -> Result<Option<&AccountInfo>, ProgramError> {
+    match split_token_2022_program(remaining) {
+        (token_2022_program, []) => Ok(token_2022_program),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+// token program to pass when creating an ata of `mint`, the ata program creates the
+// account under whichever program it is given
+pub fn token_program_account<'a>(
+    mint: &AccountInfo,
+    token_program: &'a AccountInfo,
+    token_2022_program: Option<&'a AccountInfo>,
+) -> Result<&'a AccountInfo, ProgramError> {
+    if mint.is_owned_by(&TOKEN_2022_PROGRAM_ID) {
+        token_2022_program.ok_or(ProgramError::NotEnoughAccountKeys)
+    } else {
+        Ok(token_program)
+    }
+}
+
+// transfer through whichever token program owns `mint`. token-2022 rejects a plain
+// transfer for mints with a transfer fee, TransferChecked works under both programs
+pub struct TransferChecked<'a> {
+    pub from: &'a AccountInfo,
+    pub mint: &'a AccountInfo,
+    pub to: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl TransferChecked<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let mut data = [0u8; 10];
+        data[0] = TRANSFER_CHECKED;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+        data[9] = MintData::from_account_info(self.mint)?.decimals();
+
+        let account_metas = [
+            AccountMeta::writable(self.from.key()),
+            AccountMeta::readonly(self.mint.key()),
+            AccountMeta::writable(self.to.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        invoke_signed(
+            &Instruction {
+                program_id: token_program_of(self.mint),
+                accounts: &account_metas,
+                data: &data,
+            },
+            &[self.from, self.mint, self.to, self.authority],
+            signers,
+        )
+    }
+}
+
+// mint through whichever token program owns `mint`
+pub struct MintTo<'a> {
+    pub mint: &'a AccountInfo,
+    pub account: &'a AccountInfo,
+    pub mint_authority: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl MintTo<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let mut data = [0u8; 9];
+        data[0] = MINT_TO;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+
+        let account_metas = [
+            AccountMeta::writable(self.mint.key()),
+            AccountMeta::writable(self.account.key()),
+            AccountMeta::readonly_signer(self.mint_authority.key()),
+        ];
+
+        invoke_signed(
+            &Instruction {
+                program_id: token_program_of(self.mint),
+                accounts: &account_metas,
+                data: &data,
+            },
+            &[self.mint, self.account, self.mint_authority],
+            signers,
+        )
+    }
+}
+
+// burn through whichever token program owns `mint`
+pub struct Burn<'a> {
+    pub account: &'a AccountInfo,
+    pub mint: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+    pub amount: u64,
+}
+
+impl Burn<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let mut data = [0u8; 9];
+        data[0] = BURN;
+        data[1..9].copy_from_slice(&self.amount.to_le_bytes());
+
+        let account_metas = [
+            AccountMeta::writable(self.account.key()),
+            AccountMeta::writable(self.mint.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        invoke_signed(
+            &Instruction {
+                program_id: token_program_of(self.mint),
+                accounts: &account_metas,
+                data: &data,
+            },
+            &[self.account, self.mint, self.authority],
+            signers,
+        )
+    }
+}
+
+// close through whichever token program owns `account`
+pub struct CloseAccount<'a> {
+    pub account: &'a AccountInfo,
+    pub destination: &'a AccountInfo,
+    pub authority: &'a AccountInfo,
+}
+
+impl CloseAccount<'_> {
+    #[inline(always)]
+    pub fn invoke(&self) -> ProgramResult {
+        self.invoke_signed(&[])
+    }
+
+    pub fn invoke_signed(&self, signers: &[Signer]) -> ProgramResult {
+        let account_metas = [
+            AccountMeta::writable(self.account.key()),
+            AccountMeta::writable(self.destination.key()),
+            AccountMeta::readonly_signer(self.authority.key()),
+        ];
+
+        invoke_signed(
+            &Instruction {
+                program_id: token_program_of(self.account),
+                accounts: &account_metas,
+                data: &[CLOSE_ACCOUNT],
+            },
+            &[self.account, self.destination, self.authority],
+            signers,
+        )
+    }
+}
@@ -0,0 +1,156 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    instructions::{
+        assert_unique_accounts, AccountCheck, CloseAccount, SignerAccount, TokenAccountData,
+        TransferChecked, WritableAccount,
+    },
+    math::LpAmount,
+    pdas::LP_LOCK_SEED,
+    state::{Config, LpLock},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct UnlockLpAccounts<'a> {
+    pub owner: &'a AccountInfo,
+    pub config: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub lock: &'a AccountInfo,
+    pub escrow: &'a AccountInfo,
+    // receives the unlocked lp
+    pub owner_lp_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for UnlockLpAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [owner, config, lp_mint, lock, escrow, owner_lp_ata, token_program] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(owner)?;
+        WritableAccount::check(owner)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(lock)?;
+        WritableAccount::check(escrow)?;
+        WritableAccount::check(owner_lp_ata)?;
+
+        assert_unique_accounts(&[owner, config, lp_mint, lock, escrow, owner_lp_ata])?;
+
+        Ok(Self {
+            owner,
+            config,
+            lp_mint,
+            lock,
+            escrow,
+            owner_lp_ata,
+            token_program,
+        })
+    }
+}
+
+pub const UNLOCK_LP_DATA_LEN: usize = 0;
+
+pub struct UnlockLp<'a> {
+    pub accounts: UnlockLpAccounts<'a>,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for UnlockLp<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        if data.len() != UNLOCK_LP_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let accounts = UnlockLpAccounts::try_from(accounts)?;
+
+        Ok(Self { accounts })
+    }
+}
+
+impl<'a> UnlockLp<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &22;
+
+    // once the unlock time has passed, returns the escrowed lp to the owner and closes
+    // both the escrow and the lock, the rent of each goes back to the owner
+    pub fn process(&self) -> ProgramResult {
+        let (lock_seed, lock_bump, locked) = {
+            let lock = LpLock::load(self.accounts.lock)?;
+            lock.verify_address(self.accounts.lock)?;
+            lock.require_owner(self.accounts.owner)?;
+            lock.require_unlocked(Clock::get()?.unix_timestamp)?;
+
+            if lock.config().ne(self.accounts.config.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            (lock.seed(), lock.bump(), LpAmount::new(lock.amount()))
+        };
+
+        {
+            let mut config = Config::load_mut(self.accounts.config)?;
+
+            if config.lp_mint().ne(self.accounts.lp_mint.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            config.remove_locked_lp_amount(locked)?;
+        }
+
+        // anything sent to the escrow on top of the lock goes back as well
+        let escrowed = {
+            let escrow = TokenAccountData::from_account_info(self.accounts.escrow)?;
+
+            if escrow.owner().ne(self.accounts.lock.key())
+                || escrow.mint().ne(self.accounts.lp_mint.key())
+            {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            escrow.amount()
+        };
+
+        let lock_seed = lock_seed.to_le_bytes();
+        let lock_bump = [lock_bump];
+        let lock_seeds = [
+            Seed::from(LP_LOCK_SEED),
+            Seed::from(self.accounts.config.key().as_ref()),
+            Seed::from(self.accounts.owner.key().as_ref()),
+            Seed::from(&lock_seed),
+            Seed::from(&lock_bump),
+        ];
+        let lock_signer = [Signer::from(&lock_seeds)];
+
+        if escrowed != 0 {
+            TransferChecked {
+                from: self.accounts.escrow,
+                mint: self.accounts.lp_mint,
+                to: self.accounts.owner_lp_ata,
+                authority: self.accounts.lock,
+                amount: escrowed,
+            }
+            .invoke_signed(&lock_signer)?;
+        }
+
+        CloseAccount {
+            account: self.accounts.escrow,
+            destination: self.accounts.owner,
+            authority: self.accounts.lock,
+        }
+        .invoke_signed(&lock_signer)?;
+
+        // the program owns the lock, so its lamports can be moved directly
+        *self.accounts.owner.try_borrow_mut_lamports()? += self.accounts.lock.lamports();
+        self.accounts.lock.close()
+    }
+}
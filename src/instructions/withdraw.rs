@@ -1,17 +1,29 @@
 use pinocchio::{
     account_info::AccountInfo,
+    instruction::{Seed, Signer},
     program_error::ProgramError,
     sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
 };
 
 use crate::{
     error::PinocchioError,
+    events::WithdrawEvent,
     instructions::{
-        AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
-        AssociatedTokenAccountInit, SignerAccount,
+        assert_unique_accounts, is_temporary_wsol, net_transfer_amount, read_i64, read_u64,
+        slot_hash_exists, split_token_2022_program, token_program_account, unwrap_sol,
+        vault_amount, AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
+        AssociatedTokenAccountInit, Burn, MintData, SignerAccount, TransferChecked,
+        WritableAccount, SLOT_HASHES_SYSVAR_ID,
     },
+    math::{LpAmount, TokenAmount},
+    state::{withdraw_amounts, Config},
 };
 
+// accounts are expected in the same order as the fields below, followed by
+// the associated token program used when the user atas need to be created, the
+// SlotHashes sysvar with a slot deadline and, when a pool mint is under it, the
+// token-2022 program
 pub struct WithdrawAccounts<'a> {
     pub user: &'a AccountInfo,
 
@@ -31,77 +43,141 @@ pub struct WithdrawAccounts<'a> {
 
     pub token_program: &'a AccountInfo,
     pub system_program: &'a AccountInfo,
+    pub slot_hashes: Option<&'a AccountInfo>,
+    pub token_2022_program: Option<&'a AccountInfo>,
 }
 
 impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawAccounts<'a> {
     type Error = ProgramError;
 
     fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
-        let [user, mint_lp, vault_x, vault_y, mint_x, mint_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program, system_program, _] =
+        let [user, mint_x, mint_y, mint_lp, vault_x, vault_y, user_x_ata, user_y_ata, user_lp_ata, config, token_program, system_program, _, remaining @ ..] =
             accounts
         else {
             return Err(ProgramError::InvalidAccountData);
         };
 
         SignerAccount::check(user)?;
+        WritableAccount::check(user)?;
+        WritableAccount::check(mint_lp)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(user_x_ata)?;
+        WritableAccount::check(user_y_ata)?;
+        WritableAccount::check(user_lp_ata)?;
+        // updates the twap accumulators
+        WritableAccount::check(config)?;
+
+        let (token_2022_program, remaining) = split_token_2022_program(remaining);
+        let slot_hashes = match remaining {
+            [] => None,
+            [slot_hashes] if slot_hashes.key() == &SLOT_HASHES_SYSVAR_ID => Some(slot_hashes),
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        assert_unique_accounts(&[
+            user,
+            mint_x,
+            mint_y,
+            mint_lp,
+            vault_x,
+            vault_y,
+            user_x_ata,
+            user_y_ata,
+            user_lp_ata,
+            config,
+        ])?;
 
         AssociatedTokenAccount::check(vault_x, config, mint_x)?;
         AssociatedTokenAccount::check(vault_y, config, mint_y)?;
 
-        AssociatedTokenAccount::check(user_x_ata, user, mint_x)?;
-        AssociatedTokenAccount::check(user_y_ata, user, mint_y)?;
+        // user_x_ata and user_y_ata may not exist yet, they are checked (or created)
+        // by init_if_needed before processing
         AssociatedTokenAccount::check(user_lp_ata, user, mint_lp)?;
 
         Ok(Self {
             user,
+            mint_x,
+            mint_y,
             mint_lp,
             vault_x,
             vault_y,
-            mint_x,
-            mint_y,
             user_x_ata,
             user_y_ata,
             user_lp_ata,
             config,
             token_program,
             system_program,
+            slot_hashes,
+            token_2022_program,
         })
     }
 }
 
 pub struct WithdrawInstructions {
-    pub amount: u64,
-    pub min_x: u64,
-    pub min_y: u64,
-    pub expiration: u64,
+    pub amount: LpAmount,
+    pub min_x: TokenAmount,
+    pub min_y: TokenAmount,
+    // unix timestamp after which the withdraw is rejected
+    pub expiration: i64,
+    // optional slot based deadline for offline (durable nonce) signers,
+    // the withdraw must land within max_slot_age slots of recent_slot
+    pub recent_slot: u64,
+    pub max_slot_age: Option<u64>,
 }
 
+// instruction data length without and with the slot deadline
+pub const WITHDRAW_DATA_LEN: usize = size_of::<u64>() * 4;
+pub const WITHDRAW_SLOT_DATA_LEN: usize = size_of::<u64>() * 6;
+
 impl TryFrom<&[u8]> for WithdrawInstructions {
     type Error = ProgramError;
 
     fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() != size_of::<u64>() * 4 {
+        if data.len() != WITHDRAW_DATA_LEN && data.len() != WITHDRAW_SLOT_DATA_LEN {
             return Err(ProgramError::InvalidInstructionData);
         }
 
-        let amount = u64::from_le_bytes(data[0..8].try_into().unwrap());
-        let min_x = u64::from_le_bytes(data[8..16].try_into().unwrap());
-        let min_y = u64::from_le_bytes(data[16..24].try_into().unwrap());
-        let expiration = u64::from_le_bytes(data[24..32].try_into().unwrap());
+        let amount = read_u64(data, 0)?;
+        let min_x = read_u64(data, 8)?;
+        let min_y = read_u64(data, 16)?;
+        let expiration = read_i64(data, 24)?;
+
+        let (recent_slot, max_slot_age) = if data.len() == WITHDRAW_SLOT_DATA_LEN {
+            (read_u64(data, 32)?, Some(read_u64(data, 40)?))
+        } else {
+            (0, None)
+        };
 
         if amount <= 0 || min_x <= 0 || min_y <= 0 {
             return Err(PinocchioError::LessThanMinimum.into());
         }
 
-        if expiration > Clock::get()?.unix_timestamp as u64 {
+        let clock = Clock::get()?;
+
+        if clock.unix_timestamp > expiration {
             return Err(PinocchioError::Expired.into());
         }
 
+        // durable nonce transactions never expire on their own, so the slot window
+        // is what bounds how long a signed withdraw stays valid
+        if let Some(max_slot_age) = max_slot_age {
+            if recent_slot > clock.slot {
+                return Err(ProgramError::InvalidInstructionData);
+            }
+
+            if clock.slot - recent_slot > max_slot_age {
+                return Err(PinocchioError::Expired.into());
+            }
+        }
+
         Ok(Self {
-            amount,
-            min_x,
-            min_y,
+            amount: LpAmount::new(amount),
+            min_x: TokenAmount::new(min_x),
+            min_y: TokenAmount::new(min_y),
             expiration,
+            recent_slot,
+            max_slot_age,
         })
     }
 }
@@ -109,6 +185,9 @@ impl TryFrom<&[u8]> for WithdrawInstructions {
 pub struct Withdraw<'a> {
     pub accounts: WithdrawAccounts<'a>,
     pub instructions: WithdrawInstructions,
+    // temporary wsol atas created for the payout, closed at the end to pay out sol
+    pub unwrap_x: bool,
+    pub unwrap_y: bool,
 }
 
 impl<'a> TryFrom<(&'a [AccountInfo], &[u8])> for Withdraw<'a> {
@@ -118,13 +197,38 @@ impl<'a> TryFrom<(&'a [AccountInfo], &[u8])> for Withdraw<'a> {
         let accounts = WithdrawAccounts::try_from(accounts)?;
         let instructions = WithdrawInstructions::try_from(data)?;
 
+        // the window alone would take any slot number the signer made up, a real
+        // recent slot of this fork has its hash in the sysvar
+        if instructions.max_slot_age.is_some() {
+            let slot_hashes = accounts
+                .slot_hashes
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            if !slot_hash_exists(slot_hashes, instructions.recent_slot)? {
+                return Err(PinocchioError::UnknownRecentSlot.into());
+            }
+        }
+
+        // mirrors the deposit floor so lp can't be unwound in dust sized chunks
+        if instructions.amount < Config::load(accounts.config)?.min_lp_burn() {
+            return Err(PinocchioError::LessThanMinimum.into());
+        }
+
+        // has to be decided before the atas below exist
+        let unwrap_x = is_temporary_wsol(accounts.user_x_ata, accounts.mint_x);
+        let unwrap_y = is_temporary_wsol(accounts.user_y_ata, accounts.mint_y);
+
         AssociatedTokenAccount::init_if_needed(
             accounts.user_x_ata,
             accounts.mint_x,
             accounts.user,
             accounts.user,
             accounts.system_program,
-            accounts.token_program,
+            token_program_account(
+                accounts.mint_x,
+                accounts.token_program,
+                accounts.token_2022_program,
+            )?,
         )?;
 
         AssociatedTokenAccount::init_if_needed(
@@ -133,12 +237,152 @@ impl<'a> TryFrom<(&'a [AccountInfo], &[u8])> for Withdraw<'a> {
             accounts.user,
             accounts.user,
             accounts.system_program,
-            accounts.token_program,
+            token_program_account(
+                accounts.mint_y,
+                accounts.token_program,
+                accounts.token_2022_program,
+            )?,
         )?;
 
         Ok(Self {
             accounts,
             instructions,
+            unwrap_x,
+            unwrap_y,
         })
     }
 }
+
+impl<'a> Withdraw<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &2;
+
+    pub fn process(&self) -> ProgramResult {
+        let (config_bump, seed, protocol_fees, locked_lp) = {
+            let config = Config::load(self.accounts.config)?;
+
+            // the config has to be the canonical pda, it signs for the vaults below
+            config.verify_address(self.accounts.config)?;
+            config.verify_pool_accounts(
+                self.accounts.mint_x,
+                self.accounts.mint_y,
+                self.accounts.vault_x,
+                self.accounts.vault_y,
+            )?;
+            config.require_withdrawable()?;
+
+            if config.lp_mint().ne(self.accounts.mint_lp.key()) {
+                return Err(ProgramError::InvalidAccountData);
+            }
+
+            (
+                config.config_bump(),
+                config.seed(),
+                config.protocol_fees(),
+                config.locked_lp(),
+            )
+        };
+
+        // read everything needed for the payout before any cpi borrows the accounts,
+        // accrued protocol fees sit in the vaults but don't belong to the lps
+        let reserve_x = vault_amount(
+            self.accounts.vault_x,
+            self.accounts.config,
+            self.accounts.mint_x,
+        )?
+        .checked_sub(protocol_fees.0)
+        .ok_or(PinocchioError::MathOverflow)?;
+        let reserve_y = vault_amount(
+            self.accounts.vault_y,
+            self.accounts.config,
+            self.accounts.mint_y,
+        )?
+        .checked_sub(protocol_fees.1)
+        .ok_or(PinocchioError::MathOverflow)?;
+        // the locked lp is part of the supply, so its share of the reserves never leaves
+        let lp_supply = LpAmount::new(MintData::from_account_info(self.accounts.mint_lp)?.supply())
+            .checked_add(locked_lp)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        let amounts = withdraw_amounts(
+            self.instructions.amount,
+            TokenAmount::new(reserve_x),
+            TokenAmount::new(reserve_y),
+            lp_supply,
+        )?;
+
+        if amounts.x < self.instructions.min_x || amounts.y < self.instructions.min_y {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        let clock = Clock::get()?;
+        let event_seq = {
+            let mut config = Config::load_mut(self.accounts.config)?;
+            config.update_price_cumulative(reserve_x, reserve_y, clock.unix_timestamp)?;
+            config.next_event_seq()?
+        };
+
+        // any token-2022 transfer fee comes out of the payout on its way to the user
+        let net_x = net_transfer_amount(self.accounts.mint_x, clock.epoch, amounts.x.get())?;
+        let net_y = net_transfer_amount(self.accounts.mint_y, clock.epoch, amounts.y.get())?;
+
+        Burn {
+            account: self.accounts.user_lp_ata,
+            mint: self.accounts.mint_lp,
+            authority: self.accounts.user,
+            amount: self.instructions.amount.get(),
+        }
+        .invoke()?;
+
+        // the config pda owns both vaults
+        let seed = seed.to_le_bytes();
+        let config_bump = [config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+        let signer = [Signer::from(&config_seeds)];
+
+        TransferChecked {
+            from: self.accounts.vault_x,
+            mint: self.accounts.mint_x,
+            to: self.accounts.user_x_ata,
+            authority: self.accounts.config,
+            amount: amounts.x.get(),
+        }
+        .invoke_signed(&signer)?;
+
+        TransferChecked {
+            from: self.accounts.vault_y,
+            mint: self.accounts.mint_y,
+            to: self.accounts.user_y_ata,
+            authority: self.accounts.config,
+            amount: amounts.y.get(),
+        }
+        .invoke_signed(&signer)?;
+
+        if self.unwrap_x {
+            unwrap_sol(self.accounts.user, self.accounts.user_x_ata)?;
+        }
+        if self.unwrap_y {
+            unwrap_sol(self.accounts.user, self.accounts.user_y_ata)?;
+        }
+
+        let event = WithdrawEvent {
+            user: *self.accounts.user.key(),
+            lp_amount: self.instructions.amount.get(),
+            gross_x: amounts.x.get(),
+            gross_y: amounts.y.get(),
+            fee_x: amounts.x.get() - net_x,
+            fee_y: amounts.y.get() - net_y,
+            net_x,
+            net_y,
+        };
+        event.emit(self.accounts.config.key(), event_seq);
+        event.set_return_data();
+
+        Ok(())
+    }
+}
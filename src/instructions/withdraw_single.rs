@@ -0,0 +1,358 @@
+use pinocchio::{
+    account_info::AccountInfo,
+    instruction::{Seed, Signer},
+    program_error::ProgramError,
+    sysvars::{clock::Clock, Sysvar},
+    ProgramResult,
+};
+
+use crate::{
+    error::PinocchioError,
+    events::WithdrawEvent,
+    instructions::{
+        assert_unique_accounts, create_temporary_wsol, is_temporary_wsol, net_transfer_amount,
+        read_bool, read_i64, read_u64, split_token_2022_program, token_program_account, unwrap_sol,
+        AccountCheck, AssociatedTokenAccount, AssociatedTokenAccountCheck,
+        AssociatedTokenAccountInit, Burn, MintInterface, SignerAccount, SwapPool, TransferChecked,
+        WritableAccount,
+    },
+    math::{LpAmount, TokenAmount},
+    state::{fee_amount, fee_share, withdraw_amounts, Config},
+};
+
+// accounts are expected in the same order as the fields below
+pub struct WithdrawSingleAccounts<'a> {
+    pub user: &'a AccountInfo,
+
+    pub mint_x: &'a AccountInfo,
+    pub mint_y: &'a AccountInfo,
+    pub lp_mint: &'a AccountInfo,
+
+    pub config: &'a AccountInfo,
+
+    pub vault_x: &'a AccountInfo,
+    pub vault_y: &'a AccountInfo,
+
+    // the user's ata of the mint paid out, created when missing
+    pub user_ata: &'a AccountInfo,
+    pub user_lp_ata: &'a AccountInfo,
+
+    pub token_program: &'a AccountInfo,
+    pub system_program: &'a AccountInfo,
+    pub associated_token_program: &'a AccountInfo,
+
+    // trailing accounts, the observation buffer as on swaps and the token-2022
+    // program, when a pool mint is under it, last
+    pub observations: Option<&'a AccountInfo>,
+    pub token_2022_program: Option<&'a AccountInfo>,
+}
+
+impl<'a> TryFrom<&'a [AccountInfo]> for WithdrawSingleAccounts<'a> {
+    type Error = ProgramError;
+
+    fn try_from(accounts: &'a [AccountInfo]) -> Result<Self, Self::Error> {
+        let [user, mint_x, mint_y, lp_mint, config, vault_x, vault_y, user_ata, user_lp_ata, token_program, system_program, associated_token_program, remaining @ ..] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        SignerAccount::check(user)?;
+        WritableAccount::check(user)?;
+        WritableAccount::check(config)?;
+        WritableAccount::check(lp_mint)?;
+        WritableAccount::check(vault_x)?;
+        WritableAccount::check(vault_y)?;
+        WritableAccount::check(user_ata)?;
+        WritableAccount::check(user_lp_ata)?;
+
+        MintInterface::check(mint_x)?;
+        MintInterface::check(mint_y)?;
+        let (token_2022_program, remaining) = split_token_2022_program(remaining);
+        let observations = match remaining {
+            [] => None,
+            [observations] => {
+                WritableAccount::check(observations)?;
+                Some(observations)
+            }
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+
+        if mint_x.key() == mint_y.key() {
+            return Err(PinocchioError::IdenticalTokenMints.into());
+        }
+
+        assert_unique_accounts(&[
+            user,
+            mint_x,
+            mint_y,
+            lp_mint,
+            config,
+            vault_x,
+            vault_y,
+            user_ata,
+            user_lp_ata,
+        ])?;
+
+        AssociatedTokenAccount::check(vault_x, config, mint_x)?;
+        AssociatedTokenAccount::check(vault_y, config, mint_y)?;
+        AssociatedTokenAccount::check(user_lp_ata, user, lp_mint)?;
+
+        Ok(Self {
+            user,
+            mint_x,
+            mint_y,
+            lp_mint,
+            config,
+            vault_x,
+            vault_y,
+            user_ata,
+            user_lp_ata,
+            token_program,
+            system_program,
+            associated_token_program,
+            observations,
+            token_2022_program,
+        })
+    }
+}
+
+pub struct WithdrawSingleInstruction {
+    pub amount: LpAmount,
+    // least the user is willing to receive, after any transfer fee
+    pub min_out: u64,
+    // true to be paid in x, false to be paid in y
+    pub x: bool,
+    // unix timestamp after which the withdraw is rejected
+    pub expiration: i64,
+}
+
+pub const WITHDRAW_SINGLE_DATA_LEN: usize = size_of::<u64>() * 2 + 1 + size_of::<i64>();
+
+impl<'a> TryFrom<&'a [u8]> for WithdrawSingleInstruction {
+    type Error = ProgramError;
+
+    fn try_from(data: &'a [u8]) -> Result<Self, Self::Error> {
+        if data.len() != WITHDRAW_SINGLE_DATA_LEN {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+
+        let amount = read_u64(data, 0)?;
+        let min_out = read_u64(data, 8)?;
+        let x = read_bool(data, 16)?;
+        let expiration = read_i64(data, 17)?;
+
+        if amount == 0 || min_out == 0 {
+            return Err(PinocchioError::LessThanMinimum.into());
+        }
+
+        if Clock::get()?.unix_timestamp > expiration {
+            return Err(PinocchioError::Expired.into());
+        }
+
+        Ok(Self {
+            amount: LpAmount::new(amount),
+            min_out,
+            x,
+            expiration,
+        })
+    }
+}
+
+pub struct WithdrawSingle<'a> {
+    pub accounts: WithdrawSingleAccounts<'a>,
+    pub instruction: WithdrawSingleInstruction,
+    // temporary wsol ata created for the payout, closed at the end to pay out sol
+    pub unwrap: bool,
+}
+
+impl<'a> TryFrom<(&'a [AccountInfo], &'a [u8])> for WithdrawSingle<'a> {
+    type Error = ProgramError;
+
+    fn try_from((accounts, data): (&'a [AccountInfo], &'a [u8])) -> Result<Self, Self::Error> {
+        let accounts = WithdrawSingleAccounts::try_from(accounts)?;
+        let instruction = WithdrawSingleInstruction::try_from(data)?;
+
+        // mirrors the deposit floor so lp can't be unwound in dust sized chunks
+        if instruction.amount < Config::load(accounts.config)?.min_lp_burn() {
+            return Err(PinocchioError::LessThanMinimum.into());
+        }
+
+        let mint = if instruction.x {
+            accounts.mint_x
+        } else {
+            accounts.mint_y
+        };
+
+        let unwrap = is_temporary_wsol(accounts.user_ata, mint);
+        if unwrap {
+            create_temporary_wsol(
+                accounts.user,
+                accounts.user_ata,
+                mint,
+                Some(accounts.system_program),
+                accounts.token_program,
+            )?;
+        } else {
+            AssociatedTokenAccount::init_if_needed(
+                accounts.user_ata,
+                mint,
+                accounts.user,
+                accounts.user,
+                accounts.system_program,
+                token_program_account(mint, accounts.token_program, accounts.token_2022_program)?,
+            )?;
+        }
+
+        Ok(Self {
+            accounts,
+            instruction,
+            unwrap,
+        })
+    }
+}
+
+impl<'a> WithdrawSingle<'a> {
+    pub const DISCRIMINATOR: &'a u8 = &16;
+
+    // zap out: burns lp for its share of both reserves and pays it out entirely in one
+    // token. the other share is swapped through the pool's curve at the post withdraw
+    // reserves without leaving its vault
+    pub fn process(&self) -> ProgramResult {
+        // the swap leg is gated like any other swap
+        let mut pool = SwapPool::load(
+            self.accounts.config,
+            self.accounts.mint_x,
+            self.accounts.mint_y,
+            self.accounts.lp_mint,
+            self.accounts.vault_x,
+            self.accounts.vault_y,
+            self.accounts.observations,
+        )?;
+        pool.require_no_launch_guard()?;
+        pool.require_no_royalty()?;
+
+        // the locked lp is part of the supply, so its share of the reserves never leaves
+        let amounts = withdraw_amounts(
+            self.instruction.amount,
+            TokenAmount::new(pool.reserve_x),
+            TokenAmount::new(pool.reserve_y),
+            pool.lp_supply,
+        )?;
+
+        // the swap prices against what is left once the lp share is taken out, and its
+        // fee goes to the lp that stays
+        pool.reserve_x -= amounts.x.get();
+        pool.reserve_y -= amounts.y.get();
+        pool.lp_supply = pool
+            .lp_supply
+            .checked_sub(self.instruction.amount)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        // the share of the other token is swapped into the paid out one
+        let x_to_y = !self.instruction.x;
+        let (swap_amount, kept, mint_out, vault_out) = if self.instruction.x {
+            (
+                amounts.y.get(),
+                amounts.x.get(),
+                self.accounts.mint_x,
+                self.accounts.vault_x,
+            )
+        } else {
+            (
+                amounts.x.get(),
+                amounts.y.get(),
+                self.accounts.mint_y,
+                self.accounts.vault_y,
+            )
+        };
+
+        let (swap_out, protocol_fee) = if swap_amount > 0 {
+            let (reserve_in, reserve_out) = pool.reserves(x_to_y);
+            let swap_out =
+                pool.curve_type
+                    .swap_exact_in(reserve_in, reserve_out, swap_amount, pool.fee)?;
+            pool.check_price_band(x_to_y, swap_amount, swap_out)?;
+
+            (
+                swap_out,
+                fee_share(swap_amount, pool.fee, pool.protocol_fee_bps)?,
+            )
+        } else {
+            (0, 0)
+        };
+
+        let amount_out = kept
+            .checked_add(swap_out)
+            .ok_or(PinocchioError::MathOverflow)?;
+
+        // the bound is on what reaches the user, any transfer fee on the way out counts
+        let net_out = net_transfer_amount(mint_out, pool.epoch, amount_out)?;
+        if net_out < self.instruction.min_out {
+            return Err(PinocchioError::SlipageExceeded.into());
+        }
+
+        if protocol_fee > 0 {
+            Config::load_mut(self.accounts.config)?.accrue_protocol_fee(x_to_y, protocol_fee)?;
+        }
+        pool.record_swap(self.accounts.config, x_to_y, swap_amount, false)?;
+
+        Burn {
+            account: self.accounts.user_lp_ata,
+            mint: self.accounts.lp_mint,
+            authority: self.accounts.user,
+            amount: self.instruction.amount.get(),
+        }
+        .invoke()?;
+
+        // the config pda owns both vaults
+        let seed = pool.seed.to_le_bytes();
+        let config_bump = [pool.config_bump];
+        let config_seeds = [
+            Seed::from(b"config"),
+            Seed::from(self.accounts.mint_x.key().as_ref()),
+            Seed::from(self.accounts.mint_y.key().as_ref()),
+            Seed::from(&seed),
+            Seed::from(&config_bump),
+        ];
+
+        TransferChecked {
+            from: vault_out,
+            mint: mint_out,
+            to: self.accounts.user_ata,
+            authority: self.accounts.config,
+            amount: amount_out,
+        }
+        .invoke_signed(&[Signer::from(&config_seeds)])?;
+
+        if self.unwrap {
+            unwrap_sol(self.accounts.user, self.accounts.user_ata)?;
+        }
+
+        // the paid out token loses any transfer fee, the swapped one the swap fee and
+        // reaches the user only as part of the paid out token
+        let transfer_fee = amount_out - net_out;
+        let swap_fee = fee_amount(swap_amount, pool.fee)?;
+        let (fee_x, fee_y, net_x, net_y) = if self.instruction.x {
+            (transfer_fee, swap_fee, net_out, 0)
+        } else {
+            (swap_fee, transfer_fee, 0, net_out)
+        };
+
+        let event_seq = Config::load_mut(self.accounts.config)?.next_event_seq()?;
+        let event = WithdrawEvent {
+            user: *self.accounts.user.key(),
+            lp_amount: self.instruction.amount.get(),
+            gross_x: amounts.x.get(),
+            gross_y: amounts.y.get(),
+            fee_x,
+            fee_y,
+            net_x,
+            net_y,
+        };
+        event.emit(self.accounts.config.key(), event_seq);
+        event.set_return_data();
+
+        Ok(())
+    }
+}
@@ -1,14 +1,22 @@
 #![no_std]
 #![allow(unexpected_cfgs)]
+#![cfg_attr(feature = "safe-deser", deny(unsafe_code))]
+// a panic aborts the transaction with an opaque error, every fallible read has to
+// surface a ProgramError instead
+#![deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
 
 use pinocchio::pubkey::Pubkey;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
 
+pub mod client;
 pub mod instructions;
+pub mod math;
+pub mod pdas;
 pub mod state;
 pub mod error;
+pub mod events;
 
 pub const ID: Pubkey = [
     0x0f, 0x1e, 0x6b, 0x14, 0x21, 0xc0, 0x4a, 0x07, 0x04, 0x31, 0x26, 0x5c, 0x19, 0xc5, 0xbb, 0xee,
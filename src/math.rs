@@ -0,0 +1,233 @@
+// checked integer helpers shared by the curve math. every helper returns None
+// instead of wrapping or panicking, callers map that to their own overflow error
+
+// floor(sqrt(value))
+pub fn integer_sqrt(value: u128) -> u128 {
+    value.isqrt()
+}
+
+// floor(a * b / denominator), None on overflow or a zero denominator
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    a.checked_mul(b)?.checked_div(denominator)
+}
+
+// ceil(a * b / denominator), None on overflow or a zero denominator
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    Some(a.checked_mul(b)?.div_ceil(denominator))
+}
+
+// typed amounts, so an lp amount can't be passed where a token amount is expected
+// (or the other way round) without an explicit conversion. only checked arithmetic
+// is provided, mixing two kinds of amount doesn't compile
+
+// an amount of one of the pool tokens, x or y
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TokenAmount(u64);
+
+// an amount of the pool's lp token
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LpAmount(u64);
+
+// a fee or a share in basis points
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bps(u16);
+
+impl TokenAmount {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn new(amount: u64) -> Self {
+        Self(amount)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl LpAmount {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn new(amount: u64) -> Self {
+        Self(amount)
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Bps {
+    pub const ZERO: Self = Self(0);
+
+    pub const fn new(bps: u16) -> Self {
+        Self(bps)
+    }
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xorshift, enough to spread the property checks over the whole range without a
+    // dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        // mostly small values, with the occasional one near the top of the range
+        fn next_sized(&mut self) -> u64 {
+            let shift = self.next_u64() % 64;
+            self.next_u64() >> shift
+        }
+    }
+
+    const ROUNDS: usize = 100_000;
+
+    #[test]
+    fn integer_sqrt_exhaustive_small() {
+        for value in 0..=100_000u128 {
+            let root = integer_sqrt(value);
+            assert!(root * root <= value);
+            assert!((root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn integer_sqrt_edges() {
+        assert_eq!(integer_sqrt(0), 0);
+        assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(
+            integer_sqrt((u64::MAX as u128) * (u64::MAX as u128)),
+            u64::MAX as u128
+        );
+        assert_eq!(
+            integer_sqrt((u64::MAX as u128) * (u64::MAX as u128) - 1),
+            u64::MAX as u128 - 1
+        );
+    }
+
+    #[test]
+    fn integer_sqrt_brackets_random_values() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..ROUNDS {
+            let value = (rng.next_sized() as u128) << 64 | rng.next_u64() as u128;
+            let root = integer_sqrt(value);
+            assert!(root * root <= value);
+            assert!((root + 1)
+                .checked_mul(root + 1)
+                .is_none_or(|next| next > value));
+
+            // perfect squares and their predecessors
+            let k = rng.next_u64() as u128;
+            assert_eq!(integer_sqrt(k * k), k);
+            if k > 0 {
+                assert_eq!(integer_sqrt(k * k - 1), k - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn mul_div_exhaustive_small() {
+        for a in 0..32u128 {
+            for b in 0..32u128 {
+                assert_eq!(mul_div_floor(a, b, 0), None);
+                assert_eq!(mul_div_ceil(a, b, 0), None);
+
+                for denominator in 1..32u128 {
+                    let product = a * b;
+                    assert_eq!(
+                        mul_div_floor(a, b, denominator),
+                        Some(product / denominator)
+                    );
+                    assert_eq!(
+                        mul_div_ceil(a, b, denominator),
+                        Some(product.div_ceil(denominator))
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_div_overflow_edges() {
+        let max = u128::MAX;
+
+        // the product itself has to fit, even when the quotient would
+        assert_eq!(mul_div_floor(max, 2, 2), None);
+        assert_eq!(mul_div_ceil(max, 2, 2), None);
+        assert_eq!(mul_div_floor(1 << 64, 1 << 64, max), None);
+        assert_eq!(mul_div_ceil(1 << 64, 1 << 64, max), None);
+
+        assert_eq!(mul_div_floor(max, 1, 1), Some(max));
+        assert_eq!(mul_div_ceil(max, 1, 1), Some(max));
+        assert_eq!(mul_div_floor(max, 1, max), Some(1));
+        assert_eq!(mul_div_ceil(max - 1, 1, max), Some(1));
+        assert_eq!(mul_div_floor(max - 1, 1, max), Some(0));
+        assert_eq!(mul_div_ceil(0, max, 1), Some(0));
+    }
+
+    #[test]
+    fn mul_div_rounding_random_values() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..ROUNDS {
+            // u64 operands keep the product in u128, so the result can be bracketed
+            let (a, b) = (rng.next_sized() as u128, rng.next_sized() as u128);
+            let denominator = rng.next_sized().max(1) as u128;
+            let product = a * b;
+
+            let Some(floor) = mul_div_floor(a, b, denominator) else {
+                unreachable!()
+            };
+            let Some(ceil) = mul_div_ceil(a, b, denominator) else {
+                unreachable!()
+            };
+
+            assert!(floor * denominator <= product);
+            assert!(product - floor * denominator < denominator);
+            assert_eq!(ceil - floor, !product.is_multiple_of(denominator) as u128);
+        }
+    }
+}
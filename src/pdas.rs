@@ -0,0 +1,199 @@
+use pinocchio::pubkey::{find_program_address, Pubkey};
+
+use crate::instructions::{find_associated_token_address, TOKEN_METADATA_PROGRAM_ID};
+
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const LP_MINT_SEED: &[u8] = b"lp_mint";
+pub const DCA_ORDER_SEED: &[u8] = b"dca_order";
+pub const OBSERVATIONS_SEED: &[u8] = b"observations";
+pub const DEPOSIT_PERMIT_SEED: &[u8] = b"deposit_permit";
+pub const METADATA_SEED: &[u8] = b"metadata";
+pub const LP_LOCK_SEED: &[u8] = b"lp_lock";
+pub const LAUNCH_BUYS_SEED: &[u8] = b"launch_buys";
+
+// every address a pool owns, pools are keyed by (mint_x, mint_y, seed)
+pub struct PoolAddresses {
+    pub config: Pubkey,
+    pub config_bump: u8,
+    pub lp_mint: Pubkey,
+    pub lp_bump: u8,
+    pub vault_x: Pubkey,
+    pub vault_y: Pubkey,
+}
+
+// config pda of the pool for the given mints and seed
+pub fn config_address(mint_x: &Pubkey, mint_y: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    config_address_of(&crate::ID, mint_x, mint_y, seed)
+}
+
+// lp mint pda of the pool owning `config`
+pub fn lp_mint_address(config: &Pubkey) -> (Pubkey, u8) {
+    lp_mint_address_of(&crate::ID, config)
+}
+
+// config_address for the program deployed under `program_id`, see client::Cluster
+pub fn config_address_of(
+    program_id: &Pubkey,
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    seed: u64,
+) -> (Pubkey, u8) {
+    find_program_address(
+        &[CONFIG_SEED, mint_x, mint_y, &seed.to_le_bytes()],
+        program_id,
+    )
+}
+
+// lp_mint_address for the program deployed under `program_id`
+pub fn lp_mint_address_of(program_id: &Pubkey, config: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[LP_MINT_SEED, config], program_id)
+}
+
+// derives the whole pool so callers never hand-roll the seeds. the vault ata seeds
+// include the token program, so each vault is derived under the program owning its
+// mint (the legacy token program or token-2022)
+pub fn pool_accounts(
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    seed: u64,
+    token_program_x: &Pubkey,
+    token_program_y: &Pubkey,
+) -> PoolAddresses {
+    pool_accounts_of(
+        &crate::ID,
+        mint_x,
+        mint_y,
+        seed,
+        token_program_x,
+        token_program_y,
+    )
+}
+
+// pool_accounts for the program deployed under `program_id`
+pub fn pool_accounts_of(
+    program_id: &Pubkey,
+    mint_x: &Pubkey,
+    mint_y: &Pubkey,
+    seed: u64,
+    token_program_x: &Pubkey,
+    token_program_y: &Pubkey,
+) -> PoolAddresses {
+    let (config, config_bump) = config_address_of(program_id, mint_x, mint_y, seed);
+    let (lp_mint, lp_bump) = lp_mint_address_of(program_id, &config);
+
+    PoolAddresses {
+        config,
+        config_bump,
+        lp_mint,
+        lp_bump,
+        vault_x: find_associated_token_address(&config, mint_x, token_program_x),
+        vault_y: find_associated_token_address(&config, mint_y, token_program_y),
+    }
+}
+
+// dca order pda of `owner` on the pool `config`, the seed lets an owner run several
+pub fn dca_order_address(config: &Pubkey, owner: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    find_program_address(
+        &[DCA_ORDER_SEED, config, owner, &seed.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+// reserve snapshot ring buffer of the pool owning `config`
+pub fn observations_address(config: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[OBSERVATIONS_SEED, config], &crate::ID)
+}
+
+// deposit permit pda of `user` on the permissioned pool `config`
+pub fn deposit_permit_address(config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[DEPOSIT_PERMIT_SEED, config, user], &crate::ID)
+}
+
+// lp lock pda of `owner` on the pool `config`, the seed lets an owner hold several
+pub fn lp_lock_address(config: &Pubkey, owner: &Pubkey, seed: u64) -> (Pubkey, u8) {
+    find_program_address(
+        &[LP_LOCK_SEED, config, owner, &seed.to_le_bytes()],
+        &crate::ID,
+    )
+}
+
+// launch window buys of `user` on the pool `config`
+pub fn launch_buys_address(config: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[LAUNCH_BUYS_SEED, config, user], &crate::ID)
+}
+
+// metaplex metadata pda of `mint`, derived under the metadata program
+pub fn metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(
+        &[METADATA_SEED, &TOKEN_METADATA_PROGRAM_ID, mint],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::TOKEN_2022_PROGRAM_ID;
+
+    const MINT_X: Pubkey = [1; 32];
+    const MINT_Y: Pubkey = [2; 32];
+    const SEED: u64 = 7;
+
+    // off-chain pinocchio has no pda derivation, it only exists as a runtime syscall
+    #[test]
+    #[cfg_attr(
+        not(target_os = "solana"),
+        ignore = "pda derivation needs the runtime syscall"
+    )]
+    fn pool_accounts_match_the_single_derivations() {
+        for (token_program_x, token_program_y) in [
+            (pinocchio_token::ID, pinocchio_token::ID),
+            (pinocchio_token::ID, TOKEN_2022_PROGRAM_ID),
+            (TOKEN_2022_PROGRAM_ID, pinocchio_token::ID),
+            (TOKEN_2022_PROGRAM_ID, TOKEN_2022_PROGRAM_ID),
+        ] {
+            let pool = pool_accounts(&MINT_X, &MINT_Y, SEED, &token_program_x, &token_program_y);
+
+            assert_eq!(
+                (pool.config, pool.config_bump),
+                config_address(&MINT_X, &MINT_Y, SEED)
+            );
+            assert_eq!((pool.lp_mint, pool.lp_bump), lp_mint_address(&pool.config));
+            assert_eq!(
+                pool.vault_x,
+                find_associated_token_address(&pool.config, &MINT_X, &token_program_x)
+            );
+            assert_eq!(
+                pool.vault_y,
+                find_associated_token_address(&pool.config, &MINT_Y, &token_program_y)
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(
+        not(target_os = "solana"),
+        ignore = "pda derivation needs the runtime syscall"
+    )]
+    fn vaults_depend_on_the_token_program() {
+        let legacy = pool_accounts(
+            &MINT_X,
+            &MINT_Y,
+            SEED,
+            &pinocchio_token::ID,
+            &pinocchio_token::ID,
+        );
+        let token_2022 = pool_accounts(
+            &MINT_X,
+            &MINT_Y,
+            SEED,
+            &TOKEN_2022_PROGRAM_ID,
+            &TOKEN_2022_PROGRAM_ID,
+        );
+
+        assert_eq!(legacy.config, token_2022.config);
+        assert_eq!(legacy.lp_mint, token_2022.lp_mint);
+        assert_ne!(legacy.vault_x, token_2022.vault_x);
+        assert_ne!(legacy.vault_y, token_2022.vault_y);
+    }
+}
@@ -0,0 +1,1353 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{AccountCheck, SignerAccount},
+    math::{Bps, LpAmount},
+    state::{
+        ramp_amp, validate_amp_ramp, CurveType, DepositPermit, PoolStats, PriceBand,
+        BPS_DENOMINATOR,
+    },
+};
+
+// slots an admin change to pool pricing waits before it takes effect (~1 day),
+// long enough for lps to exit ahead of a change they disagree with
+pub const ADMIN_TIMELOCK_SLOTS: u64 = 216_000;
+
+// highest swap fee a pool may charge, in basis points
+pub const MAX_FEE_BPS: u16 = 1000;
+
+// highest creator royalty a pool may charge on top of the swap fee, in basis points
+pub const MAX_ROYALTY_BPS: u16 = 1000;
+
+// most a single UpdateFee may move the swap fee of a new pool, in basis points.
+// one change is allowed per ADMIN_TIMELOCK_SLOTS, so lps watching the timelock
+// never see a jump. the authority may set another bound with SetMaxFeeChange
+pub const DEFAULT_MAX_FEE_CHANGE_BPS: u16 = 50;
+
+// longest launch window a pool may open after its trading start (1 day), the
+// restrictions it places on trading always lapse on their own
+pub const MAX_LAUNCH_WINDOW_SECS: i64 = 86_400;
+
+// headroom kept free at the end of Config. new fields are carved out of the
+// reserve, the assert below fails the build before one takes it below this
+const MIN_RESERVED_LEN: usize = 64;
+const RESERVED_LEN: usize = 64;
+
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct Config {
+    authority: Pubkey,
+    mint_x: Pubkey,
+    mint_y: Pubkey,
+    mint_x_vault: Pubkey,
+    mint_y_vault: Pubkey,
+    lp_mint: Pubkey,
+    min_lp_mint: u64,
+    min_lp_burn: u64,
+    trading_start_ts: i64,
+    fee: u16,
+    config_bump: u8,
+    // 1 when deposits (and swaps) must come from a top level instruction
+    no_cpi: u8,
+    // AmmState discriminator
+    state: u8,
+    // bump of the lp mint pda, stored so it never has to be searched for again
+    lp_bump: u8,
+    // explicit tail padding so the layout has no implicit bytes (required by Pod)
+    _padding: [u8; 2],
+    // 0 means the lp supply is uncapped
+    max_lp_supply: u64,
+    // bumped by every admin op, admin instructions must quote the current value
+    admin_nonce: u64,
+    // user chosen pool seed, lets many pools exist for the same mint pair
+    seed: u64,
+    // stable swap amplification, 0 for constant product pools. while a RampAmp is
+    // scheduled this is the amp the ramp started from
+    amp: u64,
+    // CurveType discriminator, see CurveType::new
+    curve_type: u8,
+    _curve_padding: [u8; 7],
+    // hard bounds on the spot price of x in y (scaled by PRICE_SCALE), 0 leaves a side open
+    price_band_min: u64,
+    price_band_max: u64,
+    // band scheduled by the authority, replaces the above from the activation slot
+    pending_price_band_min: u64,
+    pending_price_band_max: u64,
+    price_band_activation_slot: u64,
+    // protocol share of swap fees, accrued per token and held in the vaults
+    // until collected, these balances are excluded from the lp reserves
+    protocol_fee_x: u64,
+    protocol_fee_y: u64,
+    // share of every swap fee owed to the protocol, in basis points of the fee
+    protocol_fee_bps: u16,
+    // fee scheduled by the authority, replaces `fee` from the activation slot
+    pending_fee: u16,
+    // share of every swap fee paid to the referrer a swap names, in basis points of the fee
+    referral_fee_bps: u16,
+    _fee_padding: [u8; 2],
+    fee_activation_slot: u64,
+    // lp locked by the first deposit, counts towards the supply but has no holder
+    locked_lp: u64,
+    // twap accumulators, the sum of the spot price of x in y (and of y in x),
+    // scaled by PRICE_SCALE, times the seconds it held. little endian u128s that
+    // wrap on overflow, readers only ever use the difference of two observations
+    price_cumulative_x: [u8; 16],
+    price_cumulative_y: [u8; 16],
+    last_update_timestamp: i64,
+    // outstanding flash loan, only ever set between a FlashBorrow and the FlashRepay
+    // later in the same transaction. the repay has to bring the lent vault back to
+    // flash_loan_vault_target, which includes the loan fee
+    flash_loan_vault_target: u64,
+    flash_loan_active: u8,
+    // 1 when the loan was taken from vault x
+    flash_loan_x: u8,
+    _flash_loan_padding: [u8; 6],
+    // signs deposit permits. while set the pool is permissioned and only users
+    // holding a DepositPermit may deposit, default for an open pool
+    whitelist_authority: Pubkey,
+    // 1 once the pool has an observation buffer, every trade then has to pass it
+    // so the oracle can't be starved by leaving it out
+    observations_required: u8,
+    _observations_padding: [u8; 7],
+    // sequence number of the next event the pool logs, see events
+    event_seq: u64,
+    // lp share of every swap fee per lp unit, Q64 fixed point little endian u128s of
+    // the input token that wrap on overflow like the twap accumulators. the fees an
+    // lp position earned between two reads is its balance times the difference
+    fee_growth_x: [u8; 16],
+    fee_growth_y: [u8; 16],
+    // swap volume and fees of the open epoch bucket and the last few closed ones
+    stats: PoolStats,
+    // slot of the last UpdateFee, 0 while the fee was never changed
+    last_fee_change_slot: u64,
+    // lp held in LpLock escrows, unlike locked_lp it has holders and is released by UnlockLp
+    locked_lp_amount: u64,
+    // amp ramp scheduled by the authority, amp moves linearly from `amp` at the start
+    // to `future_amp` at the end. an end of 0 means no ramp was ever scheduled
+    future_amp: u64,
+    amp_ramp_start_ts: i64,
+    amp_ramp_end_ts: i64,
+    // seconds after trading_start_ts during which swaps may only buy the launched
+    // token, 0 when the pool has no launch window. see Config::launch_guard
+    launch_window_secs: i64,
+    // most of the launched token one wallet may buy during the window, 0 for no cap
+    launch_wallet_cap: u64,
+    // 1 when buying the launched token is the x to y direction
+    launch_x_to_y: u8,
+    _launch_padding: [u8; 7],
+    // most of the launched token all wallets together may buy in one slot during the
+    // window, 0 for no cap. the counter below belongs to launch_slot and restarts
+    // on the first buy of a later slot, see Config::record_launch_slot_buy
+    launch_slot_cap: u64,
+    launch_slot: u64,
+    launch_slot_bought: u64,
+    // creator royalty charged on every swap input on top of the swap fee, in basis
+    // points of the input, and the wallet whose token accounts receive it
+    royalty_destination: Pubkey,
+    royalty_bps: u16,
+    // most a single UpdateFee may move the swap fee, in basis points
+    max_fee_change_bps: u16,
+    _fee_change_padding: [u8; 4],
+    // headroom for future fields, see MIN_RESERVED_LEN
+    _reserved: [u8; RESERVED_LEN],
+}
+
+// launch window restrictions, see Config::launch_guard
+#[derive(Clone, Copy)]
+pub struct LaunchGuard {
+    // the only direction swaps may take, buying the launched token
+    pub x_to_y: bool,
+    // most of the launched token one wallet may buy in the window, 0 for no cap
+    pub wallet_cap: u64,
+    // most of the launched token the pool sells in one slot of the window, 0 for no cap
+    pub slot_cap: u64,
+}
+
+#[repr(u8)]
+pub enum AmmState {
+    Uninitialized = 0u8,
+    Initialized = 1u8,
+    Disabled = 2u8,
+    WithdrawOnly = 3u8,
+}
+
+impl TryFrom<u8> for AmmState {
+    type Error = ProgramError;
+
+    fn try_from(state: u8) -> Result<Self, Self::Error> {
+        match state {
+            0 => Ok(Self::Uninitialized),
+            1 => Ok(Self::Initialized),
+            2 => Ok(Self::Disabled),
+            3 => Ok(Self::WithdrawOnly),
+            _ => Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+impl Config {
+    pub const LEN: usize = size_of::<Self>();
+
+    // inline always attribute rather than adding the function call to the cll stack
+    // it adds the function code to the call stack which eliminate the overhead function call
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            Self::from_bytes_unchecked(data)
+        }))
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub unsafe fn load_unchecked(account_info: &AccountInfo) -> Result<&Self, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Self::from_bytes_unchecked(
+            account_info.borrow_data_unchecked(),
+        ))
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub unsafe fn from_bytes_unchecked(bytes: &[u8]) -> &Self {
+        &*(bytes.as_ptr() as *const Config)
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(
+            account_info.try_borrow_mut_data()?,
+            |data| unsafe { Self::from_bytes_unchecked_mut(data) },
+        ))
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub unsafe fn from_bytes_unchecked_mut(bytes: &mut [u8]) -> &mut Self {
+        &mut *(bytes.as_mut_ptr() as *mut Config)
+    }
+
+    // bytemuck backed loads, the size check above the cast doubles as the pod length check
+    // and account data is always 8 byte aligned by the runtime
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| {
+            bytemuck::from_bytes::<Self>(data)
+        }))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut::<Self>(data)
+        }))
+    }
+
+    pub fn set_inner(
+        &mut self,
+        authority: Pubkey,
+        mint_x: Pubkey,
+        mint_y: Pubkey,
+        mint_x_vault: Pubkey,
+        mint_y_vault: Pubkey,
+        lp_mint: Pubkey,
+        min_lp_mint: u64,
+        min_lp_burn: u64,
+        trading_start_ts: i64,
+        max_lp_supply: u64,
+        seed: u64,
+        fee: u16,
+        config_bump: u8,
+        lp_bump: u8,
+        curve_type: CurveType,
+        price_band: PriceBand,
+    ) -> Result<(), ProgramError> {
+        self.authority = authority;
+        self.mint_x = mint_x;
+        self.mint_y = mint_y;
+        self.mint_x_vault = mint_x_vault;
+        self.mint_y_vault = mint_y_vault;
+        self.lp_mint = lp_mint;
+        self.min_lp_mint = min_lp_mint;
+        self.min_lp_burn = min_lp_burn;
+        self.trading_start_ts = trading_start_ts;
+        self.max_lp_supply = max_lp_supply;
+        self.seed = seed;
+        self.fee = fee;
+        self.config_bump = config_bump;
+        self.lp_bump = lp_bump;
+        self.state = AmmState::Initialized as u8;
+        self.curve_type = curve_type.discriminator();
+        self.amp = curve_type.amp();
+        self.price_band_min = price_band.min_price;
+        self.price_band_max = price_band.max_price;
+        self.max_fee_change_bps = DEFAULT_MAX_FEE_CHANGE_BPS;
+        Ok(())
+    }
+
+    pub fn mint_x(&self) -> &Pubkey {
+        &self.mint_x
+    }
+
+    pub fn mint_y(&self) -> &Pubkey {
+        &self.mint_y
+    }
+
+    pub fn mint_x_vault(&self) -> &Pubkey {
+        &self.mint_x_vault
+    }
+
+    pub fn mint_y_vault(&self) -> &Pubkey {
+        &self.mint_y_vault
+    }
+
+    pub fn lp_mint(&self) -> &Pubkey {
+        &self.lp_mint
+    }
+
+    pub fn config_bump(&self) -> u8 {
+        self.config_bump
+    }
+
+    pub fn lp_bump(&self) -> u8 {
+        self.lp_bump
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // swap fee in basis points in force at `slot`, a scheduled fee applies as soon
+    // as its timelock has elapsed without needing a write to the config
+    pub fn fee(&self, slot: u64) -> Bps {
+        if self.fee_activation_slot != 0 && slot >= self.fee_activation_slot {
+            Bps::new(self.pending_fee)
+        } else {
+            Bps::new(self.fee)
+        }
+    }
+
+    // queues `fee` behind the admin timelock, a fee that already activated is
+    // folded into the live one first so it isn't lost. the change is rate limited to
+    // max_fee_change_bps from the live fee, once per timelock period
+    pub fn schedule_fee(&mut self, fee: u16, slot: u64) -> Result<(), ProgramError> {
+        if self.last_fee_change_slot != 0
+            && slot
+                < self
+                    .last_fee_change_slot
+                    .saturating_add(ADMIN_TIMELOCK_SLOTS)
+        {
+            return Err(PinocchioError::FeeChangeRateLimited.into());
+        }
+
+        let live_fee = self.fee(slot).get();
+        if fee.abs_diff(live_fee) > self.max_fee_change_bps {
+            return Err(PinocchioError::FeeChangeRateLimited.into());
+        }
+
+        self.fee = live_fee;
+        self.pending_fee = fee;
+        self.fee_activation_slot = slot
+            .checked_add(ADMIN_TIMELOCK_SLOTS)
+            .ok_or(PinocchioError::MathOverflow)?;
+        self.last_fee_change_slot = slot;
+        Ok(())
+    }
+
+    // curve in force at `now`, a ramping amp is read off the ramp without needing a
+    // write to the config
+    pub fn curve_type(&self, now: i64) -> Result<CurveType, ProgramError> {
+        let amp = if self.amp_ramp_end_ts != 0 {
+            ramp_amp(
+                self.amp,
+                self.future_amp,
+                self.amp_ramp_start_ts,
+                self.amp_ramp_end_ts,
+                now,
+            )
+        } else {
+            self.amp
+        };
+
+        CurveType::new(self.curve_type, amp).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    // ramps the amp of a stable pool from its value at `now` to `future_amp` at
+    // `end_ts`, a ramp still in progress is folded into the start of the new one
+    pub fn schedule_amp_ramp(
+        &mut self,
+        future_amp: u64,
+        end_ts: i64,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        let CurveType::Stable { amp } = self.curve_type(now)? else {
+            return Err(PinocchioError::InvalidCurve.into());
+        };
+
+        validate_amp_ramp(amp, future_amp, now, end_ts)?;
+
+        self.amp = amp;
+        self.future_amp = future_amp;
+        self.amp_ramp_start_ts = now;
+        self.amp_ramp_end_ts = end_ts;
+        Ok(())
+    }
+
+    // restrictions in force at `now`, None once the launch window has passed or when
+    // the pool never had one
+    pub fn launch_guard(&self, now: i64) -> Option<LaunchGuard> {
+        let window_end = self
+            .trading_start_ts
+            .saturating_add(self.launch_window_secs);
+        let in_window =
+            self.launch_window_secs != 0 && now >= self.trading_start_ts && now < window_end;
+
+        in_window.then_some(LaunchGuard {
+            x_to_y: self.launch_x_to_y == 1,
+            wallet_cap: self.launch_wallet_cap,
+            slot_cap: self.launch_slot_cap,
+        })
+    }
+
+    // opens a launch window of `window_secs` from the trading start, only before
+    // trading starts so traders never see buy-only rules appear mid trading
+    pub fn set_launch_guard(
+        &mut self,
+        window_secs: i64,
+        guard: LaunchGuard,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        if now >= self.trading_start_ts {
+            return Err(PinocchioError::LaunchAlreadyStarted.into());
+        }
+
+        self.launch_window_secs = window_secs;
+        self.launch_x_to_y = guard.x_to_y as u8;
+        self.launch_wallet_cap = guard.wallet_cap;
+        self.launch_slot_cap = guard.slot_cap;
+        Ok(())
+    }
+
+    // adds a launch window buy of `amount` to what the pool sold in `slot`. the
+    // counter is only reset lazily, by the first buy of a slot after launch_slot
+    pub fn record_launch_slot_buy(&mut self, amount: u64, slot: u64) -> Result<(), ProgramError> {
+        if self.launch_slot_cap == 0 {
+            return Ok(());
+        }
+
+        let bought = if slot == self.launch_slot {
+            self.launch_slot_bought
+        } else {
+            0
+        };
+
+        self.launch_slot_bought = bought
+            .checked_add(amount)
+            .filter(|bought| *bought <= self.launch_slot_cap)
+            .ok_or(PinocchioError::LaunchSlotCapExceeded)?;
+        self.launch_slot = slot;
+        Ok(())
+    }
+
+    pub fn royalty_bps(&self) -> Bps {
+        Bps::new(self.royalty_bps)
+    }
+
+    // wallet owed the royalty, None while the pool charges none
+    pub fn royalty_destination(&self) -> Option<Pubkey> {
+        if self.royalty_bps != 0 {
+            Some(self.royalty_destination)
+        } else {
+            None
+        }
+    }
+
+    pub fn max_fee_change_bps(&self) -> u16 {
+        self.max_fee_change_bps
+    }
+
+    pub fn set_max_fee_change_bps(&mut self, max_fee_change_bps: u16) {
+        self.max_fee_change_bps = max_fee_change_bps;
+    }
+
+    pub fn set_royalty(&mut self, royalty_bps: u16, royalty_destination: Pubkey) {
+        self.royalty_bps = royalty_bps;
+        self.royalty_destination = royalty_destination;
+    }
+
+    pub fn protocol_fee_bps(&self) -> Bps {
+        Bps::new(self.protocol_fee_bps)
+    }
+
+    pub fn set_protocol_fee_bps(&mut self, protocol_fee_bps: u16) -> Result<(), ProgramError> {
+        Self::check_fee_shares(protocol_fee_bps, self.referral_fee_bps)?;
+        self.protocol_fee_bps = protocol_fee_bps;
+        Ok(())
+    }
+
+    pub fn referral_fee_bps(&self) -> Bps {
+        Bps::new(self.referral_fee_bps)
+    }
+
+    pub fn set_referral_fee_bps(&mut self, referral_fee_bps: u16) -> Result<(), ProgramError> {
+        Self::check_fee_shares(self.protocol_fee_bps, referral_fee_bps)?;
+        self.referral_fee_bps = referral_fee_bps;
+        Ok(())
+    }
+
+    // the protocol and referral shares both come out of the same swap fee
+    fn check_fee_shares(protocol_fee_bps: u16, referral_fee_bps: u16) -> Result<(), ProgramError> {
+        if protocol_fee_bps as u64 + referral_fee_bps as u64 > BPS_DENOMINATOR {
+            return Err(PinocchioError::InvalidFee.into());
+        }
+        Ok(())
+    }
+
+    // accrued (x, y) protocol fees still sitting in the vaults
+    pub fn protocol_fees(&self) -> (u64, u64) {
+        (self.protocol_fee_x, self.protocol_fee_y)
+    }
+
+    // records `amount` of the swap input token as owed to the protocol
+    pub fn accrue_protocol_fee(&mut self, x_to_y: bool, amount: u64) -> Result<(), ProgramError> {
+        let accrued = if x_to_y {
+            &mut self.protocol_fee_x
+        } else {
+            &mut self.protocol_fee_y
+        };
+
+        *accrued = accrued
+            .checked_add(amount)
+            .ok_or(PinocchioError::MathOverflow)?;
+        Ok(())
+    }
+
+    // (x, y) fee growth per lp unit, Q64
+    pub fn fee_growth(&self) -> (u128, u128) {
+        (
+            u128::from_le_bytes(self.fee_growth_x),
+            u128::from_le_bytes(self.fee_growth_y),
+        )
+    }
+
+    // credits `lp_fee` of the swap input token to every one of the `lp_supply` lp units
+    pub fn accrue_fee_growth(&mut self, x_to_y: bool, lp_fee: u64, lp_supply: LpAmount) {
+        if lp_fee == 0 || lp_supply.is_zero() {
+            return;
+        }
+
+        let growth = ((lp_fee as u128) << 64) / lp_supply.get() as u128;
+        let fee_growth = if x_to_y {
+            &mut self.fee_growth_x
+        } else {
+            &mut self.fee_growth_y
+        };
+
+        *fee_growth = u128::from_le_bytes(*fee_growth)
+            .wrapping_add(growth)
+            .to_le_bytes();
+    }
+
+    pub fn stats(&self) -> &PoolStats {
+        &self.stats
+    }
+
+    pub fn stats_mut(&mut self) -> &mut PoolStats {
+        &mut self.stats
+    }
+
+    pub fn price_cumulative(&self) -> (u128, u128, i64) {
+        (
+            u128::from_le_bytes(self.price_cumulative_x),
+            u128::from_le_bytes(self.price_cumulative_y),
+            self.last_update_timestamp,
+        )
+    }
+
+    // folds the price the pool held since the last update into the accumulators,
+    // called with the reserves from before the instruction moves them so a trade
+    // never contributes its own price
+    pub fn update_price_cumulative(
+        &mut self,
+        reserve_x: u64,
+        reserve_y: u64,
+        now: i64,
+    ) -> Result<(), ProgramError> {
+        let elapsed = now.saturating_sub(self.last_update_timestamp);
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        // an empty pool has no price, its first interval is skipped
+        if self.last_update_timestamp != 0 && reserve_x != 0 && reserve_y != 0 {
+            let curve_type = self.curve_type(now)?;
+            let price_x = curve_type.spot_price(reserve_x, reserve_y)?;
+            let price_y = curve_type.spot_price(reserve_y, reserve_x)?;
+
+            self.price_cumulative_x = u128::from_le_bytes(self.price_cumulative_x)
+                .wrapping_add(price_x.wrapping_mul(elapsed as u128))
+                .to_le_bytes();
+            self.price_cumulative_y = u128::from_le_bytes(self.price_cumulative_y)
+                .wrapping_add(price_y.wrapping_mul(elapsed as u128))
+                .to_le_bytes();
+        }
+
+        self.last_update_timestamp = now;
+        Ok(())
+    }
+
+    // clears the accrued fees, returning what was owed
+    pub fn take_protocol_fees(&mut self) -> (u64, u64) {
+        let fees = self.protocol_fees();
+        self.protocol_fee_x = 0;
+        self.protocol_fee_y = 0;
+        fees
+    }
+
+    // band in force at `slot`, a scheduled band applies as soon as its timelock has
+    // elapsed without needing a write to the config
+    pub fn price_band(&self, slot: u64) -> PriceBand {
+        if self.price_band_activation_slot != 0 && slot >= self.price_band_activation_slot {
+            PriceBand {
+                min_price: self.pending_price_band_min,
+                max_price: self.pending_price_band_max,
+            }
+        } else {
+            PriceBand {
+                min_price: self.price_band_min,
+                max_price: self.price_band_max,
+            }
+        }
+    }
+
+    // queues `price_band` behind the admin timelock, a band that already activated
+    // is folded into the live one first so it isn't lost
+    pub fn schedule_price_band(
+        &mut self,
+        price_band: PriceBand,
+        slot: u64,
+    ) -> Result<(), ProgramError> {
+        let current = self.price_band(slot);
+        self.price_band_min = current.min_price;
+        self.price_band_max = current.max_price;
+
+        self.pending_price_band_min = price_band.min_price;
+        self.pending_price_band_max = price_band.max_price;
+        self.price_band_activation_slot = slot
+            .checked_add(ADMIN_TIMELOCK_SLOTS)
+            .ok_or(PinocchioError::MathOverflow)?;
+        Ok(())
+    }
+
+    // re-derives the pool pda from the stored seeds and bump, the config has to be
+    // the canonical address since it signs for the vaults and the lp mint
+    pub fn verify_address(&self, config: &AccountInfo) -> Result<(), ProgramError> {
+        let seed = self.seed.to_le_bytes();
+        let address = create_program_address(
+            &[
+                b"config".as_ref(),
+                &self.mint_x,
+                &self.mint_y,
+                &seed,
+                &[self.config_bump],
+            ],
+            &crate::ID,
+        )?;
+
+        if address.ne(config.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+
+    // the passed mints and vaults must be this pool's, the vault ata checks alone
+    // would accept the mints and vaults of any pool signed for by this config
+    pub fn verify_pool_accounts(
+        &self,
+        mint_x: &AccountInfo,
+        mint_y: &AccountInfo,
+        vault_x: &AccountInfo,
+        vault_y: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        // the most likely client bug, reported on its own so it's easy to spot
+        if self.mint_x.eq(mint_y.key()) && self.mint_y.eq(mint_x.key()) {
+            return Err(PinocchioError::SwappedMints.into());
+        }
+
+        if self.mint_x.ne(mint_x.key())
+            || self.mint_y.ne(mint_y.key())
+            || self.mint_x_vault.ne(vault_x.key())
+            || self.mint_y_vault.ne(vault_y.key())
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    pub fn locked_lp(&self) -> LpAmount {
+        LpAmount::new(self.locked_lp)
+    }
+
+    pub fn lock_lp(&mut self, amount: LpAmount) -> Result<(), ProgramError> {
+        self.locked_lp = self
+            .locked_lp()
+            .checked_add(amount)
+            .ok_or(PinocchioError::MathOverflow)?
+            .get();
+        Ok(())
+    }
+
+    pub fn locked_lp_amount(&self) -> LpAmount {
+        LpAmount::new(self.locked_lp_amount)
+    }
+
+    pub fn add_locked_lp_amount(&mut self, amount: LpAmount) -> Result<(), ProgramError> {
+        self.locked_lp_amount = self
+            .locked_lp_amount()
+            .checked_add(amount)
+            .ok_or(PinocchioError::MathOverflow)?
+            .get();
+        Ok(())
+    }
+
+    pub fn remove_locked_lp_amount(&mut self, amount: LpAmount) -> Result<(), ProgramError> {
+        self.locked_lp_amount = self
+            .locked_lp_amount()
+            .checked_sub(amount)
+            .ok_or(PinocchioError::MathOverflow)?
+            .get();
+        Ok(())
+    }
+
+    // smallest LP amount a single deposit may mint
+    pub fn min_lp_mint(&self) -> LpAmount {
+        LpAmount::new(self.min_lp_mint)
+    }
+
+    // smallest LP amount a single withdraw may burn
+    pub fn min_lp_burn(&self) -> LpAmount {
+        LpAmount::new(self.min_lp_burn)
+    }
+
+    // unix timestamp from which swaps are accepted
+    pub fn trading_start_ts(&self) -> i64 {
+        self.trading_start_ts
+    }
+
+    pub fn set_trading_start_ts(&mut self, trading_start_ts: i64) {
+        self.trading_start_ts = trading_start_ts;
+    }
+
+    // cap on the total lp supply, None when the pool is uncapped
+    pub fn max_lp_supply(&self) -> Option<LpAmount> {
+        if self.max_lp_supply != 0 {
+            Some(LpAmount::new(self.max_lp_supply))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_max_lp_supply(&mut self, max_lp_supply: u64) {
+        self.max_lp_supply = max_lp_supply;
+    }
+
+    pub fn admin_nonce(&self) -> u64 {
+        self.admin_nonce
+    }
+
+    pub fn increment_admin_nonce(&mut self) -> Result<(), ProgramError> {
+        self.admin_nonce = self
+            .admin_nonce
+            .checked_add(1)
+            .ok_or(PinocchioError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn state(&self) -> Result<AmmState, ProgramError> {
+        AmmState::try_from(self.state)
+    }
+
+    pub fn set_state(&mut self, state: AmmState) {
+        self.state = state as u8;
+    }
+
+    // deposits and swaps need a fully active pool
+    pub fn require_active(&self) -> Result<(), ProgramError> {
+        self.require_no_flash_loan()?;
+
+        match self.state()? {
+            AmmState::Initialized => Ok(()),
+            AmmState::Uninitialized => Err(ProgramError::UninitializedAccount),
+            AmmState::Disabled => Err(PinocchioError::PoolDisabled.into()),
+            AmmState::WithdrawOnly => Err(PinocchioError::PoolWithdrawOnly.into()),
+        }
+    }
+
+    // withdrawals stay open in WithdrawOnly so lps can always exit a winding down pool
+    pub fn require_withdrawable(&self) -> Result<(), ProgramError> {
+        self.require_no_flash_loan()?;
+
+        match self.state()? {
+            AmmState::Initialized | AmmState::WithdrawOnly => Ok(()),
+            AmmState::Uninitialized => Err(ProgramError::UninitializedAccount),
+            AmmState::Disabled => Err(PinocchioError::PoolDisabled.into()),
+        }
+    }
+
+    // the vaults are short by the lent amount until the repay, nothing that reads or
+    // moves them may run in between
+    pub fn require_no_flash_loan(&self) -> Result<(), ProgramError> {
+        if self.flash_loan_active != 0 {
+            return Err(PinocchioError::FlashLoanActive.into());
+        }
+        Ok(())
+    }
+
+    // (lent from vault x, vault target) of the outstanding flash loan
+    pub fn flash_loan(&self) -> Option<(bool, u64)> {
+        if self.flash_loan_active != 0 {
+            Some((self.flash_loan_x == 1, self.flash_loan_vault_target))
+        } else {
+            None
+        }
+    }
+
+    pub fn start_flash_loan(&mut self, x: bool, vault_target: u64) {
+        self.flash_loan_active = 1;
+        self.flash_loan_x = x as u8;
+        self.flash_loan_vault_target = vault_target;
+    }
+
+    pub fn end_flash_loan(&mut self) {
+        self.flash_loan_active = 0;
+        self.flash_loan_x = 0;
+        self.flash_loan_vault_target = 0;
+    }
+
+    pub fn no_cpi(&self) -> bool {
+        self.no_cpi == 1
+    }
+
+    pub fn set_no_cpi(&mut self, no_cpi: bool) {
+        self.no_cpi = no_cpi as u8;
+    }
+
+    pub fn has_authority(&self) -> Option<Pubkey> {
+        if self.authority != Pubkey::default() {
+            Some(self.authority)
+        } else {
+            None
+        }
+    }
+
+    // gate for admin instructions, a renounced (default) authority can never sign
+    pub fn require_authority(&self, signer: &AccountInfo) -> Result<(), ProgramError> {
+        let authority = self
+            .has_authority()
+            .ok_or(PinocchioError::AuthorityRenounced)?;
+
+        SignerAccount::check(signer)?;
+
+        if authority.ne(signer.key()) {
+            return Err(PinocchioError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+
+    pub fn whitelist_authority(&self) -> Option<Pubkey> {
+        if self.whitelist_authority != Pubkey::default() {
+            Some(self.whitelist_authority)
+        } else {
+            None
+        }
+    }
+
+    // the default key opens the pool again, permits already issued are kept
+    pub fn set_whitelist_authority(&mut self, whitelist_authority: Pubkey) {
+        self.whitelist_authority = whitelist_authority;
+    }
+
+    // gate for AddToWhitelist, an open pool has nobody to issue permits
+    pub fn require_whitelist_authority(&self, signer: &AccountInfo) -> Result<(), ProgramError> {
+        let whitelist_authority = self
+            .whitelist_authority()
+            .ok_or(PinocchioError::PoolNotPermissioned)?;
+
+        SignerAccount::check(signer)?;
+
+        if whitelist_authority.ne(signer.key()) {
+            return Err(PinocchioError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+
+    pub fn observations_required(&self) -> bool {
+        self.observations_required == 1
+    }
+
+    pub fn require_observations(&mut self) {
+        self.observations_required = 1;
+    }
+
+    // sequence number for the event about to be logged, every event takes the next one
+    pub fn next_event_seq(&mut self) -> Result<u64, ProgramError> {
+        let event_seq = self.event_seq;
+        self.event_seq = event_seq
+            .checked_add(1)
+            .ok_or(PinocchioError::MathOverflow)?;
+        Ok(event_seq)
+    }
+
+    // a permissioned pool only takes deposits from users holding a permit for it
+    pub fn require_deposit_permit(
+        &self,
+        config: &AccountInfo,
+        user: &AccountInfo,
+        permit: Option<&AccountInfo>,
+    ) -> Result<(), ProgramError> {
+        if self.whitelist_authority().is_none() {
+            return Ok(());
+        }
+
+        let permit_account = permit.ok_or(PinocchioError::DepositPermitMissing)?;
+        let permit = DepositPermit::load(permit_account)?;
+
+        if permit.config().ne(config.key()) || permit.user().ne(user.key()) {
+            return Err(PinocchioError::DepositPermitMissing.into());
+        }
+        // permits are only ever created by AddToWhitelist, at the pda of these two keys
+        permit.verify_address(permit_account)
+    }
+}
+
+// compile time layout checks, a field change that moves an offset or adds
+// implicit padding fails the build instead of silently changing the account format
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(Config::LEN == 880);
+    assert!(offset_of!(Config, authority) == 0);
+    assert!(offset_of!(Config, mint_x) == 32);
+    assert!(offset_of!(Config, mint_y) == 64);
+    assert!(offset_of!(Config, mint_x_vault) == 96);
+    assert!(offset_of!(Config, mint_y_vault) == 128);
+    assert!(offset_of!(Config, lp_mint) == 160);
+    assert!(offset_of!(Config, min_lp_mint) == 192);
+    assert!(offset_of!(Config, min_lp_burn) == 200);
+    assert!(offset_of!(Config, trading_start_ts) == 208);
+    assert!(offset_of!(Config, fee) == 216);
+    assert!(offset_of!(Config, config_bump) == 218);
+    assert!(offset_of!(Config, no_cpi) == 219);
+    assert!(offset_of!(Config, state) == 220);
+    assert!(offset_of!(Config, lp_bump) == 221);
+    assert!(offset_of!(Config, _padding) == 222);
+    assert!(offset_of!(Config, max_lp_supply) == 224);
+    assert!(offset_of!(Config, admin_nonce) == 232);
+    assert!(offset_of!(Config, seed) == 240);
+    assert!(offset_of!(Config, amp) == 248);
+    assert!(offset_of!(Config, curve_type) == 256);
+    assert!(offset_of!(Config, _curve_padding) == 257);
+    assert!(offset_of!(Config, price_band_min) == 264);
+    assert!(offset_of!(Config, price_band_max) == 272);
+    assert!(offset_of!(Config, pending_price_band_min) == 280);
+    assert!(offset_of!(Config, pending_price_band_max) == 288);
+    assert!(offset_of!(Config, price_band_activation_slot) == 296);
+    assert!(offset_of!(Config, protocol_fee_x) == 304);
+    assert!(offset_of!(Config, protocol_fee_y) == 312);
+    assert!(offset_of!(Config, protocol_fee_bps) == 320);
+    assert!(offset_of!(Config, pending_fee) == 322);
+    assert!(offset_of!(Config, referral_fee_bps) == 324);
+    assert!(offset_of!(Config, _fee_padding) == 326);
+    assert!(offset_of!(Config, fee_activation_slot) == 328);
+    assert!(offset_of!(Config, locked_lp) == 336);
+    assert!(offset_of!(Config, price_cumulative_x) == 344);
+    assert!(offset_of!(Config, price_cumulative_y) == 360);
+    assert!(offset_of!(Config, last_update_timestamp) == 376);
+    assert!(offset_of!(Config, flash_loan_vault_target) == 384);
+    assert!(offset_of!(Config, flash_loan_active) == 392);
+    assert!(offset_of!(Config, flash_loan_x) == 393);
+    assert!(offset_of!(Config, _flash_loan_padding) == 394);
+    assert!(offset_of!(Config, whitelist_authority) == 400);
+    assert!(offset_of!(Config, observations_required) == 432);
+    assert!(offset_of!(Config, _observations_padding) == 433);
+    assert!(offset_of!(Config, event_seq) == 440);
+    assert!(offset_of!(Config, fee_growth_x) == 448);
+    assert!(offset_of!(Config, fee_growth_y) == 464);
+    assert!(offset_of!(Config, stats) == 480);
+    assert!(offset_of!(Config, last_fee_change_slot) == 688);
+    assert!(offset_of!(Config, locked_lp_amount) == 696);
+    assert!(offset_of!(Config, future_amp) == 704);
+    assert!(offset_of!(Config, amp_ramp_start_ts) == 712);
+    assert!(offset_of!(Config, amp_ramp_end_ts) == 720);
+    assert!(offset_of!(Config, launch_window_secs) == 728);
+    assert!(offset_of!(Config, launch_wallet_cap) == 736);
+    assert!(offset_of!(Config, launch_x_to_y) == 744);
+    assert!(offset_of!(Config, _launch_padding) == 745);
+    assert!(offset_of!(Config, launch_slot_cap) == 752);
+    assert!(offset_of!(Config, launch_slot) == 760);
+    assert!(offset_of!(Config, launch_slot_bought) == 768);
+    assert!(offset_of!(Config, royalty_destination) == 776);
+    assert!(offset_of!(Config, royalty_bps) == 808);
+    assert!(offset_of!(Config, max_fee_change_bps) == 810);
+    assert!(offset_of!(Config, _fee_change_padding) == 812);
+    assert!(offset_of!(Config, _reserved) == 816);
+
+    // no implicit padding, every byte belongs to a declared field
+    assert!(offset_of!(Config, _reserved) + RESERVED_LEN == Config::LEN);
+    assert!(RESERVED_LEN >= MIN_RESERVED_LEN);
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::MIN_AMP_RAMP_DURATION;
+
+    // account data is 8 byte aligned by the runtime, the test buffers have to match
+    #[repr(C, align(8))]
+    struct ConfigBytes([u8; Config::LEN]);
+
+    // the views load and load_mut hand out once the length and owner checks pass
+    #[cfg(not(feature = "safe-deser"))]
+    fn view(bytes: &ConfigBytes) -> &Config {
+        unsafe { Config::from_bytes_unchecked(&bytes.0) }
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    fn view_mut(bytes: &mut ConfigBytes) -> &mut Config {
+        unsafe { Config::from_bytes_unchecked_mut(&mut bytes.0) }
+    }
+
+    #[cfg(feature = "safe-deser")]
+    fn view(bytes: &ConfigBytes) -> &Config {
+        bytemuck::from_bytes(&bytes.0)
+    }
+
+    #[cfg(feature = "safe-deser")]
+    fn view_mut(bytes: &mut ConfigBytes) -> &mut Config {
+        bytemuck::from_bytes_mut(&mut bytes.0)
+    }
+
+    fn read_u64(bytes: &ConfigBytes, offset: usize) -> u64 {
+        let mut value = [0; 8];
+        value.copy_from_slice(&bytes.0[offset..offset + 8]);
+        u64::from_le_bytes(value)
+    }
+
+    #[test]
+    fn config_round_trips_through_raw_bytes() {
+        let mut written = ConfigBytes([0; Config::LEN]);
+        {
+            let config = view_mut(&mut written);
+            let set = config.set_inner(
+                [1; 32],
+                [2; 32],
+                [3; 32],
+                [4; 32],
+                [5; 32],
+                [6; 32],
+                7,
+                8,
+                9,
+                10,
+                11,
+                30,
+                254,
+                253,
+                CurveType::Stable { amp: 100 },
+                PriceBand {
+                    min_price: 12,
+                    max_price: 13,
+                },
+            );
+            assert!(set.is_ok());
+            assert!(config.lock_lp(LpAmount::new(1000)).is_ok());
+            assert!(config.add_locked_lp_amount(LpAmount::new(14)).is_ok());
+            assert_eq!(config.next_event_seq(), Ok(0));
+            config.set_whitelist_authority([15; 32]);
+            config.require_observations();
+        }
+
+        // what another instruction reads back from the account data
+        let read = ConfigBytes(written.0);
+        let config = view(&read);
+
+        assert_eq!(config.has_authority(), Some([1; 32]));
+        assert_eq!(config.mint_x(), &[2; 32]);
+        assert_eq!(config.mint_y(), &[3; 32]);
+        assert_eq!(config.mint_x_vault(), &[4; 32]);
+        assert_eq!(config.mint_y_vault(), &[5; 32]);
+        assert_eq!(config.lp_mint(), &[6; 32]);
+        assert!(config.min_lp_mint() == LpAmount::new(7));
+        assert!(config.min_lp_burn() == LpAmount::new(8));
+        assert_eq!(config.trading_start_ts(), 9);
+        assert!(config.max_lp_supply() == Some(LpAmount::new(10)));
+        assert_eq!(config.seed(), 11);
+        assert!(config.fee(0) == Bps::new(30));
+        assert_eq!(config.config_bump(), 254);
+        assert_eq!(config.lp_bump(), 253);
+        assert!(matches!(config.state(), Ok(AmmState::Initialized)));
+        assert!(matches!(
+            config.curve_type(0),
+            Ok(CurveType::Stable { amp: 100 })
+        ));
+        assert_eq!(config.price_band(0).min_price, 12);
+        assert_eq!(config.price_band(0).max_price, 13);
+        assert!(config.locked_lp() == LpAmount::new(1000));
+        assert!(config.locked_lp_amount() == LpAmount::new(14));
+        assert_eq!(config.whitelist_authority(), Some([15; 32]));
+        assert!(config.observations_required());
+
+        // and the bytes themselves sit at the offsets asserted above
+        assert_eq!(&read.0[0..32], &[1; 32]);
+        assert_eq!(&read.0[160..192], &[6; 32]);
+        assert_eq!(read_u64(&read, 192), 7);
+        assert_eq!(&read.0[216..222], &[30, 0, 254, 0, 1, 253]);
+        assert_eq!(read_u64(&read, 240), 11);
+        assert_eq!(read_u64(&read, 248), 100);
+        assert_eq!(read.0[256], CurveType::STABLE);
+        assert_eq!(read_u64(&read, 264), 12);
+        assert_eq!(read_u64(&read, 336), 1000);
+        assert_eq!(&read.0[400..432], &[15; 32]);
+        assert_eq!(read.0[432], 1);
+        assert_eq!(read_u64(&read, 440), 1);
+        assert_eq!(read_u64(&read, 696), 14);
+        assert_eq!(&read.0[810..812], &DEFAULT_MAX_FEE_CHANGE_BPS.to_le_bytes());
+        assert!(read.0[704..810].iter().all(|byte| *byte == 0));
+        assert!(read.0[812..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn raw_bytes_read_back_through_config() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        bytes.0[218] = 200;
+        bytes.0[220] = AmmState::WithdrawOnly as u8;
+        bytes.0[240..248].copy_from_slice(&u64::MAX.to_le_bytes());
+        bytes.0[232..240].copy_from_slice(&42u64.to_le_bytes());
+        bytes.0[448..464].copy_from_slice(&u128::MAX.to_le_bytes());
+        bytes.0[464..480].copy_from_slice(&1u128.to_le_bytes());
+
+        let config = view(&bytes);
+        assert_eq!(config.config_bump(), 200);
+        assert!(matches!(config.state(), Ok(AmmState::WithdrawOnly)));
+        assert_eq!(config.seed(), u64::MAX);
+        assert_eq!(config.admin_nonce(), 42);
+        assert_eq!(config.fee_growth(), (u128::MAX, 1));
+        // an all zero authority reads as renounced
+        assert_eq!(config.has_authority(), None);
+
+        // an unknown state byte is rejected instead of read as some state
+        bytes.0[220] = 4;
+        assert!(view(&bytes).state().is_err());
+    }
+
+    fn initialized_config(bytes: &mut ConfigBytes, curve_type: CurveType) -> &mut Config {
+        let config = view_mut(bytes);
+        let price_band = PriceBand {
+            min_price: 0,
+            max_price: 0,
+        };
+        let set = config.set_inner(
+            [1; 32], [2; 32], [3; 32], [4; 32], [5; 32], [6; 32], 1, 1, 0, 0, 0, 30, 255, 255,
+            curve_type, price_band,
+        );
+        assert!(set.is_ok());
+        config
+    }
+
+    fn amp_at(config: &Config, now: i64) -> u64 {
+        let Ok(CurveType::Stable { amp }) = config.curve_type(now) else {
+            unreachable!()
+        };
+        amp
+    }
+
+    #[test]
+    fn amp_follows_the_scheduled_ramp() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        let config = initialized_config(&mut bytes, CurveType::Stable { amp: 100 });
+        let start = 1_000_000;
+        let end = start + 2 * MIN_AMP_RAMP_DURATION;
+
+        assert!(config.schedule_amp_ramp(200, end, start).is_ok());
+        assert_eq!(amp_at(config, start - 1), 100);
+        assert_eq!(amp_at(config, start), 100);
+        assert_eq!(amp_at(config, start + MIN_AMP_RAMP_DURATION), 150);
+        assert_eq!(amp_at(config, end), 200);
+        assert_eq!(amp_at(config, i64::MAX), 200);
+
+        // a new ramp starts from wherever the running one has got to
+        let now = start + MIN_AMP_RAMP_DURATION;
+        assert!(config
+            .schedule_amp_ramp(50, now + MIN_AMP_RAMP_DURATION, now)
+            .is_ok());
+        assert_eq!(amp_at(config, now), 150);
+        assert_eq!(amp_at(config, now + MIN_AMP_RAMP_DURATION), 50);
+
+        // ramps past the bounds leave the config untouched
+        assert!(config
+            .schedule_amp_ramp(10_000, now + MIN_AMP_RAMP_DURATION, now)
+            .is_err());
+        assert!(config.schedule_amp_ramp(100, now + 1, now).is_err());
+        assert_eq!(amp_at(config, now + MIN_AMP_RAMP_DURATION), 50);
+    }
+
+    #[test]
+    fn fee_changes_stay_within_the_pool_bound() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        let config = initialized_config(&mut bytes, CurveType::ConstantProduct);
+        assert_eq!(config.max_fee_change_bps(), DEFAULT_MAX_FEE_CHANGE_BPS);
+
+        config.set_max_fee_change_bps(10);
+        assert_eq!(
+            config.schedule_fee(41, 1),
+            Err(PinocchioError::FeeChangeRateLimited.into())
+        );
+        assert!(config.schedule_fee(40, 1).is_ok());
+        assert_eq!(config.fee(1 + ADMIN_TIMELOCK_SLOTS).get(), 40);
+
+        // a pool bound of 0 freezes the fee
+        config.set_max_fee_change_bps(0);
+        assert_eq!(
+            config.schedule_fee(41, 1 + ADMIN_TIMELOCK_SLOTS),
+            Err(PinocchioError::FeeChangeRateLimited.into())
+        );
+    }
+
+    #[test]
+    fn launch_guard_lapses_after_the_window() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        let config = initialized_config(&mut bytes, CurveType::ConstantProduct);
+        config.set_trading_start_ts(1_000);
+
+        assert!(config.launch_guard(1_000).is_none());
+        let guard = LaunchGuard {
+            x_to_y: false,
+            wallet_cap: 50,
+            slot_cap: 0,
+        };
+        assert!(config.set_launch_guard(600, guard, 999).is_ok());
+
+        // open from the trading start for window seconds, not before and not after
+        assert!(config.launch_guard(999).is_none());
+        assert!(matches!(
+            config.launch_guard(1_000),
+            Some(LaunchGuard {
+                x_to_y: false,
+                wallet_cap: 50,
+                slot_cap: 0
+            })
+        ));
+        assert!(config.launch_guard(1_599).is_some());
+        assert!(config.launch_guard(1_600).is_none());
+
+        // trading has started, the window can no longer be moved or reopened
+        assert_eq!(
+            config.set_launch_guard(600, guard, 1_000),
+            Err(PinocchioError::LaunchAlreadyStarted.into())
+        );
+        assert_eq!(
+            config.set_launch_guard(MAX_LAUNCH_WINDOW_SECS, guard, 2_000),
+            Err(PinocchioError::LaunchAlreadyStarted.into())
+        );
+    }
+
+    #[test]
+    fn launch_slot_buys_reset_on_a_new_slot() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        let config = initialized_config(&mut bytes, CurveType::ConstantProduct);
+        config.set_trading_start_ts(1_000);
+
+        // no slot cap, nothing is counted
+        assert!(config.record_launch_slot_buy(u64::MAX, 5).is_ok());
+
+        let guard = LaunchGuard {
+            x_to_y: true,
+            wallet_cap: 0,
+            slot_cap: 100,
+        };
+        assert!(config.set_launch_guard(600, guard, 0).is_ok());
+
+        assert!(config.record_launch_slot_buy(70, 5).is_ok());
+        assert!(config.record_launch_slot_buy(30, 5).is_ok());
+        assert_eq!(
+            config.record_launch_slot_buy(1, 5),
+            Err(PinocchioError::LaunchSlotCapExceeded.into())
+        );
+
+        // the first buy of the next slot starts from zero
+        assert!(config.record_launch_slot_buy(100, 6).is_ok());
+        assert_eq!(
+            config.record_launch_slot_buy(1, 6),
+            Err(PinocchioError::LaunchSlotCapExceeded.into())
+        );
+        assert_eq!(
+            config.record_launch_slot_buy(101, 7),
+            Err(PinocchioError::LaunchSlotCapExceeded.into())
+        );
+    }
+
+    #[test]
+    fn constant_product_pools_have_no_amp_to_ramp() {
+        let mut bytes = ConfigBytes([0; Config::LEN]);
+        let config = initialized_config(&mut bytes, CurveType::ConstantProduct);
+
+        assert_eq!(
+            config.schedule_amp_ramp(100, MIN_AMP_RAMP_DURATION, 0),
+            Err(PinocchioError::InvalidCurve.into())
+        );
+        assert!(matches!(
+            config.curve_type(0),
+            Ok(CurveType::ConstantProduct)
+        ));
+    }
+}
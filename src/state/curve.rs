@@ -0,0 +1,1294 @@
+use core::cmp;
+
+use crate::math::{integer_sqrt, mul_div_ceil, mul_div_floor, Bps, LpAmount, TokenAmount};
+
+// fees are expressed in basis points of the input amount
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// smallest lp amount the first deposit may mint
+pub const MINIMUM_INITIAL_LP: u64 = 1000;
+
+// lp taken from the first deposit and locked in the pool forever, so the supply
+// can never be drained back to a handful of units and the share price inflated
+pub const MINIMUM_LIQUIDITY: LpAmount = LpAmount::new(1000);
+
+// bounds on the stable swap amplification coefficient
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+
+// shortest amp ramp the authority may schedule and the most one ramp may scale amp
+// by, so the curve never reshapes faster than arbitrage can follow
+pub const MIN_AMP_RAMP_DURATION: i64 = 86_400;
+pub const MAX_AMP_CHANGE: u64 = 10;
+
+// fixed point scale of spot prices, a price of 1 y per x is PRICE_SCALE
+pub const PRICE_SCALE: u128 = 1_000_000_000;
+
+// the stable swap invariant below is specialised for a two token pool
+const N_COINS: u128 = 2;
+
+// newton iterations normally converge in a handful of steps, this only bounds the loop
+const MAX_ITERATIONS: u16 = 256;
+
+pub enum CurveError {
+    Overflow,
+    InvalidSupply,
+    ZeroAmount,
+    InsufficientLiquidity,
+    InvalidFee,
+    InvalidCurve,
+    NotConverged,
+    InvalidPriceBand,
+    PriceOutOfBand,
+    InvalidAmpRamp,
+}
+
+// pricing curve of a pool, stored in the config at initialization
+#[derive(Clone, Copy)]
+pub enum CurveType {
+    ConstantProduct,
+    // amplified invariant for like priced assets (e.g. usdc/usdt), a higher amp
+    // keeps the price flat over a wider range around the 1:1 point
+    Stable { amp: u64 },
+}
+
+impl CurveType {
+    pub const CONSTANT_PRODUCT: u8 = 0;
+    pub const STABLE: u8 = 1;
+
+    pub fn new(curve_type: u8, amp: u64) -> Result<Self, CurveError> {
+        match curve_type {
+            Self::CONSTANT_PRODUCT => Ok(Self::ConstantProduct),
+            Self::STABLE => {
+                if !(MIN_AMP..=MAX_AMP).contains(&amp) {
+                    return Err(CurveError::InvalidCurve);
+                }
+                Ok(Self::Stable { amp })
+            }
+            _ => Err(CurveError::InvalidCurve),
+        }
+    }
+
+    pub fn discriminator(&self) -> u8 {
+        match self {
+            Self::ConstantProduct => Self::CONSTANT_PRODUCT,
+            Self::Stable { .. } => Self::STABLE,
+        }
+    }
+
+    pub fn amp(&self) -> u64 {
+        match self {
+            Self::ConstantProduct => 0,
+            Self::Stable { amp } => *amp,
+        }
+    }
+
+    pub fn swap_exact_in(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+        fee_bps: Bps,
+    ) -> Result<u64, CurveError> {
+        match self {
+            Self::ConstantProduct => swap_exact_in(reserve_in, reserve_out, amount_in, fee_bps),
+            Self::Stable { amp } => {
+                stable_swap_exact_in(*amp, reserve_in, reserve_out, amount_in, fee_bps)
+            }
+        }
+    }
+
+    // input required to receive exactly `amount_out`, rounded up in favour of the pool
+    pub fn swap_exact_out(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_out: u64,
+        fee_bps: Bps,
+    ) -> Result<u64, CurveError> {
+        match self {
+            Self::ConstantProduct => swap_exact_out(reserve_in, reserve_out, amount_out, fee_bps),
+            Self::Stable { amp } => {
+                stable_swap_exact_out(*amp, reserve_in, reserve_out, amount_out, fee_bps)
+            }
+        }
+    }
+
+    // marginal price of x in terms of y, scaled by PRICE_SCALE
+    pub fn spot_price(&self, reserve_x: u64, reserve_y: u64) -> Result<u128, CurveError> {
+        if reserve_x == 0 || reserve_y == 0 {
+            return Err(CurveError::InsufficientLiquidity);
+        }
+
+        match self {
+            Self::ConstantProduct => {
+                mul_div_floor(reserve_y as u128, PRICE_SCALE, reserve_x as u128)
+                    .ok_or(CurveError::Overflow)
+            }
+            Self::Stable { amp } => stable_spot_price(*amp, reserve_x, reserve_y),
+        }
+    }
+
+    // `fee_bps` only applies to stable pools, where an unbalanced deposit would
+    // otherwise be a fee free swap once withdrawn pro-rata
+    pub fn deposit_lp_amount(
+        &self,
+        amount_x: TokenAmount,
+        amount_y: TokenAmount,
+        reserve_x: TokenAmount,
+        reserve_y: TokenAmount,
+        lp_supply: LpAmount,
+        fee_bps: Bps,
+    ) -> Result<LpAmount, CurveError> {
+        let (amount_x, amount_y) = (amount_x.get(), amount_y.get());
+        let (reserve_x, reserve_y) = (reserve_x.get(), reserve_y.get());
+
+        let lp_amount = match self {
+            Self::ConstantProduct => {
+                deposit_lp_amount(amount_x, amount_y, reserve_x, reserve_y, lp_supply.get())?
+            }
+            Self::Stable { amp } => stable_deposit_lp_amount(
+                *amp,
+                amount_x,
+                amount_y,
+                reserve_x,
+                reserve_y,
+                lp_supply.get(),
+                fee_bps,
+            )?,
+        };
+        Ok(LpAmount::new(lp_amount))
+    }
+
+    // part of a one sided deposit of `amount_in` to swap into the other token first,
+    // so the rest of the input and the swap output match the pool's ratio after the
+    // swap, priced on the pool's own curve
+    pub fn optimal_swap_amount_for_single_deposit(
+        &self,
+        reserve_in: u64,
+        reserve_out: u64,
+        amount_in: u64,
+        fee_bps: Bps,
+    ) -> Result<u64, CurveError> {
+        optimal_swap_amount_for_single_deposit(reserve_in, reserve_out, amount_in, |swap_amount| {
+            self.swap_exact_in(reserve_in, reserve_out, swap_amount, fee_bps)
+        })
+    }
+}
+
+pub struct XYAmounts {
+    pub x: TokenAmount,
+    pub y: TokenAmount,
+}
+
+// narrowing back to u64 is checked, a u128 result that doesn't fit is an error
+#[inline(always)]
+fn to_u64(value: u128) -> Result<u64, CurveError> {
+    u64::try_from(value).map_err(|_| CurveError::Overflow)
+}
+
+// lp minted for depositing `amount_x` / `amount_y` into the current reserves,
+// the first deposit mints sqrt(x * y) and later ones the smaller pro-rata share
+fn deposit_lp_amount(
+    amount_x: u64,
+    amount_y: u64,
+    reserve_x: u64,
+    reserve_y: u64,
+    lp_supply: u64,
+) -> Result<u64, CurveError> {
+    if reserve_x == 0 && reserve_y == 0 {
+        let product = (amount_x as u128)
+            .checked_mul(amount_y as u128)
+            .ok_or(CurveError::Overflow)?;
+
+        if product == 0 {
+            return Err(CurveError::InvalidSupply);
+        }
+
+        let lp_amount = to_u64(integer_sqrt(product))?;
+
+        if lp_amount < MINIMUM_INITIAL_LP {
+            return Err(CurveError::InvalidSupply);
+        }
+
+        return Ok(lp_amount);
+    }
+
+    if reserve_x == 0 || reserve_y == 0 || lp_supply == 0 {
+        return Err(CurveError::InvalidSupply);
+    }
+
+    let lp_from_x = to_u64(
+        mul_div_floor(amount_x as u128, lp_supply as u128, reserve_x as u128)
+            .ok_or(CurveError::Overflow)?,
+    )?;
+
+    let lp_from_y = to_u64(
+        mul_div_floor(amount_y as u128, lp_supply as u128, reserve_y as u128)
+            .ok_or(CurveError::Overflow)?,
+    )?;
+
+    Ok(cmp::min(lp_from_x, lp_from_y))
+}
+
+// hard price bounds for pegged pairs, expressed like CurveType::spot_price.
+// 0 leaves that side of the band open
+#[derive(Clone, Copy)]
+pub struct PriceBand {
+    pub min_price: u64,
+    pub max_price: u64,
+}
+
+impl PriceBand {
+    pub fn is_enabled(&self) -> bool {
+        self.min_price != 0 || self.max_price != 0
+    }
+
+    pub fn validate(&self) -> Result<(), CurveError> {
+        if self.min_price != 0 && self.max_price != 0 && self.min_price > self.max_price {
+            return Err(CurveError::InvalidPriceBand);
+        }
+        Ok(())
+    }
+
+    // a trade may not push the price past a bound, trades that bring an out of band
+    // pool back towards the band are always allowed
+    pub fn check_move(&self, price_before: u128, price_after: u128) -> Result<(), CurveError> {
+        if self.min_price != 0 && price_after < self.min_price as u128 && price_after < price_before
+        {
+            return Err(CurveError::PriceOutOfBand);
+        }
+
+        if self.max_price != 0 && price_after > self.max_price as u128 && price_after > price_before
+        {
+            return Err(CurveError::PriceOutOfBand);
+        }
+        Ok(())
+    }
+}
+
+// whether `price` is within `tolerance_bps` of `expected`, both scaled by PRICE_SCALE
+pub fn price_within_tolerance(price: u128, expected: u64, tolerance_bps: u16) -> bool {
+    let deviation = price.abs_diff(expected as u128);
+
+    match deviation.checked_mul(BPS_DENOMINATOR as u128) {
+        Some(deviation) => deviation <= expected as u128 * tolerance_bps as u128,
+        None => false,
+    }
+}
+
+// share of each reserve owned by `lp_amount`, rounded down in favour of the pool.
+// withdrawing at the pool ratio never moves the price, so this holds for every curve type
+pub fn withdraw_amounts(
+    lp_amount: LpAmount,
+    reserve_x: TokenAmount,
+    reserve_y: TokenAmount,
+    lp_supply: LpAmount,
+) -> Result<XYAmounts, CurveError> {
+    if lp_supply.is_zero() || lp_amount > lp_supply {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    let (lp_amount, lp_supply) = (lp_amount.get(), lp_supply.get());
+    let (reserve_x, reserve_y) = (reserve_x.get(), reserve_y.get());
+
+    let x = to_u64(
+        mul_div_floor(lp_amount as u128, reserve_x as u128, lp_supply as u128)
+            .ok_or(CurveError::Overflow)?,
+    )?;
+
+    let y = to_u64(
+        mul_div_floor(lp_amount as u128, reserve_y as u128, lp_supply as u128)
+            .ok_or(CurveError::Overflow)?,
+    )?;
+
+    if x == 0 && y == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    Ok(XYAmounts {
+        x: TokenAmount::new(x),
+        y: TokenAmount::new(y),
+    })
+}
+
+// input left after the pool fee, the fee stays in the pool for lps
+fn amount_after_fee(amount: u64, fee_bps: Bps) -> Result<u128, CurveError> {
+    if fee_bps.get() as u64 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+
+    mul_div_floor(
+        amount as u128,
+        (BPS_DENOMINATOR - fee_bps.get() as u64) as u128,
+        BPS_DENOMINATOR as u128,
+    )
+    .ok_or(CurveError::Overflow)
+}
+
+// gross input whose post fee amount is at least `amount_after_fee`, the inverse of the above
+fn amount_before_fee(amount_after_fee: u128, fee_bps: Bps) -> Result<u64, CurveError> {
+    if fee_bps.get() as u64 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+
+    let net = (BPS_DENOMINATOR - fee_bps.get() as u64) as u128;
+    to_u64(
+        mul_div_ceil(amount_after_fee, BPS_DENOMINATOR as u128, net).ok_or(CurveError::Overflow)?,
+    )
+}
+
+// fee charged on `amount_in`, whatever amount_after_fee held back from the input
+pub fn fee_amount(amount_in: u64, fee_bps: Bps) -> Result<u64, CurveError> {
+    to_u64(amount_in as u128 - amount_after_fee(amount_in, fee_bps)?)
+}
+
+// creator royalty charged on `amount_in` on top of the swap fee, it comes off the
+// input before pricing and is rounded like the fee
+pub fn royalty_amount(amount_in: u64, royalty_bps: Bps) -> Result<u64, CurveError> {
+    fee_amount(amount_in, royalty_bps)
+}
+
+// gross input that still covers `amount_in` once the royalty is taken, the exact
+// output side of the above
+pub fn amount_with_royalty(amount_in: u64, royalty_bps: Bps) -> Result<u64, CurveError> {
+    amount_before_fee(amount_in as u128, royalty_bps)
+}
+
+// `share_bps` of the fee charged on `amount_in` (the protocol or referral share)
+pub fn fee_share(amount_in: u64, fee_bps: Bps, share_bps: Bps) -> Result<u64, CurveError> {
+    if share_bps.get() as u64 > BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+
+    let fee = fee_amount(amount_in, fee_bps)? as u128;
+
+    to_u64(
+        mul_div_floor(fee, share_bps.get() as u128, BPS_DENOMINATOR as u128)
+            .ok_or(CurveError::Overflow)?,
+    )
+}
+
+// constant product (x * y = k) output for an exact input, the fee is taken from
+// the input before pricing so it stays in the pool for lps
+pub fn swap_exact_in(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_bps: Bps,
+) -> Result<u64, CurveError> {
+    let amount_in_after_fee = amount_after_fee(amount_in, fee_bps)?;
+
+    if amount_in == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let denominator = (reserve_in as u128)
+        .checked_add(amount_in_after_fee)
+        .ok_or(CurveError::Overflow)?;
+
+    let amount_out = to_u64(
+        mul_div_floor(reserve_out as u128, amount_in_after_fee, denominator)
+            .ok_or(CurveError::Overflow)?,
+    )?;
+
+    if amount_out == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    Ok(amount_out)
+}
+
+// largest swap amount `s` whose leftover input still covers the post swap ratio,
+// (amount_in - s) / (reserve_in + s) >= out / (reserve_out - out), with `swap`
+// pricing the output. the left side falls and the right side grows with `s` on
+// either curve, so the exact pricing is bisected in at most 64 steps (the constant
+// product closed form needs more than 128 bits once the reserves get large, the
+// stable one has none). rounding leaves up to about one unit of output worth of the
+// input over, which stays in the pool
+fn optimal_swap_amount_for_single_deposit(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    swap: impl Fn(u64) -> Result<u64, CurveError>,
+) -> Result<u64, CurveError> {
+    if amount_in == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let covers = |swap_amount: u64| -> Result<bool, CurveError> {
+        let amount_out = match swap(swap_amount) {
+            Ok(amount_out) => amount_out,
+            Err(CurveError::ZeroAmount) => 0,
+            Err(err) => return Err(err),
+        };
+
+        let left = (amount_in - swap_amount) as u128 * (reserve_out - amount_out) as u128;
+        let right = (amount_out as u128).checked_mul(reserve_in as u128 + swap_amount as u128);
+
+        // a product past u128 is past anything the left side can reach
+        Ok(right.is_some_and(|right| left >= right))
+    };
+
+    let (mut low, mut high) = (0, amount_in);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if covers(mid)? {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+// constant product input needed for an exact output, both divisions round up so
+// quoting the result back through swap_exact_in pays out at least `amount_out`
+pub fn swap_exact_out(
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee_bps: Bps,
+) -> Result<u64, CurveError> {
+    if amount_out == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    if reserve_in == 0 || amount_out >= reserve_out {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let amount_in_after_fee = mul_div_ceil(
+        reserve_in as u128,
+        amount_out as u128,
+        (reserve_out - amount_out) as u128,
+    )
+    .ok_or(CurveError::Overflow)?;
+
+    amount_before_fee(amount_in_after_fee, fee_bps)
+}
+
+// amp at `now` of a ramp moving linearly from `initial_amp` at `start_ts` to
+// `future_amp` at `end_ts`, before and after the ramp its nearest end holds
+pub fn ramp_amp(initial_amp: u64, future_amp: u64, start_ts: i64, end_ts: i64, now: i64) -> u64 {
+    if now >= end_ts {
+        return future_amp;
+    }
+    if now <= start_ts {
+        return initial_amp;
+    }
+
+    // start_ts < now < end_ts, so the step is below the full amp difference
+    let elapsed = now.abs_diff(start_ts) as u128;
+    let duration = end_ts.abs_diff(start_ts) as u128;
+    let step = |difference: u64| (difference as u128 * elapsed / duration) as u64;
+
+    if future_amp >= initial_amp {
+        initial_amp + step(future_amp - initial_amp)
+    } else {
+        initial_amp - step(initial_amp - future_amp)
+    }
+}
+
+// a ramp from `current_amp` starting at `now` has to stay in the amp bounds, last at
+// least MIN_AMP_RAMP_DURATION and scale amp by at most MAX_AMP_CHANGE either way
+pub fn validate_amp_ramp(
+    current_amp: u64,
+    future_amp: u64,
+    now: i64,
+    end_ts: i64,
+) -> Result<(), CurveError> {
+    if !(MIN_AMP..=MAX_AMP).contains(&future_amp) {
+        return Err(CurveError::InvalidCurve);
+    }
+
+    let min_end_ts = now
+        .checked_add(MIN_AMP_RAMP_DURATION)
+        .ok_or(CurveError::Overflow)?;
+    if end_ts < min_end_ts {
+        return Err(CurveError::InvalidAmpRamp);
+    }
+
+    if future_amp > current_amp.saturating_mul(MAX_AMP_CHANGE)
+        || current_amp > future_amp.saturating_mul(MAX_AMP_CHANGE)
+    {
+        return Err(CurveError::InvalidAmpRamp);
+    }
+    Ok(())
+}
+
+// stable swap invariant D for the balances `x` and `y`, solved by newton iteration on
+// A * n^n * (x + y) + D = A * n^n * D + D^(n + 1) / (n^n * x * y)
+// every step is checked, balances too large for u128 headroom error out instead of mispricing
+pub fn compute_d(amp: u64, x: u64, y: u64) -> Result<u128, CurveError> {
+    if amp < MIN_AMP {
+        return Err(CurveError::InvalidCurve);
+    }
+
+    if x == 0 && y == 0 {
+        return Ok(0);
+    }
+
+    if x == 0 || y == 0 {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let sum = x as u128 + y as u128;
+    let ann = (amp as u128)
+        .checked_mul(N_COINS)
+        .ok_or(CurveError::Overflow)?;
+
+    // the smaller balance is divided out first, dividing a large balance out first
+    // floors away enough of D^(n + 1) / (n^n * x * y) on an unbalanced pool for newton
+    // to oscillate instead of converging. it also makes D independent of the order
+    // the balances are passed in
+    let (small, large) = (cmp::min(x, y) as u128, cmp::max(x, y) as u128);
+
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^(n + 1) / (n^n * x * y), one balance at a time to keep the intermediates small
+        let d_p = d.checked_mul(d).ok_or(CurveError::Overflow)? / (small * N_COINS);
+        let d_p = d_p.checked_mul(d).ok_or(CurveError::Overflow)? / (large * N_COINS);
+
+        let d_prev = d;
+
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|v| v.checked_add(d_p.checked_mul(N_COINS)?))
+            .and_then(|v| v.checked_mul(d))
+            .ok_or(CurveError::Overflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|v| v.checked_add(d_p.checked_mul(N_COINS + 1)?))
+            .ok_or(CurveError::Overflow)?;
+
+        d = numerator / denominator;
+
+        if d.abs_diff(d_prev) <= 1 {
+            return Ok(d);
+        }
+    }
+
+    Err(CurveError::NotConverged)
+}
+
+// balance of the other token that keeps the invariant at `d` once one side holds `x`
+fn compute_y(amp: u64, x: u128, d: u128) -> Result<u128, CurveError> {
+    let ann = (amp as u128)
+        .checked_mul(N_COINS)
+        .ok_or(CurveError::Overflow)?;
+
+    // c = D^(n + 1) / (n^n * x * Ann), b = x + D / Ann
+    let c = d.checked_mul(d).ok_or(CurveError::Overflow)? / (x * N_COINS);
+    let c = c.checked_mul(d).ok_or(CurveError::Overflow)? / (ann * N_COINS);
+    let b = x.checked_add(d / ann).ok_or(CurveError::Overflow)?;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|v| v.checked_add(c))
+            .ok_or(CurveError::Overflow)?;
+        let denominator = y
+            .checked_mul(2)
+            .and_then(|v| v.checked_add(b))
+            .and_then(|v| v.checked_sub(d))
+            .ok_or(CurveError::Overflow)?;
+
+        if denominator == 0 {
+            return Err(CurveError::InsufficientLiquidity);
+        }
+
+        y = numerator / denominator;
+
+        if y.abs_diff(y_prev) <= 1 {
+            return Ok(y);
+        }
+    }
+
+    Err(CurveError::NotConverged)
+}
+
+// stable swap output for an exact input, priced on the invariant after the fee is taken
+pub fn stable_swap_exact_in(
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_in: u64,
+    fee_bps: Bps,
+) -> Result<u64, CurveError> {
+    let amount_in_after_fee = amount_after_fee(amount_in, fee_bps)?;
+
+    if amount_in == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    if reserve_in == 0 || reserve_out == 0 {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = (reserve_in as u128)
+        .checked_add(amount_in_after_fee)
+        .ok_or(CurveError::Overflow)?;
+    let new_reserve_out = compute_y(amp, new_reserve_in, d)?;
+
+    // one unit is kept back so newton's rounding never pays out more than the invariant allows
+    let amount_out = to_u64(
+        (reserve_out as u128)
+            .checked_sub(new_reserve_out)
+            .and_then(|v| v.checked_sub(1))
+            .ok_or(CurveError::ZeroAmount)?,
+    )?;
+
+    if amount_out == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    Ok(amount_out)
+}
+
+// marginal price of x in y on the stable swap invariant, the ratio of its partial
+// derivatives (Ann + D^3 / (4 x^2 y)) / (Ann + D^3 / (4 x y^2)). each term is built
+// from D / 2x and D / 2y, which stay close to 1 around the peg, so the scaled
+// intermediates fit comfortably in u128
+fn stable_spot_price(amp: u64, reserve_x: u64, reserve_y: u64) -> Result<u128, CurveError> {
+    let d = compute_d(amp, reserve_x, reserve_y)?;
+    let ann = (amp as u128)
+        .checked_mul(N_COINS)
+        .ok_or(CurveError::Overflow)?;
+
+    let u = d.checked_mul(PRICE_SCALE).ok_or(CurveError::Overflow)? / (reserve_x as u128 * 2);
+    let v = d.checked_mul(PRICE_SCALE).ok_or(CurveError::Overflow)? / (reserve_y as u128 * 2);
+
+    // D^3 / (4 x^2 y) = (D / 2x)^2 * (D / y), and symmetrically for y
+    let k_x = (u.checked_mul(u).ok_or(CurveError::Overflow)? / PRICE_SCALE)
+        .checked_mul(v * 2)
+        .ok_or(CurveError::Overflow)?
+        / PRICE_SCALE;
+    let k_y = (v.checked_mul(v).ok_or(CurveError::Overflow)? / PRICE_SCALE)
+        .checked_mul(u * 2)
+        .ok_or(CurveError::Overflow)?
+        / PRICE_SCALE;
+
+    let ann = ann.checked_mul(PRICE_SCALE).ok_or(CurveError::Overflow)?;
+    let numerator = ann.checked_add(k_x).ok_or(CurveError::Overflow)?;
+    let denominator = ann.checked_add(k_y).ok_or(CurveError::Overflow)?;
+
+    Ok(numerator
+        .checked_mul(PRICE_SCALE)
+        .ok_or(CurveError::Overflow)?
+        / denominator)
+}
+
+// stable swap input needed for an exact output, solved on the invariant for the
+// input side once the output leaves the pool
+pub fn stable_swap_exact_out(
+    amp: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    amount_out: u64,
+    fee_bps: Bps,
+) -> Result<u64, CurveError> {
+    if amount_out == 0 {
+        return Err(CurveError::ZeroAmount);
+    }
+
+    if reserve_in == 0 || amount_out >= reserve_out {
+        return Err(CurveError::InsufficientLiquidity);
+    }
+
+    let d = compute_d(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = compute_y(amp, (reserve_out - amount_out) as u128, d)?;
+
+    // one extra unit covers newton's rounding here and another the unit exact in
+    // keeps back, so quoting the result through exact in pays at least `amount_out`
+    let amount_in_after_fee = new_reserve_in
+        .saturating_sub(reserve_in as u128)
+        .checked_add(2)
+        .ok_or(CurveError::Overflow)?;
+
+    amount_before_fee(amount_in_after_fee, fee_bps)
+}
+
+// lp minted by a stable pool, proportional to the growth of the invariant. each side
+// that moves the pool away from its current ratio pays half the swap fee on the excess
+fn stable_deposit_lp_amount(
+    amp: u64,
+    amount_x: u64,
+    amount_y: u64,
+    reserve_x: u64,
+    reserve_y: u64,
+    lp_supply: u64,
+    fee_bps: Bps,
+) -> Result<u64, CurveError> {
+    if reserve_x == 0 && reserve_y == 0 {
+        if amount_x == 0 || amount_y == 0 {
+            return Err(CurveError::InvalidSupply);
+        }
+
+        let lp_amount = to_u64(compute_d(amp, amount_x, amount_y)?)?;
+
+        if lp_amount < MINIMUM_INITIAL_LP {
+            return Err(CurveError::InvalidSupply);
+        }
+
+        return Ok(lp_amount);
+    }
+
+    if reserve_x == 0 || reserve_y == 0 || lp_supply == 0 {
+        return Err(CurveError::InvalidSupply);
+    }
+
+    if fee_bps.get() as u64 >= BPS_DENOMINATOR {
+        return Err(CurveError::InvalidFee);
+    }
+
+    let new_x = reserve_x
+        .checked_add(amount_x)
+        .ok_or(CurveError::Overflow)?;
+    let new_y = reserve_y
+        .checked_add(amount_y)
+        .ok_or(CurveError::Overflow)?;
+
+    let d0 = compute_d(amp, reserve_x, reserve_y)?;
+    let d1 = compute_d(amp, new_x, new_y)?;
+
+    let charge_imbalance = |reserve: u64, new_balance: u64| -> Result<u64, CurveError> {
+        let ideal = mul_div_floor(d1, reserve as u128, d0).ok_or(CurveError::Overflow)?;
+        let excess = ideal.abs_diff(new_balance as u128);
+        let imbalance_fee =
+            mul_div_floor(excess, fee_bps.get() as u128, 2 * BPS_DENOMINATOR as u128)
+                .ok_or(CurveError::Overflow)?;
+
+        to_u64(
+            (new_balance as u128)
+                .checked_sub(imbalance_fee)
+                .ok_or(CurveError::Overflow)?,
+        )
+    };
+
+    let d2 = compute_d(
+        amp,
+        charge_imbalance(reserve_x, new_x)?,
+        charge_imbalance(reserve_y, new_y)?,
+    )?;
+
+    let growth = d2.checked_sub(d0).ok_or(CurveError::ZeroAmount)?;
+
+    to_u64(mul_div_floor(lp_supply as u128, growth, d0).ok_or(CurveError::Overflow)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_FEE: Bps = Bps::new(0);
+    const FEE: Bps = Bps::new(30);
+
+    #[test]
+    fn swap_exact_in_tiny_reserves() {
+        // 1 * 1 / (1 + 1) floors to nothing
+        assert!(matches!(
+            swap_exact_in(1, 1, 1, NO_FEE),
+            Err(CurveError::ZeroAmount)
+        ));
+        assert!(matches!(swap_exact_in(2, 2, 2, NO_FEE), Ok(1)));
+        assert!(matches!(swap_exact_in(1, 3, 1, NO_FEE), Ok(1)));
+
+        // the fee floors the input before pricing, 10 * 0.997 -> 9
+        assert!(matches!(swap_exact_in(1000, 1000, 10, FEE), Ok(8)));
+        // and can take a single unit of input down to nothing
+        assert!(matches!(
+            swap_exact_in(1, 1_000_000, 1, FEE),
+            Err(CurveError::ZeroAmount)
+        ));
+    }
+
+    #[test]
+    fn swap_exact_in_huge_reserves() {
+        let max = u64::MAX;
+
+        // max * max fits in u128, so the largest pool prices without overflowing
+        assert!(matches!(swap_exact_in(max, max, max, NO_FEE), Ok(out) if out == max / 2));
+        assert!(matches!(swap_exact_in(1, max, max, NO_FEE), Ok(out) if out == max - 1));
+        assert!(matches!(
+            swap_exact_in(max, max, 1, FEE),
+            Err(CurveError::ZeroAmount)
+        ));
+        assert!(matches!(
+            swap_exact_in(max, 1, max, NO_FEE),
+            Err(CurveError::ZeroAmount)
+        ));
+
+        let Ok(out) = swap_exact_in(max, max, max, FEE) else {
+            unreachable!()
+        };
+        assert!(out < max / 2);
+    }
+
+    #[test]
+    fn swap_exact_in_never_shrinks_k() {
+        let values = [
+            1,
+            2,
+            3,
+            1000,
+            1_000_000,
+            u32::MAX as u64,
+            u64::MAX / 2,
+            u64::MAX,
+        ];
+
+        for reserve_in in values {
+            for reserve_out in values {
+                for amount_in in values {
+                    for fee in [NO_FEE, FEE] {
+                        let Ok(out) = swap_exact_in(reserve_in, reserve_out, amount_in, fee) else {
+                            continue;
+                        };
+
+                        assert!(out < reserve_out);
+                        let k_before = reserve_in as u128 * reserve_out as u128;
+                        let k_after = (reserve_in as u128 + amount_in as u128)
+                            .checked_mul((reserve_out - out) as u128);
+                        assert!(k_after.is_none_or(|k_after| k_after >= k_before));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deposit_never_mints_more_than_the_pro_rata_share() {
+        let values = [
+            1,
+            2,
+            3,
+            1000,
+            1_000_000,
+            u32::MAX as u64,
+            u64::MAX / 2,
+            u64::MAX,
+        ];
+
+        for reserve_x in values {
+            for reserve_y in values {
+                for lp_supply in values {
+                    for amount_x in values {
+                        for amount_y in values {
+                            let Ok(lp) = deposit_lp_amount(
+                                amount_x, amount_y, reserve_x, reserve_y, lp_supply,
+                            ) else {
+                                continue;
+                            };
+
+                            // lp / lp_supply never exceeds the share either side adds
+                            let lp = lp as u128;
+                            let lp_supply = lp_supply as u128;
+                            assert!(lp * reserve_x as u128 <= amount_x as u128 * lp_supply);
+                            assert!(lp * reserve_y as u128 <= amount_y as u128 * lp_supply);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn royalty_grossed_up_input_covers_the_royalty() {
+        for royalty_bps in [Bps::new(0), Bps::new(1), Bps::new(250), Bps::new(1000)] {
+            for amount_in in [1, 2, 3, 999, 1000, 1_000_000, u32::MAX as u64] {
+                let Ok(gross) = amount_with_royalty(amount_in, royalty_bps) else {
+                    unreachable!()
+                };
+                let Ok(royalty) = royalty_amount(gross, royalty_bps) else {
+                    unreachable!()
+                };
+
+                assert!(gross - royalty >= amount_in);
+                // and one unit less would not, the gross up never overcharges
+                if let Ok(short) = royalty_amount(gross - 1, royalty_bps) {
+                    assert!(gross - 1 - short < amount_in);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn swap_exact_in_rejects_bad_input() {
+        assert!(matches!(
+            swap_exact_in(0, 1000, 10, FEE),
+            Err(CurveError::InsufficientLiquidity)
+        ));
+        assert!(matches!(
+            swap_exact_in(1000, 0, 10, FEE),
+            Err(CurveError::InsufficientLiquidity)
+        ));
+        assert!(matches!(
+            swap_exact_in(1000, 1000, 0, FEE),
+            Err(CurveError::ZeroAmount)
+        ));
+        assert!(matches!(
+            swap_exact_in(1000, 1000, 10, Bps::new(10_000)),
+            Err(CurveError::InvalidFee)
+        ));
+    }
+
+    #[test]
+    fn to_u64_rejects_truncation() {
+        assert!(matches!(to_u64(0), Ok(0)));
+        assert!(matches!(to_u64(u64::MAX as u128), Ok(u64::MAX)));
+        assert!(matches!(
+            to_u64(u64::MAX as u128 + 1),
+            Err(CurveError::Overflow)
+        ));
+        assert!(matches!(to_u64(u128::MAX), Err(CurveError::Overflow)));
+    }
+
+    #[test]
+    fn results_past_u64_error_instead_of_wrapping() {
+        let max = u64::MAX;
+
+        // the input for all but one unit of the output reserve is ~max * max
+        assert!(matches!(
+            swap_exact_out(max, max, max - 1, NO_FEE),
+            Err(CurveError::Overflow)
+        ));
+        // the fee grossed back up on an input that only just fits
+        assert!(matches!(
+            amount_before_fee(max as u128, FEE),
+            Err(CurveError::Overflow)
+        ));
+        assert!(matches!(
+            amount_before_fee(max as u128, NO_FEE),
+            Ok(out) if out == max
+        ));
+        // lp shares when the supply dwarfs the reserves
+        assert!(matches!(
+            deposit_lp_amount(max, max, 1, 1, max),
+            Err(CurveError::Overflow)
+        ));
+    }
+
+    const AMPS: [u64; 5] = [MIN_AMP, 10, 100, 2_000, MAX_AMP];
+    const BALANCES: [u64; 5] = [
+        1_000,
+        1_000_000,
+        123_456_789,
+        1_000_000_000_000,
+        1_000_000_000_000_000,
+    ];
+
+    #[test]
+    fn stable_invariant_converges() {
+        for amp in AMPS {
+            for x in BALANCES {
+                for y in BALANCES {
+                    let d = match compute_d(amp, x, y) {
+                        Ok(d) => d,
+                        // only a pool this unbalanced at the top of the range runs
+                        // out of u128 headroom, it never fails to converge
+                        Err(CurveError::Overflow) => {
+                            assert!(x.max(y) >= 1_000_000_000_000_000);
+                            continue;
+                        }
+                        Err(_) => unreachable!(),
+                    };
+
+                    // the invariant sits between the constant product and the
+                    // constant sum of the balances
+                    let sum = x as u128 + y as u128;
+                    assert!(d <= sum);
+                    assert!(d + 1 >= 2 * integer_sqrt(x as u128 * y as u128));
+
+                    // solving for either balance lands back on it, give or take the
+                    // rounding of whichever side the invariant is less sensitive to
+                    for (known, other) in [(x, y), (y, x)] {
+                        let Ok(solved) = compute_y(amp, known as u128, d) else {
+                            unreachable!()
+                        };
+                        let solved_d = compute_d(amp, known, solved as u64);
+                        assert!(
+                            solved.abs_diff(other as u128) <= 1
+                                || solved_d.is_ok_and(|solved_d| solved_d.abs_diff(d) <= 2)
+                        );
+                    }
+                }
+
+                // a balanced pool's invariant is the plain sum
+                let Ok(d) = compute_d(amp, x, x) else {
+                    unreachable!()
+                };
+                assert!(d.abs_diff(2 * x as u128) <= 1);
+            }
+        }
+
+        assert!(matches!(compute_d(0, 1, 1), Err(CurveError::InvalidCurve)));
+        assert!(matches!(compute_d(100, 0, 0), Ok(0)));
+        assert!(matches!(
+            compute_d(100, 0, 1),
+            Err(CurveError::InsufficientLiquidity)
+        ));
+    }
+
+    #[test]
+    fn stable_curve_is_symmetric() {
+        let stable = CurveType::Stable { amp: 100 };
+
+        for x in BALANCES {
+            for y in BALANCES {
+                let (Ok(d_xy), Ok(d_yx)) = (compute_d(100, x, y), compute_d(100, y, x)) else {
+                    continue;
+                };
+                assert_eq!(d_xy, d_yx);
+            }
+
+            // a balanced pool prices 1:1
+            let Ok(price) = stable.spot_price(x, x) else {
+                unreachable!()
+            };
+            assert!(price.abs_diff(PRICE_SCALE) <= 1);
+        }
+
+        // the price of x in y is the inverse of the price of y in x
+        let (Ok(price_xy), Ok(price_yx)) = (
+            stable.spot_price(1_000_000, 3_000_000),
+            stable.spot_price(3_000_000, 1_000_000),
+        ) else {
+            unreachable!()
+        };
+        let product = price_xy * price_yx;
+        assert!(
+            product.abs_diff(PRICE_SCALE * PRICE_SCALE) <= PRICE_SCALE * PRICE_SCALE / 1_000_000
+        );
+    }
+
+    #[test]
+    fn stable_swap_flatter_than_constant_product() {
+        let (reserve, amount) = (1_000_000_000, 100_000_000);
+
+        let Ok(constant_product) = swap_exact_in(reserve, reserve, amount, NO_FEE) else {
+            unreachable!()
+        };
+        let mut previous = constant_product;
+        for amp in AMPS {
+            let Ok(stable) = stable_swap_exact_in(amp, reserve, reserve, amount, NO_FEE) else {
+                unreachable!()
+            };
+            // a higher amp is closer to 1:1, never past it
+            assert!(stable >= previous);
+            assert!(stable < amount);
+            previous = stable;
+        }
+    }
+
+    #[test]
+    fn stable_swap_round_trips() {
+        for amp in AMPS {
+            for reserve_in in BALANCES {
+                for reserve_out in BALANCES {
+                    for amount_out in [1, reserve_out / 1_000, reserve_out / 2] {
+                        let Ok(amount_in) =
+                            stable_swap_exact_out(amp, reserve_in, reserve_out, amount_out, FEE)
+                        else {
+                            continue;
+                        };
+
+                        // quoting the exact out input back through exact in pays at
+                        // least what was asked for
+                        if let Ok(out) =
+                            stable_swap_exact_in(amp, reserve_in, reserve_out, amount_in, FEE)
+                        {
+                            assert!(out >= amount_out);
+                        }
+                    }
+
+                    // swapping there and back never returns more than was sent
+                    let amount_in = reserve_in / 100;
+                    let Ok(out) =
+                        stable_swap_exact_in(amp, reserve_in, reserve_out, amount_in, FEE)
+                    else {
+                        continue;
+                    };
+                    if let Ok(back) = stable_swap_exact_in(
+                        amp,
+                        reserve_out - out,
+                        reserve_in + amount_in,
+                        out,
+                        FEE,
+                    ) {
+                        assert!(back <= amount_in);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stable_lp_round_trips() {
+        let stable = CurveType::Stable { amp: 100 };
+        let (x, y) = (
+            TokenAmount::new(1_000_000_000),
+            TokenAmount::new(1_500_000_000),
+        );
+
+        // the first deposit mints the invariant
+        let Ok(supply) = stable.deposit_lp_amount(
+            x,
+            y,
+            TokenAmount::ZERO,
+            TokenAmount::ZERO,
+            LpAmount::ZERO,
+            FEE,
+        ) else {
+            unreachable!()
+        };
+        let Ok(d) = compute_d(100, x.get(), y.get()) else {
+            unreachable!()
+        };
+        assert_eq!(supply.get() as u128, d);
+
+        for (amount_x, amount_y) in [
+            (1_000_000, 1_500_000),
+            (10_000_000, 0),
+            (0, 10_000_000),
+            (500_000_000, 1),
+        ] {
+            let (amount_x, amount_y) = (TokenAmount::new(amount_x), TokenAmount::new(amount_y));
+            let Ok(lp) = stable.deposit_lp_amount(amount_x, amount_y, x, y, supply, FEE) else {
+                unreachable!()
+            };
+
+            // withdrawing what was just minted leaves the pool's invariant no
+            // smaller than before the deposit, the lp took no value out of it
+            let (Some(reserve_x), Some(reserve_y), Some(new_supply)) = (
+                x.checked_add(amount_x),
+                y.checked_add(amount_y),
+                supply.checked_add(lp),
+            ) else {
+                unreachable!()
+            };
+            let Ok(out) = withdraw_amounts(lp, reserve_x, reserve_y, new_supply) else {
+                unreachable!()
+            };
+            let Ok(d_after) = compute_d(
+                100,
+                reserve_x.get() - out.x.get(),
+                reserve_y.get() - out.y.get(),
+            ) else {
+                unreachable!()
+            };
+            assert!(d_after >= d);
+
+            // a balanced deposit loses no more than rounding, an unbalanced one pays
+            // the imbalance fee
+            if amount_x.get() * 3 == amount_y.get() * 2 {
+                assert!(out.x.get() + 2 >= amount_x.get());
+                assert!(out.y.get() + 2 >= amount_y.get());
+            } else {
+                let Ok(lp_without_fee) =
+                    stable.deposit_lp_amount(amount_x, amount_y, x, y, supply, NO_FEE)
+                else {
+                    unreachable!()
+                };
+                assert!(lp < lp_without_fee);
+            }
+        }
+    }
+
+    #[test]
+    fn ramp_amp_interpolates_linearly() {
+        // before, at and after the ends of the ramp
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, 0), 100);
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, 1000), 100);
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, 2000), 200);
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, i64::MAX), 200);
+
+        // up and down ramps move by the elapsed share, rounded towards the start
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, 1500), 150);
+        assert_eq!(ramp_amp(200, 100, 1000, 2000, 1500), 150);
+        assert_eq!(ramp_amp(100, 200, 1000, 2000, 1999), 199);
+        assert_eq!(ramp_amp(200, 100, 1000, 2000, 1999), 101);
+        assert_eq!(ramp_amp(1, 10, 0, 3, 1), 4);
+
+        // a ramp spanning the whole timestamp range can't overflow
+        assert_eq!(ramp_amp(MIN_AMP, MAX_AMP, i64::MIN, i64::MAX, 0), 500_000);
+    }
+
+    #[test]
+    fn ramp_amp_stays_between_its_ends() {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..10_000 {
+            let initial_amp = next() % MAX_AMP + 1;
+            let future_amp = next() % MAX_AMP + 1;
+            let start_ts = (next() % 1_000_000) as i64;
+            let end_ts = start_ts + (next() % 1_000_000) as i64 + 1;
+            let now = start_ts + (next() % 1_000_000) as i64;
+
+            let amp = ramp_amp(initial_amp, future_amp, start_ts, end_ts, now);
+            let later = ramp_amp(initial_amp, future_amp, start_ts, end_ts, now + 1);
+
+            assert!(amp >= initial_amp.min(future_amp) && amp <= initial_amp.max(future_amp));
+            // every second moves amp towards the target, never back
+            if future_amp >= initial_amp {
+                assert!(later >= amp);
+            } else {
+                assert!(later <= amp);
+            }
+        }
+    }
+
+    #[test]
+    fn amp_ramps_are_bounded() {
+        let now = 1_000_000;
+        let end_ts = now + MIN_AMP_RAMP_DURATION;
+
+        assert!(validate_amp_ramp(100, 1000, now, end_ts).is_ok());
+        assert!(validate_amp_ramp(100, 10, now, end_ts).is_ok());
+        assert!(validate_amp_ramp(100, 100, now, end_ts).is_ok());
+
+        assert!(matches!(
+            validate_amp_ramp(100, 1001, now, end_ts),
+            Err(CurveError::InvalidAmpRamp)
+        ));
+        assert!(matches!(
+            validate_amp_ramp(100, 9, now, end_ts),
+            Err(CurveError::InvalidAmpRamp)
+        ));
+        assert!(matches!(
+            validate_amp_ramp(100, 200, now, end_ts - 1),
+            Err(CurveError::InvalidAmpRamp)
+        ));
+        assert!(matches!(
+            validate_amp_ramp(100, 0, now, end_ts),
+            Err(CurveError::InvalidCurve)
+        ));
+        assert!(matches!(
+            validate_amp_ramp(MAX_AMP, MAX_AMP + 1, now, end_ts),
+            Err(CurveError::InvalidCurve)
+        ));
+        assert!(matches!(
+            validate_amp_ramp(100, 200, i64::MAX, i64::MAX),
+            Err(CurveError::Overflow)
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{AccountCheck, SignerAccount},
+    pdas::DCA_ORDER_SEED,
+};
+
+// a dollar cost averaging order, the input tokens sit in the order's ata (the
+// escrow) and keepers swap `amount_per_tick` of them every `interval` seconds
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct DcaOrder {
+    owner: Pubkey,
+    config: Pubkey,
+    seed: u64,
+    amount_per_tick: u64,
+    // least output for a full tick, partial ticks are held to the same price
+    min_out_per_tick: u64,
+    // paid to the keeper from the escrow on every tick, in the input token
+    tip_per_tick: u64,
+    interval: i64,
+    next_tick_ts: i64,
+    bump: u8,
+    // 1 when the order sells x for y
+    x_to_y: u8,
+    _padding: [u8; 6],
+}
+
+// everything an order is opened with, see DcaOrder::set_inner
+#[derive(Clone, Copy)]
+pub struct DcaOrderParams {
+    pub owner: Pubkey,
+    pub config: Pubkey,
+    pub seed: u64,
+    pub bump: u8,
+    pub x_to_y: bool,
+    pub amount_per_tick: u64,
+    pub min_out_per_tick: u64,
+    pub tip_per_tick: u64,
+    pub interval: i64,
+    pub next_tick_ts: i64,
+}
+
+impl DcaOrder {
+    pub const LEN: usize = size_of::<Self>();
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const DcaOrder)
+        }))
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(
+            account_info.try_borrow_mut_data()?,
+            |data| unsafe { &mut *(data.as_mut_ptr() as *mut DcaOrder) },
+        ))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| {
+            bytemuck::from_bytes::<Self>(data)
+        }))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut::<Self>(data)
+        }))
+    }
+
+    pub fn set_inner(&mut self, params: DcaOrderParams) {
+        self.owner = params.owner;
+        self.config = params.config;
+        self.seed = params.seed;
+        self.bump = params.bump;
+        self.x_to_y = params.x_to_y as u8;
+        self.amount_per_tick = params.amount_per_tick;
+        self.min_out_per_tick = params.min_out_per_tick;
+        self.tip_per_tick = params.tip_per_tick;
+        self.interval = params.interval;
+        self.next_tick_ts = params.next_tick_ts;
+    }
+
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
+    pub fn config(&self) -> &Pubkey {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub fn x_to_y(&self) -> bool {
+        self.x_to_y == 1
+    }
+
+    pub fn amount_per_tick(&self) -> u64 {
+        self.amount_per_tick
+    }
+
+    pub fn min_out_per_tick(&self) -> u64 {
+        self.min_out_per_tick
+    }
+
+    pub fn tip_per_tick(&self) -> u64 {
+        self.tip_per_tick
+    }
+
+    pub fn require_due(&self, now: i64) -> Result<(), ProgramError> {
+        if now < self.next_tick_ts {
+            return Err(PinocchioError::DcaNotDue.into());
+        }
+        Ok(())
+    }
+
+    // the next tick is scheduled from now rather than from the missed slot, so a
+    // keeper outage never lets a burst of back to back ticks through
+    pub fn advance(&mut self, now: i64) -> Result<(), ProgramError> {
+        self.next_tick_ts = now
+            .checked_add(self.interval)
+            .ok_or(PinocchioError::MathOverflow)?;
+        Ok(())
+    }
+
+    // the order has to be the canonical pda for its config, owner and seed
+    pub fn verify_address(&self, order: &AccountInfo) -> Result<(), ProgramError> {
+        let seed = self.seed.to_le_bytes();
+        let address = create_program_address(
+            &[
+                DCA_ORDER_SEED,
+                &self.config,
+                &self.owner,
+                &seed,
+                &[self.bump],
+            ],
+            &crate::ID,
+        )?;
+
+        if address.ne(order.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+
+    pub fn require_owner(&self, signer: &AccountInfo) -> Result<(), ProgramError> {
+        SignerAccount::check(signer)?;
+
+        if self.owner.ne(signer.key()) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+        Ok(())
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(DcaOrder::LEN == 120);
+
+    assert!(offset_of!(DcaOrder, owner) == 0);
+    assert!(offset_of!(DcaOrder, config) == 32);
+    assert!(offset_of!(DcaOrder, seed) == 64);
+    assert!(offset_of!(DcaOrder, amount_per_tick) == 72);
+    assert!(offset_of!(DcaOrder, min_out_per_tick) == 80);
+    assert!(offset_of!(DcaOrder, tip_per_tick) == 88);
+    assert!(offset_of!(DcaOrder, interval) == 96);
+    assert!(offset_of!(DcaOrder, next_tick_ts) == 104);
+    assert!(offset_of!(DcaOrder, bump) == 112);
+    assert!(offset_of!(DcaOrder, x_to_y) == 113);
+    assert!(offset_of!(DcaOrder, _padding) == 114);
+};
@@ -0,0 +1,153 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::{
+    pdas::DEPOSIT_PERMIT_SEED,
+    state::{Observation, Observations},
+};
+
+// lets `user` deposit into the permissioned pool `config`, issued by the pool's
+// whitelist authority through AddToWhitelist
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct DepositPermit {
+    discriminator: u8,
+    bump: u8,
+    _padding: [u8; 6],
+    config: Pubkey,
+    user: Pubkey,
+}
+
+impl DepositPermit {
+    pub const LEN: usize = size_of::<Self>();
+
+    // tags an initialized permit, a freshly created account is all zeroes
+    pub const DISCRIMINATOR: u8 = 1;
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let permit = Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const DepositPermit)
+        });
+        if permit.discriminator != Self::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(permit)
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(
+            account_info.try_borrow_mut_data()?,
+            |data| unsafe { &mut *(data.as_mut_ptr() as *mut DepositPermit) },
+        ))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let permit = Ref::map(account_info.try_borrow_data()?, |data| {
+            bytemuck::from_bytes::<Self>(data)
+        });
+        if permit.discriminator != Self::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(permit)
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut::<Self>(data)
+        }))
+    }
+
+    pub fn set_inner(&mut self, config: Pubkey, user: Pubkey, bump: u8) {
+        self.discriminator = Self::DISCRIMINATOR;
+        self.config = config;
+        self.user = user;
+        self.bump = bump;
+    }
+
+    pub fn config(&self) -> &Pubkey {
+        &self.config
+    }
+
+    pub fn user(&self) -> &Pubkey {
+        &self.user
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    // the permit has to be the canonical pda of its config and user, an account
+    // that only copies their keys is not a permit
+    pub fn verify_address(&self, permit: &AccountInfo) -> Result<(), ProgramError> {
+        let address = create_program_address(
+            &[DEPOSIT_PERMIT_SEED, &self.config, &self.user, &[self.bump]],
+            &crate::ID,
+        )?;
+
+        if address.ne(permit.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(DepositPermit::LEN == 72);
+
+    assert!(offset_of!(DepositPermit, discriminator) == 0);
+    assert!(offset_of!(DepositPermit, bump) == 1);
+    assert!(offset_of!(DepositPermit, _padding) == 2);
+    assert!(offset_of!(DepositPermit, config) == 8);
+    assert!(offset_of!(DepositPermit, user) == 40);
+
+    // deposits tell this account apart from the observation buffer by its length alone
+    assert!(!(DepositPermit::LEN - Observations::LEN).is_multiple_of(Observation::LEN));
+};
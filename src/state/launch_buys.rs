@@ -0,0 +1,207 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::{
+    error::PinocchioError,
+    pdas::LAUNCH_BUYS_SEED,
+    state::{Observation, Observations},
+};
+
+// how much of the launched token `user` bought from the pool `config` during its
+// launch window, created by the user's first buy in the window
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct LaunchBuys {
+    discriminator: u8,
+    bump: u8,
+    _padding: [u8; 6],
+    config: Pubkey,
+    user: Pubkey,
+    bought: u64,
+}
+
+impl LaunchBuys {
+    pub const LEN: usize = size_of::<Self>();
+
+    // tags an initialized account, a freshly created account is all zeroes
+    pub const DISCRIMINATOR: u8 = 2;
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let buys = Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const LaunchBuys)
+        });
+        if buys.discriminator != Self::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(buys)
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(
+            account_info.try_borrow_mut_data()?,
+            |data| unsafe { &mut *(data.as_mut_ptr() as *mut LaunchBuys) },
+        ))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        let buys = Ref::map(account_info.try_borrow_data()?, |data| {
+            bytemuck::from_bytes::<Self>(data)
+        });
+        if buys.discriminator != Self::DISCRIMINATOR {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(buys)
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut::<Self>(data)
+        }))
+    }
+
+    pub fn set_inner(&mut self, config: Pubkey, user: Pubkey, bump: u8) {
+        self.discriminator = Self::DISCRIMINATOR;
+        self.config = config;
+        self.user = user;
+        self.bump = bump;
+        self.bought = 0;
+    }
+
+    pub fn config(&self) -> &Pubkey {
+        &self.config
+    }
+
+    pub fn user(&self) -> &Pubkey {
+        &self.user
+    }
+
+    pub fn bought(&self) -> u64 {
+        self.bought
+    }
+
+    // adds `amount` to what the user bought, as long as the total stays within `cap`
+    pub fn record_buy(&mut self, amount: u64, cap: u64) -> Result<(), ProgramError> {
+        self.bought = self
+            .bought
+            .checked_add(amount)
+            .filter(|bought| *bought <= cap)
+            .ok_or(PinocchioError::LaunchBuyCapExceeded)?;
+        Ok(())
+    }
+
+    // the account has to be the canonical pda of its config and user, an account
+    // that only copies their keys could be swapped for a fresh one to reset the cap
+    pub fn verify_address(&self, buys: &AccountInfo) -> Result<(), ProgramError> {
+        let address = create_program_address(
+            &[LAUNCH_BUYS_SEED, &self.config, &self.user, &[self.bump]],
+            &crate::ID,
+        )?;
+
+        if address.ne(buys.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(LaunchBuys::LEN == 80);
+
+    assert!(offset_of!(LaunchBuys, discriminator) == 0);
+    assert!(offset_of!(LaunchBuys, bump) == 1);
+    assert!(offset_of!(LaunchBuys, _padding) == 2);
+    assert!(offset_of!(LaunchBuys, config) == 8);
+    assert!(offset_of!(LaunchBuys, user) == 40);
+    assert!(offset_of!(LaunchBuys, bought) == 72);
+
+    // swaps tell this account apart from the observation buffer by its length alone
+    assert!(!(LaunchBuys::LEN - Observations::LEN).is_multiple_of(Observation::LEN));
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C, align(8))]
+    struct LaunchBuysBytes([u8; LaunchBuys::LEN]);
+
+    #[cfg(not(feature = "safe-deser"))]
+    fn view_mut(bytes: &mut LaunchBuysBytes) -> &mut LaunchBuys {
+        unsafe { &mut *(bytes.0.as_mut_ptr() as *mut LaunchBuys) }
+    }
+
+    #[cfg(feature = "safe-deser")]
+    fn view_mut(bytes: &mut LaunchBuysBytes) -> &mut LaunchBuys {
+        bytemuck::from_bytes_mut(&mut bytes.0)
+    }
+
+    #[test]
+    fn buys_stop_at_the_wallet_cap() {
+        let mut bytes = LaunchBuysBytes([0; LaunchBuys::LEN]);
+        let buys = view_mut(&mut bytes);
+        buys.set_inner([1; 32], [2; 32], 255);
+
+        assert!(buys.record_buy(60, 100).is_ok());
+        assert!(buys.record_buy(40, 100).is_ok());
+        assert_eq!(buys.bought(), 100);
+
+        // a buy past the cap is rejected whole, not filled up to it
+        assert_eq!(
+            buys.record_buy(1, 100),
+            Err(PinocchioError::LaunchBuyCapExceeded.into())
+        );
+        assert_eq!(
+            buys.record_buy(u64::MAX, u64::MAX),
+            Err(PinocchioError::LaunchBuyCapExceeded.into())
+        );
+        assert_eq!(buys.bought(), 100);
+    }
+}
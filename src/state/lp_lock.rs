@@ -0,0 +1,178 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::{
+    error::PinocchioError,
+    instructions::{AccountCheck, SignerAccount},
+    pdas::LP_LOCK_SEED,
+};
+
+// lp tokens of `owner` held in the lock's ata (the escrow) until `unlock_ts`, counted
+// in the pool's Config::locked_lp_amount while locked
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct LpLock {
+    owner: Pubkey,
+    config: Pubkey,
+    seed: u64,
+    amount: u64,
+    unlock_ts: i64,
+    bump: u8,
+    _padding: [u8; 7],
+}
+
+impl LpLock {
+    pub const LEN: usize = size_of::<Self>();
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| unsafe {
+            &*(data.as_ptr() as *const LpLock)
+        }))
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if account_info.owner().ne(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(
+            account_info.try_borrow_mut_data()?,
+            |data| unsafe { &mut *(data.as_mut_ptr() as *mut LpLock) },
+        ))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(Ref::map(account_info.try_borrow_data()?, |data| {
+            bytemuck::from_bytes::<Self>(data)
+        }))
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        if account_info.data_len() != Self::LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        Ok(RefMut::map(account_info.try_borrow_mut_data()?, |data| {
+            bytemuck::from_bytes_mut::<Self>(data)
+        }))
+    }
+
+    pub fn set_inner(
+        &mut self,
+        owner: Pubkey,
+        config: Pubkey,
+        seed: u64,
+        bump: u8,
+        amount: u64,
+        unlock_ts: i64,
+    ) {
+        self.owner = owner;
+        self.config = config;
+        self.seed = seed;
+        self.bump = bump;
+        self.amount = amount;
+        self.unlock_ts = unlock_ts;
+    }
+
+    pub fn owner(&self) -> &Pubkey {
+        &self.owner
+    }
+
+    pub fn config(&self) -> &Pubkey {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub fn amount(&self) -> u64 {
+        self.amount
+    }
+
+    pub fn require_unlocked(&self, now: i64) -> Result<(), ProgramError> {
+        if now < self.unlock_ts {
+            return Err(PinocchioError::LpStillLocked.into());
+        }
+        Ok(())
+    }
+
+    // the lock has to be the canonical pda for its config, owner and seed
+    pub fn verify_address(&self, lock: &AccountInfo) -> Result<(), ProgramError> {
+        let seed = self.seed.to_le_bytes();
+        let address = create_program_address(
+            &[LP_LOCK_SEED, &self.config, &self.owner, &seed, &[self.bump]],
+            &crate::ID,
+        )?;
+
+        if address.ne(lock.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+
+    pub fn require_owner(&self, signer: &AccountInfo) -> Result<(), ProgramError> {
+        SignerAccount::check(signer)?;
+
+        if self.owner.ne(signer.key()) {
+            return Err(PinocchioError::InvalidOwner.into());
+        }
+        Ok(())
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(LpLock::LEN == 96);
+
+    assert!(offset_of!(LpLock, owner) == 0);
+    assert!(offset_of!(LpLock, config) == 32);
+    assert!(offset_of!(LpLock, seed) == 64);
+    assert!(offset_of!(LpLock, amount) == 72);
+    assert!(offset_of!(LpLock, unlock_ts) == 80);
+    assert!(offset_of!(LpLock, bump) == 88);
+    assert!(offset_of!(LpLock, _padding) == 89);
+};
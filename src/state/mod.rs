@@ -0,0 +1,17 @@
+pub mod config;
+pub mod curve;
+pub mod dca;
+pub mod deposit_permit;
+pub mod launch_buys;
+pub mod lp_lock;
+pub mod observations;
+pub mod pool_stats;
+
+pub use config::*;
+pub use curve::*;
+pub use dca::*;
+pub use deposit_permit::*;
+pub use launch_buys::*;
+pub use lp_lock::*;
+pub use observations::*;
+pub use pool_stats::*;
@@ -0,0 +1,242 @@
+use pinocchio::{
+    account_info::{AccountInfo, Ref, RefMut},
+    program_error::ProgramError,
+    pubkey::{create_program_address, Pubkey},
+};
+
+use crate::pdas::OBSERVATIONS_SEED;
+
+// most snapshots a pool can keep, bounds the account size
+pub const MAX_OBSERVATIONS: u16 = 64;
+
+// reserves of the pool at `timestamp`, 0 marks a slot that was never written
+#[derive(Clone, Copy)]
+pub struct Observation {
+    pub timestamp: i64,
+    pub reserve_x: u64,
+    pub reserve_y: u64,
+}
+
+impl Observation {
+    // slots are stored as little endian timestamp | reserve_x | reserve_y, so they
+    // need no alignment and read the same with or without safe-deser
+    pub const LEN: usize = 24;
+
+    pub fn read(data: &[u8]) -> Result<Self, ProgramError> {
+        let word = |offset: usize| -> Result<[u8; 8], ProgramError> {
+            data.get(offset..offset + 8)
+                .and_then(|word| word.try_into().ok())
+                .ok_or(ProgramError::InvalidAccountData)
+        };
+
+        Ok(Self {
+            timestamp: i64::from_le_bytes(word(0)?),
+            reserve_x: u64::from_le_bytes(word(8)?),
+            reserve_y: u64::from_le_bytes(word(16)?),
+        })
+    }
+
+    fn write(&self, data: &mut [u8]) {
+        data[0..8].copy_from_slice(&self.timestamp.to_le_bytes());
+        data[8..16].copy_from_slice(&self.reserve_x.to_le_bytes());
+        data[16..24].copy_from_slice(&self.reserve_y.to_le_bytes());
+    }
+}
+
+// header of a pool's observation ring buffer, the `cardinality_next` slots follow
+// it in the account data. a twap over any window the buffer covers is the time
+// weighted mean of the spot prices of consecutive snapshots
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct Observations {
+    config: Pubkey,
+    // slot of the most recent snapshot
+    index: u16,
+    // slots in use by the ring
+    cardinality: u16,
+    // slots allocated, the ring grows into them once it wraps so no snapshot is
+    // overwritten before its time
+    cardinality_next: u16,
+    bump: u8,
+    _padding: u8,
+}
+
+impl Observations {
+    pub const LEN: usize = size_of::<Self>();
+
+    // account size for a buffer of `cardinality` slots
+    pub const fn space(cardinality: u16) -> usize {
+        Self::LEN + cardinality as usize * Observation::LEN
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    fn from_bytes(data: &[u8]) -> &Self {
+        unsafe { &*(data.as_ptr() as *const Observations) }
+    }
+
+    #[cfg(not(feature = "safe-deser"))]
+    #[inline(always)]
+    fn from_bytes_mut(data: &mut [u8]) -> &mut Self {
+        unsafe { &mut *(data.as_mut_ptr() as *mut Observations) }
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    fn from_bytes(data: &[u8]) -> &Self {
+        bytemuck::from_bytes::<Self>(&data[..Self::LEN])
+    }
+
+    #[cfg(feature = "safe-deser")]
+    #[inline(always)]
+    fn from_bytes_mut(data: &mut [u8]) -> &mut Self {
+        bytemuck::from_bytes_mut::<Self>(&mut data[..Self::LEN])
+    }
+
+    // the header and every allocated slot have to be there
+    fn check(account_info: &AccountInfo, data: &[u8]) -> Result<(), ProgramError> {
+        if !account_info.is_owned_by(&crate::ID) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        if data.len() < Self::LEN
+            || data.len() != Self::space(Self::from_bytes(data).cardinality_next)
+        {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    #[inline(always)]
+    pub fn load(account_info: &AccountInfo) -> Result<Ref<'_, Self>, ProgramError> {
+        let data = account_info.try_borrow_data()?;
+        Self::check(account_info, &data)?;
+
+        Ok(Ref::map(data, |data| Self::from_bytes(data)))
+    }
+
+    #[inline(always)]
+    pub fn load_mut(account_info: &AccountInfo) -> Result<RefMut<'_, Self>, ProgramError> {
+        let data = account_info.try_borrow_mut_data()?;
+        Self::check(account_info, &data)?;
+
+        Ok(RefMut::map(data, |data| Self::from_bytes_mut(data)))
+    }
+
+    // writes the header of a freshly created buffer, which load can't read yet. every
+    // requested slot is in use from the start and the first snapshot goes to slot 0
+    pub fn init(
+        account_info: &AccountInfo,
+        config: Pubkey,
+        bump: u8,
+        cardinality: u16,
+    ) -> Result<(), ProgramError> {
+        let mut data = account_info.try_borrow_mut_data()?;
+
+        if data.len() != Self::space(cardinality) || cardinality == 0 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let observations = Self::from_bytes_mut(&mut data);
+        observations.config = config;
+        observations.bump = bump;
+        observations.cardinality = cardinality;
+        observations.cardinality_next = cardinality;
+        observations.index = cardinality - 1;
+
+        Ok(())
+    }
+
+    pub fn config(&self) -> &Pubkey {
+        &self.config
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    pub fn cardinality(&self) -> u16 {
+        self.cardinality
+    }
+
+    pub fn cardinality_next(&self) -> u16 {
+        self.cardinality_next
+    }
+
+    // only records the new size, the account has to be resized to it right after
+    pub fn set_cardinality_next(&mut self, cardinality_next: u16) {
+        self.cardinality_next = cardinality_next;
+    }
+
+    // the buffer has to be the canonical pda of the pool it snapshots
+    pub fn verify_address(
+        &self,
+        observations: &AccountInfo,
+        config: &AccountInfo,
+    ) -> Result<(), ProgramError> {
+        if self.config.ne(config.key()) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let address =
+            create_program_address(&[OBSERVATIONS_SEED, &self.config, &[self.bump]], &crate::ID)?;
+
+        if address.ne(observations.key()) {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        Ok(())
+    }
+
+    // snapshots the reserves a trade is about to price against. only the first
+    // trade of each second is kept, so a burst of trades can't flush the buffer
+    pub fn record(
+        account_info: &AccountInfo,
+        config: &AccountInfo,
+        now: i64,
+        reserve_x: u64,
+        reserve_y: u64,
+    ) -> Result<(), ProgramError> {
+        let mut data = account_info.try_borrow_mut_data()?;
+        Self::check(account_info, &data)?;
+
+        let (header, slots) = data.split_at_mut(Self::LEN);
+        let observations = Self::from_bytes_mut(header);
+        observations.verify_address(account_info, config)?;
+
+        let last = observations.index as usize * Observation::LEN;
+        if Observation::read(&slots[last..])?.timestamp == now {
+            return Ok(());
+        }
+
+        if observations.index + 1 == observations.cardinality {
+            observations.cardinality = observations.cardinality_next;
+        }
+        observations.index = (observations.index + 1) % observations.cardinality;
+
+        let next = observations.index as usize * Observation::LEN;
+        Observation {
+            timestamp: now,
+            reserve_x,
+            reserve_y,
+        }
+        .write(&mut slots[next..next + Observation::LEN]);
+
+        Ok(())
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(Observations::LEN == 40);
+
+    assert!(offset_of!(Observations, config) == 0);
+    assert!(offset_of!(Observations, index) == 32);
+    assert!(offset_of!(Observations, cardinality) == 34);
+    assert!(offset_of!(Observations, cardinality_next) == 36);
+    assert!(offset_of!(Observations, bump) == 38);
+    assert!(offset_of!(Observations, _padding) == 39);
+};
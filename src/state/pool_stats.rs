@@ -0,0 +1,95 @@
+use pinocchio::program_error::ProgramError;
+
+use crate::error::PinocchioError;
+
+// finalized buckets a pool keeps, enough for a weekly apr on mainnet epochs
+pub const EPOCH_STATS_HISTORY_LEN: usize = 4;
+
+// swap volume and fees of one bucket, each counted in the input token of the trade.
+// a bucket covers every swap from `epoch` until the RolloverEpochStats closing it, a
+// pool's first bucket reads epoch 0 and runs until the first rollover
+#[repr(C)]
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "safe-deser", derive(bytemuck::Pod, bytemuck::Zeroable))]
+pub struct EpochStats {
+    pub epoch: u64,
+    pub volume_x: u64,
+    pub volume_y: u64,
+    pub fees_x: u64,
+    pub fees_y: u64,
+}
+
+// per epoch swap statistics of a pool, stored in its config so readers need no indexer
+#[repr(C)]
+#[cfg_attr(
+    feature = "safe-deser",
+    derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)
+)]
+pub struct PoolStats {
+    current: EpochStats,
+    // ring of finalized buckets, `history_head` is the slot written next
+    history: [EpochStats; EPOCH_STATS_HISTORY_LEN],
+    history_head: u64,
+}
+
+impl PoolStats {
+    pub const LEN: usize = size_of::<Self>();
+
+    pub fn current(&self) -> &EpochStats {
+        &self.current
+    }
+
+    // finalized buckets, most recent first. a slot never written reads as zeroes
+    pub fn history(&self) -> impl Iterator<Item = &EpochStats> {
+        let head = self.history_head as usize % EPOCH_STATS_HISTORY_LEN;
+        (1..=EPOCH_STATS_HISTORY_LEN).map(move |age| {
+            &self.history[(head + EPOCH_STATS_HISTORY_LEN - age) % EPOCH_STATS_HISTORY_LEN]
+        })
+    }
+
+    // adds a swap of `amount_in` charged `fee` to the open bucket. saturates rather
+    // than fails, statistics must never block a trade
+    pub fn record_swap(&mut self, x_to_y: bool, amount_in: u64, fee: u64) {
+        let (volume, fees) = if x_to_y {
+            (&mut self.current.volume_x, &mut self.current.fees_x)
+        } else {
+            (&mut self.current.volume_y, &mut self.current.fees_y)
+        };
+
+        *volume = volume.saturating_add(amount_in);
+        *fees = fees.saturating_add(fee);
+    }
+
+    // moves the open bucket into the history once its epoch is over and opens an
+    // empty one for `epoch`, returning the bucket that was closed
+    pub fn rollover(&mut self, epoch: u64) -> Result<EpochStats, ProgramError> {
+        if epoch <= self.current.epoch {
+            return Err(PinocchioError::EpochNotEnded.into());
+        }
+
+        let closed = self.current;
+        let head = self.history_head as usize % EPOCH_STATS_HISTORY_LEN;
+        self.history[head] = closed;
+        self.history_head = ((head + 1) % EPOCH_STATS_HISTORY_LEN) as u64;
+
+        self.current = EpochStats {
+            epoch,
+            volume_x: 0,
+            volume_y: 0,
+            fees_x: 0,
+            fees_y: 0,
+        };
+
+        Ok(closed)
+    }
+}
+
+const _: () = {
+    use core::mem::offset_of;
+
+    assert!(size_of::<EpochStats>() == 40);
+    assert!(PoolStats::LEN == 208);
+    assert!(offset_of!(PoolStats, current) == 0);
+    assert!(offset_of!(PoolStats, history) == 40);
+    assert!(offset_of!(PoolStats, history_head) == 200);
+};